@@ -0,0 +1,81 @@
+use anchor_lang::{AccountDeserialize, Result};
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use dexera::{
+    AllowlistEntry, Batch, BatchOrder, Governance, Pool, PoolStats, Position, Profile,
+    ProfileIdEntry, Proposal, ProtocolConfig, TokenRegistryEntry, Twamm, TwammOrder, VoteRecord,
+    Voter,
+};
+
+// Deserialisers for raw account data as returned by RPC, discriminator included
+
+pub fn decode_protocol_config(mut data: &[u8]) -> Result<ProtocolConfig> {
+    ProtocolConfig::try_deserialize(&mut data)
+}
+
+pub fn decode_profile(mut data: &[u8]) -> Result<Profile> {
+    Profile::try_deserialize(&mut data)
+}
+
+pub fn decode_profile_id_entry(mut data: &[u8]) -> Result<ProfileIdEntry> {
+    ProfileIdEntry::try_deserialize(&mut data)
+}
+
+pub fn decode_pool(mut data: &[u8]) -> Result<Pool> {
+    Pool::try_deserialize(&mut data)
+}
+
+pub fn decode_allowlist_entry(mut data: &[u8]) -> Result<AllowlistEntry> {
+    AllowlistEntry::try_deserialize(&mut data)
+}
+
+pub fn decode_token_registry_entry(mut data: &[u8]) -> Result<TokenRegistryEntry> {
+    TokenRegistryEntry::try_deserialize(&mut data)
+}
+
+pub fn decode_batch(mut data: &[u8]) -> Result<Batch> {
+    Batch::try_deserialize(&mut data)
+}
+
+pub fn decode_batch_order(mut data: &[u8]) -> Result<BatchOrder> {
+    BatchOrder::try_deserialize(&mut data)
+}
+
+pub fn decode_twamm(mut data: &[u8]) -> Result<Twamm> {
+    Twamm::try_deserialize(&mut data)
+}
+
+pub fn decode_twamm_order(mut data: &[u8]) -> Result<TwammOrder> {
+    TwammOrder::try_deserialize(&mut data)
+}
+
+pub fn decode_pool_stats(mut data: &[u8]) -> Result<PoolStats> {
+    PoolStats::try_deserialize(&mut data)
+}
+
+pub fn decode_governance(mut data: &[u8]) -> Result<Governance> {
+    Governance::try_deserialize(&mut data)
+}
+
+pub fn decode_voter(mut data: &[u8]) -> Result<Voter> {
+    Voter::try_deserialize(&mut data)
+}
+
+pub fn decode_proposal(mut data: &[u8]) -> Result<Proposal> {
+    Proposal::try_deserialize(&mut data)
+}
+
+pub fn decode_vote_record(mut data: &[u8]) -> Result<VoteRecord> {
+    VoteRecord::try_deserialize(&mut data)
+}
+
+pub fn decode_position(mut data: &[u8]) -> Result<Position> {
+    Position::try_deserialize(&mut data)
+}
+
+pub fn decode_token_account(mut data: &[u8]) -> Result<TokenAccount> {
+    TokenAccount::try_deserialize(&mut data)
+}
+
+pub fn decode_mint(mut data: &[u8]) -> Result<Mint> {
+    Mint::try_deserialize(&mut data)
+}
@@ -0,0 +1,1162 @@
+use anchor_lang::{
+    prelude::Pubkey, solana_program::instruction::Instruction, system_program, InstructionData,
+    ToAccountMetas,
+};
+use anchor_spl::associated_token;
+use dexera::{
+    accounts, instruction, metadata::METADATA_PROGRAM_ID, Pool, ProposalAction, TokenStatus,
+};
+
+use crate::{
+    allowlist_address, batch_address, batch_order_address, config_address, governance_address,
+    governance_vault_address, metadata_address, position_address, profile_address,
+    profile_id_address, proposal_address, token_registry_address, treasury_address,
+    twamm_order_address, user_ata, vote_record_address, voter_address, PoolKeys, DEXERA_PROGRAM_ID,
+};
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: DEXERA_PROGRAM_ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn initialize_protocol(
+    admin: &Pubkey,
+    protocol_fee_account: &Pubkey,
+    fee: u16,
+    max_integrator_fee: u16,
+) -> Instruction {
+    build(
+        accounts::InitializeProtocol {
+            admin: *admin,
+            config: config_address().0,
+            protocol_fee_account: *protocol_fee_account,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeProtocol {
+            fee,
+            max_integrator_fee,
+        },
+    )
+}
+
+fn update_protocol_config_accounts(
+    admin: &Pubkey,
+    protocol_fee_account: &Pubkey,
+) -> accounts::UpdateProtocolConfig {
+    accounts::UpdateProtocolConfig {
+        admin: *admin,
+        config: config_address().0,
+        protocol_fee_account: *protocol_fee_account,
+        system_program: system_program::ID,
+    }
+}
+
+pub fn update_protocol_fee_account(
+    admin: &Pubkey,
+    new_protocol_fee_account: &Pubkey,
+) -> Instruction {
+    build(
+        update_protocol_config_accounts(admin, new_protocol_fee_account),
+        instruction::UpdateProtocolFeeAccount {
+            new_protocol_fee_account: *new_protocol_fee_account,
+        },
+    )
+}
+
+pub fn update_max_integrator_fee(
+    admin: &Pubkey,
+    protocol_fee_account: &Pubkey,
+    max_integrator_fee: u16,
+) -> Instruction {
+    build(
+        update_protocol_config_accounts(admin, protocol_fee_account),
+        instruction::UpdateMaxIntegratorFee { max_integrator_fee },
+    )
+}
+
+pub fn update_max_creator_fee(
+    admin: &Pubkey,
+    protocol_fee_account: &Pubkey,
+    max_creator_fee: u16,
+) -> Instruction {
+    build(
+        update_protocol_config_accounts(admin, protocol_fee_account),
+        instruction::UpdateMaxCreatorFee { max_creator_fee },
+    )
+}
+
+pub fn update_pool_creation_fee(
+    admin: &Pubkey,
+    protocol_fee_account: &Pubkey,
+    pool_creation_fee: u64,
+) -> Instruction {
+    build(
+        update_protocol_config_accounts(admin, protocol_fee_account),
+        instruction::UpdatePoolCreationFee { pool_creation_fee },
+    )
+}
+
+pub fn update_permissioned_pool_creation(
+    admin: &Pubkey,
+    protocol_fee_account: &Pubkey,
+    enabled: bool,
+) -> Instruction {
+    build(
+        update_protocol_config_accounts(admin, protocol_fee_account),
+        instruction::UpdatePermissionedPoolCreation { enabled },
+    )
+}
+
+pub fn update_compound_protocol_fees(
+    admin: &Pubkey,
+    protocol_fee_account: &Pubkey,
+    enabled: bool,
+) -> Instruction {
+    build(
+        update_protocol_config_accounts(admin, protocol_fee_account),
+        instruction::UpdateCompoundProtocolFees { enabled },
+    )
+}
+
+// Share of the protocol fee refunded to traders with a profile of tier 1 to 3, in basis points
+pub fn update_tier_fee_discounts(
+    admin: &Pubkey,
+    protocol_fee_account: &Pubkey,
+    tier_fee_discounts: [u16; 3],
+) -> Instruction {
+    build(
+        update_protocol_config_accounts(admin, protocol_fee_account),
+        instruction::UpdateTierFeeDiscounts { tier_fee_discounts },
+    )
+}
+
+// Share of the treasury's LP tokens paid per crank, up to max_crank_reward LP tokens and
+// at most once every crank_interval seconds
+pub fn update_crank_reward(
+    admin: &Pubkey,
+    protocol_fee_account: &Pubkey,
+    crank_reward_bps: u16,
+    crank_interval: u32,
+    max_crank_reward: u64,
+) -> Instruction {
+    build(
+        update_protocol_config_accounts(admin, protocol_fee_account),
+        instruction::UpdateCrankReward {
+            crank_reward_bps,
+            crank_interval,
+            max_crank_reward,
+        },
+    )
+}
+
+// key is a creator wallet or the pool address of a mint pair
+pub fn add_to_allowlist(admin: &Pubkey, key: &Pubkey) -> Instruction {
+    build(
+        accounts::AddToAllowlist {
+            admin: *admin,
+            config: config_address().0,
+            allowlist_entry: allowlist_address(key).0,
+            system_program: system_program::ID,
+        },
+        instruction::AddToAllowlist { key: *key },
+    )
+}
+
+pub fn remove_from_allowlist(admin: &Pubkey, key: &Pubkey) -> Instruction {
+    build(
+        accounts::RemoveFromAllowlist {
+            admin: *admin,
+            config: config_address().0,
+            allowlist_entry: allowlist_address(key).0,
+        },
+        instruction::RemoveFromAllowlist {},
+    )
+}
+
+pub fn register_token(admin: &Pubkey, mint: &Pubkey, status: TokenStatus) -> Instruction {
+    build(
+        accounts::RegisterToken {
+            admin: *admin,
+            config: config_address().0,
+            mint: *mint,
+            token_registry_entry: token_registry_address(mint).0,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterToken { status },
+    )
+}
+
+pub fn unregister_token(admin: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        accounts::UnregisterToken {
+            admin: *admin,
+            config: config_address().0,
+            token_registry_entry: token_registry_address(mint).0,
+        },
+        instruction::UnregisterToken {},
+    )
+}
+
+pub fn create_profile(creator: &Pubkey, profile_id: u64) -> Instruction {
+    build(
+        accounts::CreateProfile {
+            creator: *creator,
+            profile: profile_address(creator).0,
+            profile_id_entry: profile_id_address(profile_id).0,
+            system_program: system_program::ID,
+        },
+        instruction::CreateProfile { profile_id },
+    )
+}
+
+// For profiles created before ids were registered
+pub fn register_profile_id(creator: &Pubkey, profile_id: u64) -> Instruction {
+    build(
+        accounts::RegisterProfileId {
+            creator: *creator,
+            profile: profile_address(creator).0,
+            profile_id_entry: profile_id_address(profile_id).0,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterProfileId {},
+    )
+}
+
+// owner is the wallet that created the profile
+pub fn update_profile_tier(admin: &Pubkey, owner: &Pubkey, tier: u8) -> Instruction {
+    build(
+        accounts::UpdateProfileTier {
+            admin: *admin,
+            config: config_address().0,
+            profile: profile_address(owner).0,
+        },
+        instruction::UpdateProfileTier { tier },
+    )
+}
+
+fn update_pool_config_accounts(keys: &PoolKeys, admin: &Pubkey) -> accounts::UpdatePoolConfig {
+    accounts::UpdatePoolConfig {
+        admin: *admin,
+        config: config_address().0,
+        mint_x: keys.mint_x,
+        mint_y: keys.mint_y,
+        pool: keys.pool,
+    }
+}
+
+pub fn update_pool_max_price_impact(
+    keys: &PoolKeys,
+    admin: &Pubkey,
+    max_price_impact_bps: u16,
+) -> Instruction {
+    build(
+        update_pool_config_accounts(keys, admin),
+        instruction::UpdatePoolMaxPriceImpact {
+            max_price_impact_bps,
+        },
+    )
+}
+
+// max_variable_fee 0 turns the dynamic fee off
+pub fn update_pool_dynamic_fee(
+    keys: &PoolKeys,
+    admin: &Pubkey,
+    max_variable_fee: u16,
+    variable_fee_control: u16,
+    volatility_decay_period: u32,
+) -> Instruction {
+    build(
+        update_pool_config_accounts(keys, admin),
+        instruction::UpdatePoolDynamicFee {
+            max_variable_fee,
+            variable_fee_control,
+            volatility_decay_period,
+        },
+    )
+}
+
+pub fn update_pool_batch_mode(keys: &PoolKeys, admin: &Pubkey, batch_duration: u32) -> Instruction {
+    build(
+        update_pool_config_accounts(keys, admin),
+        instruction::UpdatePoolBatchMode { batch_duration },
+    )
+}
+
+pub fn update_pool_verified(keys: &PoolKeys, admin: &Pubkey, verified: bool) -> Instruction {
+    build(
+        update_pool_config_accounts(keys, admin),
+        instruction::UpdatePoolVerified { verified },
+    )
+}
+
+fn update_pool_creator_accounts(keys: &PoolKeys, creator: &Pubkey) -> accounts::UpdatePoolCreator {
+    accounts::UpdatePoolCreator {
+        creator: *creator,
+        config: config_address().0,
+        mint_x: keys.mint_x,
+        mint_y: keys.mint_y,
+        pool: keys.pool,
+    }
+}
+
+pub fn update_creator_fee(keys: &PoolKeys, creator: &Pubkey, creator_fee: u16) -> Instruction {
+    build(
+        update_pool_creator_accounts(keys, creator),
+        instruction::UpdateCreatorFee { creator_fee },
+    )
+}
+
+pub fn transfer_pool_creator(
+    keys: &PoolKeys,
+    creator: &Pubkey,
+    new_creator: &Pubkey,
+) -> Instruction {
+    build(
+        update_pool_creator_accounts(keys, creator),
+        instruction::TransferPoolCreator {
+            new_creator: *new_creator,
+        },
+    )
+}
+
+pub fn claim_creator_fee(keys: &PoolKeys, creator: &Pubkey) -> Instruction {
+    build(
+        accounts::ClaimCreatorFee {
+            creator: *creator,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            pool: keys.pool,
+            creator_fee_vault: keys.creator_fee_vault,
+            creator_mint_y_ata: keys.user_ata(creator, &keys.mint_y),
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ClaimCreatorFee {},
+    )
+}
+
+// Creator fee vault for pools created before it existed, anyone can pay for it
+pub fn init_creator_fee_vault(keys: &PoolKeys, payer: &Pubkey) -> Instruction {
+    build(
+        accounts::InitCreatorFeeVault {
+            payer: *payer,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            pool: keys.pool,
+            creator_fee_vault: keys.creator_fee_vault,
+            token_program: keys.token_program,
+            system_program: system_program::ID,
+        },
+        instruction::InitCreatorFeeVault {},
+    )
+}
+
+// allowlist_key picks the entry to present while pool creation is permissioned
+pub fn create_pool(
+    keys: &PoolKeys,
+    creator: &Pubkey,
+    protocol_fee_account: &Pubkey,
+    allowlist_key: Option<Pubkey>,
+    // Attach LP metadata, needs the Metaplex program on the cluster
+    with_metadata: bool,
+) -> Instruction {
+    let metadata = |mint: &Pubkey| with_metadata.then(|| metadata_address(mint).0);
+
+    build(
+        accounts::CreatePool {
+            creator: *creator,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            mint_lp: keys.mint_lp,
+            pool_vault_x_ata: keys.vault_x,
+            pool_vault_y_ata: keys.vault_y,
+            pool_mint_lp_ata: keys.vault_lp,
+            creator_mint_x_ata: keys.user_ata(creator, &keys.mint_x),
+            creator_mint_y_ata: keys.user_ata(creator, &keys.mint_y),
+            creator_mint_lp_ata: keys.user_ata(creator, &keys.mint_lp),
+            pool: keys.pool,
+            creator_fee_vault: keys.creator_fee_vault,
+            pool_stats: keys.pool_stats,
+            config: config_address().0,
+            protocol_fee_account: *protocol_fee_account,
+            allowlist_entry: allowlist_key.map(|key| allowlist_address(&key).0),
+            token_registry_x: token_registry_address(&keys.mint_x).0,
+            token_registry_y: token_registry_address(&keys.mint_y).0,
+            lp_metadata: metadata(&keys.mint_lp),
+            mint_x_metadata: metadata(&keys.mint_x),
+            mint_y_metadata: metadata(&keys.mint_y),
+            metadata_program: with_metadata.then_some(METADATA_PROGRAM_ID),
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::CreatePool {},
+    )
+}
+
+pub fn deposit_liquidity(
+    keys: &PoolKeys,
+    depositor: &Pubkey,
+    lp_tokens_amount: u64,
+    max_x_tokens: u64,
+    max_y_tokens: u64,
+    deadline: Option<i64>,
+) -> Instruction {
+    build(
+        accounts::DepositLiquidity {
+            depositor: *depositor,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            mint_lp: keys.mint_lp,
+            config: config_address().0,
+            pool_vault_x_ata: keys.vault_x,
+            pool_vault_y_ata: keys.vault_y,
+            depositor_mint_x_ata: keys.user_ata(depositor, &keys.mint_x),
+            depositor_mint_y_ata: keys.user_ata(depositor, &keys.mint_y),
+            depositor_mint_lp_ata: keys.user_ata(depositor, &keys.mint_lp),
+            pool: keys.pool,
+            twamm: keys.twamm_orders.then_some(keys.twamm),
+            twamm_vault_x: keys.twamm_orders.then_some(keys.twamm_vault_x),
+            twamm_vault_y: keys.twamm_orders.then_some(keys.twamm_vault_y),
+            protocol_fee_ata: keys.twamm_orders.then_some(keys.protocol_fee_ata),
+            creator_fee_vault: keys.twamm_orders.then_some(keys.creator_fee_vault),
+            pool_stats: keys.twamm_orders.then_some(keys.pool_stats),
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::DepositLiquidity {
+            lp_tokens_amount,
+            max_x_tokens,
+            max_y_tokens,
+            deadline,
+        },
+    )
+}
+
+pub fn withdraw_liquidity(
+    keys: &PoolKeys,
+    withdrawer: &Pubkey,
+    amount: u64,
+    min_x: u64,
+    min_y: u64,
+    deadline: Option<i64>,
+) -> Instruction {
+    build(
+        accounts::WithdrawLiquidity {
+            withdrawer: *withdrawer,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            mint_lp: keys.mint_lp,
+            config: config_address().0,
+            pool_vault_x_ata: keys.vault_x,
+            pool_vault_y_ata: keys.vault_y,
+            pool_mint_lp_ata: keys.vault_lp,
+            withdrawer_mint_lp_ata: keys.user_ata(withdrawer, &keys.mint_lp),
+            withdrawer_mint_x_ata: keys.user_ata(withdrawer, &keys.mint_x),
+            withdrawer_mint_y_ata: keys.user_ata(withdrawer, &keys.mint_y),
+            pool: keys.pool,
+            twamm: keys.twamm_orders.then_some(keys.twamm),
+            twamm_vault_x: keys.twamm_orders.then_some(keys.twamm_vault_x),
+            twamm_vault_y: keys.twamm_orders.then_some(keys.twamm_vault_y),
+            protocol_fee_ata: keys.twamm_orders.then_some(keys.protocol_fee_ata),
+            creator_fee_vault: keys.twamm_orders.then_some(keys.creator_fee_vault),
+            pool_stats: keys.twamm_orders.then_some(keys.pool_stats),
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::WithdrawLiquidity {
+            amount,
+            min_x,
+            min_y,
+            deadline,
+        },
+    )
+}
+
+// Optional parties that take a share of a swap
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SwapFees {
+    // Wallet that owns a dexera profile
+    pub referrer: Option<Pubkey>,
+    // Token account of the integrator, in the input mint
+    pub integrator_ata: Option<Pubkey>,
+    pub integrator_fee: u16,
+    // Set while ProtocolConfig.compound_protocol_fees is, passes the treasury accounts
+    pub compound_protocol_fees: bool,
+    // Passes the user's own profile, for the fee discount of its tier
+    pub user_profile: bool,
+}
+
+fn swap_accounts(
+    keys: &PoolKeys,
+    user: &Pubkey,
+    protocol_fee_account: &Pubkey,
+    fees: &SwapFees,
+) -> accounts::SwapTokens {
+    accounts::SwapTokens {
+        user: *user,
+        mint_x: keys.mint_x,
+        mint_y: keys.mint_y,
+        mint_lp: keys.mint_lp,
+        profile: fees.referrer.map(|referrer| profile_address(&referrer).0),
+        user_profile: fees.user_profile.then(|| profile_address(user).0),
+        config: config_address().0,
+        pool_vault_x_ata: keys.vault_x,
+        pool_vault_y_ata: keys.vault_y,
+        user_mint_x_ata: keys.user_ata(user, &keys.mint_x),
+        user_mint_y_ata: keys.user_ata(user, &keys.mint_y),
+        pool: keys.pool,
+        protocol_fee_account: *protocol_fee_account,
+        protocol_fee_ata: user_ata(protocol_fee_account, &keys.mint_y, &keys.token_program),
+        referrer: fees.referrer,
+        referrer_ata: fees
+            .referrer
+            .map(|referrer| keys.user_ata(&referrer, &keys.mint_y)),
+        integrator_ata: fees.integrator_ata,
+        creator_fee_vault: keys.creator_fee_vault,
+        pool_stats: keys.pool_stats,
+        treasury: fees.compound_protocol_fees.then(|| treasury_address().0),
+        treasury_lp_ata: fees.compound_protocol_fees.then_some(keys.treasury_lp),
+        twamm: keys.twamm_orders.then_some(keys.twamm),
+        twamm_vault_x: keys.twamm_orders.then_some(keys.twamm_vault_x),
+        twamm_vault_y: keys.twamm_orders.then_some(keys.twamm_vault_y),
+        token_program: keys.token_program,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    }
+}
+
+// Sells X for Y
+pub fn swap_exact_in(
+    keys: &PoolKeys,
+    user: &Pubkey,
+    protocol_fee_account: &Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+    fees: SwapFees,
+    deadline: Option<i64>,
+) -> Instruction {
+    build(
+        swap_accounts(keys, user, protocol_fee_account, &fees),
+        instruction::SwapExactIn {
+            max_amount_in: amount_in,
+            amount_out: min_amount_out,
+            integrator_fee: fees.integrator_fee,
+            deadline,
+        },
+    )
+}
+
+// Sells Y for X
+pub fn swap_exact_out(
+    keys: &PoolKeys,
+    user: &Pubkey,
+    protocol_fee_account: &Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+    fees: SwapFees,
+    deadline: Option<i64>,
+) -> Instruction {
+    build(
+        swap_accounts(keys, user, protocol_fee_account, &fees),
+        instruction::SwapExactOut {
+            amount_in,
+            min_amount_out,
+            integrator_fee: fees.integrator_fee,
+            deadline,
+        },
+    )
+}
+
+// `batch_id` is the pool's open batch, see Pool::open_batch_id
+pub fn submit_batch_order(
+    keys: &PoolKeys,
+    user: &Pubkey,
+    batch_id: u64,
+    is_x: bool,
+    amount_in: u64,
+) -> Instruction {
+    let batch = batch_address(&keys.pool, batch_id).0;
+    let mint_in = if is_x { keys.mint_x } else { keys.mint_y };
+
+    build(
+        accounts::SubmitBatchOrder {
+            user: *user,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            pool: keys.pool,
+            batch,
+            batch_order: batch_order_address(&batch, user).0,
+            batch_vault_x: keys.batch_vault_x,
+            batch_vault_y: keys.batch_vault_y,
+            user_ata_in: keys.user_ata(user, &mint_in),
+            token_program: keys.token_program,
+            system_program: system_program::ID,
+        },
+        instruction::SubmitBatchOrder {
+            batch_id,
+            is_x,
+            amount_in,
+        },
+    )
+}
+
+// `compound_protocol_fees` as in the config. The treasury's LP token account has to exist then.
+pub fn settle_batch(
+    keys: &PoolKeys,
+    protocol_fee_account: &Pubkey,
+    batch_id: u64,
+    compound_protocol_fees: bool,
+) -> Instruction {
+    build(
+        accounts::SettleBatch {
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            mint_lp: keys.mint_lp,
+            config: config_address().0,
+            pool: keys.pool,
+            batch: batch_address(&keys.pool, batch_id).0,
+            pool_vault_x_ata: keys.vault_x,
+            pool_vault_y_ata: keys.vault_y,
+            batch_vault_x: keys.batch_vault_x,
+            batch_vault_y: keys.batch_vault_y,
+            protocol_fee_ata: user_ata(protocol_fee_account, &keys.mint_y, &keys.token_program),
+            creator_fee_vault: keys.creator_fee_vault,
+            pool_stats: keys.pool_stats,
+            treasury: compound_protocol_fees.then(|| treasury_address().0),
+            treasury_lp_ata: compound_protocol_fees.then_some(keys.treasury_lp),
+            twamm: keys.twamm_orders.then_some(keys.twamm),
+            twamm_vault_x: keys.twamm_orders.then_some(keys.twamm_vault_x),
+            twamm_vault_y: keys.twamm_orders.then_some(keys.twamm_vault_y),
+            token_program: keys.token_program,
+        },
+        instruction::SettleBatch {},
+    )
+}
+
+// `batch_payer` comes from the batch account. The owner's ATA must exist for `mint_out`,
+// the mint bought or the mint sold if the batch was cancelled
+pub fn claim_batch_order(
+    keys: &PoolKeys,
+    owner: &Pubkey,
+    batch_id: u64,
+    batch_payer: &Pubkey,
+    mint_out: &Pubkey,
+) -> Instruction {
+    let batch = batch_address(&keys.pool, batch_id).0;
+
+    build(
+        accounts::ClaimBatchOrder {
+            owner: *owner,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            pool: keys.pool,
+            batch,
+            batch_order: batch_order_address(&batch, owner).0,
+            batch_payer: *batch_payer,
+            batch_vault_x: keys.batch_vault_x,
+            batch_vault_y: keys.batch_vault_y,
+            owner_ata_out: keys.user_ata(owner, mint_out),
+            token_program: keys.token_program,
+        },
+        instruction::ClaimBatchOrder {},
+    )
+}
+
+// Sells sell_rate tokens per slot until the end of the `intervals`-th TWAMM interval from now
+pub fn place_twamm_order(
+    keys: &PoolKeys,
+    user: &Pubkey,
+    order_id: u64,
+    is_x: bool,
+    sell_rate: u64,
+    intervals: u64,
+) -> Instruction {
+    let mint_in = if is_x { keys.mint_x } else { keys.mint_y };
+
+    build(
+        accounts::PlaceTwammOrder {
+            user: *user,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            mint_lp: keys.mint_lp,
+            config: config_address().0,
+            pool: keys.pool,
+            twamm: keys.twamm,
+            twamm_vault_x: keys.twamm_vault_x,
+            twamm_vault_y: keys.twamm_vault_y,
+            protocol_fee_ata: keys.protocol_fee_ata,
+            creator_fee_vault: keys.creator_fee_vault,
+            pool_stats: keys.pool_stats,
+            twamm_order: twamm_order_address(&keys.pool, user, order_id).0,
+            pool_vault_x_ata: keys.vault_x,
+            pool_vault_y_ata: keys.vault_y,
+            user_ata_in: keys.user_ata(user, &mint_in),
+            token_program: keys.token_program,
+            system_program: system_program::ID,
+        },
+        instruction::PlaceTwammOrder {
+            order_id,
+            is_x,
+            sell_rate,
+            intervals,
+        },
+    )
+}
+
+pub fn execute_twamm(keys: &PoolKeys) -> Instruction {
+    build(
+        accounts::ExecuteTwamm {
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            mint_lp: keys.mint_lp,
+            config: config_address().0,
+            pool: keys.pool,
+            twamm: keys.twamm,
+            twamm_vault_x: keys.twamm_vault_x,
+            twamm_vault_y: keys.twamm_vault_y,
+            protocol_fee_ata: keys.protocol_fee_ata,
+            creator_fee_vault: keys.creator_fee_vault,
+            pool_stats: keys.pool_stats,
+            pool_vault_x_ata: keys.vault_x,
+            pool_vault_y_ata: keys.vault_y,
+            token_program: keys.token_program,
+        },
+        instruction::ExecuteTwamm {},
+    )
+}
+
+// The owner's ATAs must exist for both mints
+fn claim_twamm_order_accounts(
+    keys: &PoolKeys,
+    owner: &Pubkey,
+    order_id: u64,
+) -> accounts::ClaimTwammOrder {
+    accounts::ClaimTwammOrder {
+        owner: *owner,
+        mint_x: keys.mint_x,
+        mint_y: keys.mint_y,
+        mint_lp: keys.mint_lp,
+        config: config_address().0,
+        pool: keys.pool,
+        twamm: keys.twamm,
+        twamm_vault_x: keys.twamm_vault_x,
+        twamm_vault_y: keys.twamm_vault_y,
+        protocol_fee_ata: keys.protocol_fee_ata,
+        creator_fee_vault: keys.creator_fee_vault,
+        pool_stats: keys.pool_stats,
+        twamm_order: twamm_order_address(&keys.pool, owner, order_id).0,
+        pool_vault_x_ata: keys.vault_x,
+        pool_vault_y_ata: keys.vault_y,
+        owner_ata_x: keys.user_ata(owner, &keys.mint_x),
+        owner_ata_y: keys.user_ata(owner, &keys.mint_y),
+        token_program: keys.token_program,
+    }
+}
+
+pub fn withdraw_twamm_proceeds(keys: &PoolKeys, owner: &Pubkey, order_id: u64) -> Instruction {
+    build(
+        claim_twamm_order_accounts(keys, owner, order_id),
+        instruction::WithdrawTwammProceeds {},
+    )
+}
+
+pub fn cancel_twamm_order(keys: &PoolKeys, owner: &Pubkey, order_id: u64) -> Instruction {
+    build(
+        claim_twamm_order_accounts(keys, owner, order_id),
+        instruction::CancelTwammOrder {},
+    )
+}
+
+// `rewarded` passes the treasury's and the keeper's LP token accounts to claim the reward
+pub fn crank_pool(keys: &PoolKeys, keeper: &Pubkey, rewarded: bool) -> Instruction {
+    build(
+        accounts::CrankPool {
+            keeper: *keeper,
+            config: config_address().0,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            mint_lp: keys.mint_lp,
+            pool: keys.pool,
+            pool_vault_x_ata: keys.vault_x,
+            pool_vault_y_ata: keys.vault_y,
+            pool_stats: keys.pool_stats,
+            twamm: keys.twamm_orders.then_some(keys.twamm),
+            twamm_vault_x: keys.twamm_orders.then_some(keys.twamm_vault_x),
+            twamm_vault_y: keys.twamm_orders.then_some(keys.twamm_vault_y),
+            protocol_fee_ata: keys.twamm_orders.then_some(keys.protocol_fee_ata),
+            creator_fee_vault: keys.twamm_orders.then_some(keys.creator_fee_vault),
+            treasury: rewarded.then(|| treasury_address().0),
+            treasury_lp_ata: rewarded.then_some(keys.treasury_lp),
+            keeper_lp_ata: rewarded.then(|| keys.user_ata(keeper, &keys.mint_lp)),
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::CrankPool {},
+    )
+}
+
+// `position_mint` is a fresh keypair, it signs the transaction too
+pub fn wrap_position(
+    keys: &PoolKeys,
+    owner: &Pubkey,
+    position_mint: &Pubkey,
+    lp_amount: u64,
+) -> Instruction {
+    build(
+        accounts::WrapPosition {
+            owner: *owner,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            mint_lp: keys.mint_lp,
+            pool: keys.pool,
+            position_vault: keys.position_vault,
+            owner_lp_ata: keys.user_ata(owner, &keys.mint_lp),
+            position_mint: *position_mint,
+            owner_position_ata: keys.user_ata(owner, position_mint),
+            position: position_address(position_mint).0,
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::WrapPosition { lp_amount },
+    )
+}
+
+// The NFT is taken from the owner's associated token account
+pub fn unwrap_position(keys: &PoolKeys, owner: &Pubkey, position_mint: &Pubkey) -> Instruction {
+    build(
+        accounts::UnwrapPosition {
+            owner: *owner,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            mint_lp: keys.mint_lp,
+            pool: keys.pool,
+            position_vault: keys.position_vault,
+            owner_lp_ata: keys.user_ata(owner, &keys.mint_lp),
+            position_mint: *position_mint,
+            owner_position_account: keys.user_ata(owner, position_mint),
+            position: position_address(position_mint).0,
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::UnwrapPosition {},
+    )
+}
+
+// `pool` is the pool account, its batches, TWAMM orders and positions leave vaults to
+// close. The creator's LP account is closed too when the creator signs.
+pub fn close_pool(keys: &PoolKeys, authority: &Pubkey, pool: &Pool) -> Instruction {
+    let creator = &pool.creator;
+    let batched = pool.batch_id > 0;
+    let twamm = pool.twamm_vault_x_bump != 0;
+
+    build(
+        accounts::ClosePool {
+            authority: *authority,
+            config: config_address().0,
+            creator: *creator,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            mint_lp: keys.mint_lp,
+            pool: keys.pool,
+            pool_stats: keys.pool_stats,
+            pool_vault_x_ata: keys.vault_x,
+            pool_vault_y_ata: keys.vault_y,
+            pool_mint_lp_ata: keys.vault_lp,
+            creator_fee_vault: keys.creator_fee_vault,
+            batch_vault_x: batched.then_some(keys.batch_vault_x),
+            batch_vault_y: batched.then_some(keys.batch_vault_y),
+            twamm: twamm.then_some(keys.twamm),
+            twamm_vault_x: twamm.then_some(keys.twamm_vault_x),
+            twamm_vault_y: twamm.then_some(keys.twamm_vault_y),
+            position_vault: (pool.position_vault_bump != 0).then_some(keys.position_vault),
+            creator_mint_x_ata: keys.user_ata(creator, &keys.mint_x),
+            creator_mint_y_ata: keys.user_ata(creator, &keys.mint_y),
+            creator_mint_lp_ata: (authority == creator)
+                .then(|| keys.user_ata(creator, &keys.mint_lp)),
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ClosePool {},
+    )
+}
+
+// Stats for pools created before they existed, anyone can pay for them
+pub fn init_pool_stats(keys: &PoolKeys, payer: &Pubkey) -> Instruction {
+    build(
+        accounts::InitPoolStats {
+            payer: *payer,
+            pool: keys.pool,
+            pool_stats: keys.pool_stats,
+            system_program: system_program::ID,
+        },
+        instruction::InitPoolStats {},
+    )
+}
+
+// The tokens go to the protocol fee account
+pub fn withdraw_protocol_liquidity(
+    keys: &PoolKeys,
+    admin: &Pubkey,
+    protocol_fee_account: &Pubkey,
+    lp_tokens_amount: u64,
+    min_x: u64,
+    min_y: u64,
+) -> Instruction {
+    build(
+        accounts::WithdrawProtocolLiquidity {
+            admin: *admin,
+            config: config_address().0,
+            treasury: treasury_address().0,
+            protocol_fee_account: *protocol_fee_account,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            mint_lp: keys.mint_lp,
+            pool: keys.pool,
+            pool_vault_x_ata: keys.vault_x,
+            pool_vault_y_ata: keys.vault_y,
+            treasury_lp_ata: keys.treasury_lp,
+            protocol_mint_x_ata: keys.user_ata(protocol_fee_account, &keys.mint_x),
+            protocol_mint_y_ata: keys.user_ata(protocol_fee_account, &keys.mint_y),
+            twamm: keys.twamm_orders.then_some(keys.twamm),
+            twamm_vault_x: keys.twamm_orders.then_some(keys.twamm_vault_x),
+            twamm_vault_y: keys.twamm_orders.then_some(keys.twamm_vault_y),
+            creator_fee_vault: keys.twamm_orders.then_some(keys.creator_fee_vault),
+            pool_stats: keys.twamm_orders.then_some(keys.pool_stats),
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::WithdrawProtocolLiquidity {
+            lp_tokens_amount,
+            min_x,
+            min_y,
+        },
+    )
+}
+
+pub fn redirect_protocol_liquidity(
+    keys: &PoolKeys,
+    admin: &Pubkey,
+    recipient: &Pubkey,
+    lp_tokens_amount: u64,
+) -> Instruction {
+    build(
+        accounts::RedirectProtocolLiquidity {
+            admin: *admin,
+            config: config_address().0,
+            treasury: treasury_address().0,
+            recipient: *recipient,
+            mint_lp: keys.mint_lp,
+            treasury_lp_ata: keys.treasury_lp,
+            recipient_lp_ata: keys.user_ata(recipient, &keys.mint_lp),
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::RedirectProtocolLiquidity { lp_tokens_amount },
+    )
+}
+
+pub fn init_governance(
+    admin: &Pubkey,
+    governance_mint: &Pubkey,
+    token_program: &Pubkey,
+    voting_period: i64,
+    execution_delay: i64,
+    quorum: u64,
+    proposal_threshold: u64,
+) -> Instruction {
+    build(
+        accounts::InitGovernance {
+            admin: *admin,
+            config: config_address().0,
+            governance: governance_address().0,
+            governance_mint: *governance_mint,
+            governance_vault: governance_vault_address().0,
+            token_program: *token_program,
+            system_program: system_program::ID,
+        },
+        instruction::InitGovernance {
+            voting_period,
+            execution_delay,
+            quorum,
+            proposal_threshold,
+        },
+    )
+}
+
+fn lock_governance_tokens_accounts(
+    owner: &Pubkey,
+    governance_mint: &Pubkey,
+    token_program: &Pubkey,
+) -> accounts::LockGovernanceTokens {
+    accounts::LockGovernanceTokens {
+        owner: *owner,
+        governance: governance_address().0,
+        governance_mint: *governance_mint,
+        governance_vault: governance_vault_address().0,
+        voter: voter_address(owner).0,
+        owner_ata: user_ata(owner, governance_mint, token_program),
+        token_program: *token_program,
+        system_program: system_program::ID,
+    }
+}
+
+pub fn lock_governance_tokens(
+    owner: &Pubkey,
+    governance_mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    build(
+        lock_governance_tokens_accounts(owner, governance_mint, token_program),
+        instruction::LockGovernanceTokens { amount },
+    )
+}
+
+pub fn unlock_governance_tokens(
+    owner: &Pubkey,
+    governance_mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    build(
+        lock_governance_tokens_accounts(owner, governance_mint, token_program),
+        instruction::UnlockGovernanceTokens { amount },
+    )
+}
+
+// `proposal_id` is the governance's proposal_count when the proposal is created
+pub fn create_proposal(proposer: &Pubkey, proposal_id: u64, action: ProposalAction) -> Instruction {
+    build(
+        accounts::CreateProposal {
+            proposer: *proposer,
+            governance: governance_address().0,
+            voter: voter_address(proposer).0,
+            proposal: proposal_address(proposal_id).0,
+            system_program: system_program::ID,
+        },
+        instruction::CreateProposal { action },
+    )
+}
+
+pub fn cast_vote(owner: &Pubkey, proposal_id: u64, support: bool) -> Instruction {
+    let proposal = proposal_address(proposal_id).0;
+
+    build(
+        accounts::CastVote {
+            owner: *owner,
+            proposal,
+            voter: voter_address(owner).0,
+            vote_record: vote_record_address(&proposal, owner).0,
+            system_program: system_program::ID,
+        },
+        instruction::CastVote { support },
+    )
+}
+
+// `pool` only for PoolLocked proposals
+pub fn execute_proposal(proposal_id: u64, pool: Option<Pubkey>) -> Instruction {
+    build(
+        accounts::ExecuteProposal {
+            governance: governance_address().0,
+            proposal: proposal_address(proposal_id).0,
+            config: config_address().0,
+            pool,
+        },
+        instruction::ExecuteProposal {},
+    )
+}
+
+fn quote_accounts(keys: &PoolKeys) -> accounts::Quote {
+    accounts::Quote {
+        mint_x: keys.mint_x,
+        mint_y: keys.mint_y,
+        mint_lp: keys.mint_lp,
+        config: config_address().0,
+        pool_vault_x_ata: keys.vault_x,
+        pool_vault_y_ata: keys.vault_y,
+        pool: keys.pool,
+        twamm: keys.twamm_orders.then_some(keys.twamm),
+        token_program: keys.token_program,
+    }
+}
+
+pub fn quote_swap(
+    keys: &PoolKeys,
+    is_x: bool,
+    amount_in: u64,
+    integrator_fee: u16,
+    referred: bool,
+) -> Instruction {
+    build(
+        quote_accounts(keys),
+        instruction::QuoteSwap {
+            is_x,
+            amount_in,
+            integrator_fee,
+            referred,
+        },
+    )
+}
+
+pub fn quote_deposit(keys: &PoolKeys, lp_tokens_amount: u64) -> Instruction {
+    build(
+        quote_accounts(keys),
+        instruction::QuoteDeposit { lp_tokens_amount },
+    )
+}
+
+pub fn quote_withdraw(keys: &PoolKeys, lp_tokens_amount: u64) -> Instruction {
+    build(
+        quote_accounts(keys),
+        instruction::QuoteWithdraw { lp_tokens_amount },
+    )
+}
+
+pub fn migrate_config(admin: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateConfig {
+            admin: *admin,
+            config: config_address().0,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateConfig {},
+    )
+}
+
+pub fn migrate_pool(admin: &Pubkey, pool: &Pubkey) -> Instruction {
+    build(
+        accounts::MigratePool {
+            admin: *admin,
+            config: config_address().0,
+            pool: *pool,
+            system_program: system_program::ID,
+        },
+        instruction::MigratePool {},
+    )
+}
+
+// Profiles are keyed by their creator
+pub fn migrate_profile(admin: &Pubkey, creator: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateProfile {
+            admin: *admin,
+            config: config_address().0,
+            profile: profile_address(creator).0,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateProfile {},
+    )
+}
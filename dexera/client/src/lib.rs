@@ -0,0 +1,11 @@
+// Off-chain helpers for building and simulating dexera transactions
+pub mod accounts;
+pub mod instructions;
+pub mod pda;
+pub mod quote;
+
+pub use dexera::ID as DEXERA_PROGRAM_ID;
+
+pub use accounts::*;
+pub use pda::*;
+pub use quote::*;
@@ -0,0 +1,249 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use dexera::metadata::METADATA_PROGRAM_ID;
+
+use crate::DEXERA_PROGRAM_ID;
+
+pub fn config_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"config"], &DEXERA_PROGRAM_ID)
+}
+
+// Owns the protocol's LP tokens while protocol fees are compounded
+pub fn treasury_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"treasury"], &DEXERA_PROGRAM_ID)
+}
+
+pub fn profile_address(creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"profile", creator.as_ref()], &DEXERA_PROGRAM_ID)
+}
+
+// Resolves a profile_id to the profile holding it
+pub fn profile_id_address(profile_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"profile_id", profile_id.to_le_bytes().as_ref()],
+        &DEXERA_PROGRAM_ID,
+    )
+}
+
+// Referral codes are up to 8 ASCII letters or digits, packed into a profile_id.
+// Lower case reads as upper case, so codes don't depend on how a link was typed.
+pub fn referral_code_to_profile_id(code: &str) -> Option<u64> {
+    if code.is_empty() || code.len() > 8 || !code.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return None;
+    }
+
+    let mut bytes = [0; 8];
+    bytes[..code.len()].copy_from_slice(code.to_ascii_uppercase().as_bytes());
+
+    Some(u64::from_le_bytes(bytes))
+}
+
+// None for ids that weren't made from a referral code
+pub fn profile_id_to_referral_code(profile_id: u64) -> Option<String> {
+    let bytes = profile_id.to_le_bytes();
+    let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    let code = std::str::from_utf8(&bytes[..len]).ok()?;
+
+    (referral_code_to_profile_id(code) == Some(profile_id)).then(|| code.to_string())
+}
+
+// Keyed by a creator wallet or by the pool address of an approved mint pair
+pub fn allowlist_address(key: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"allowlist", key.as_ref()], &DEXERA_PROGRAM_ID)
+}
+
+pub fn token_registry_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"token", mint.as_ref()], &DEXERA_PROGRAM_ID)
+}
+
+pub fn pool_address(mint_x: &Pubkey, mint_y: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"pool", mint_x.as_ref(), mint_y.as_ref()],
+        &DEXERA_PROGRAM_ID,
+    )
+}
+
+// Token account in Y holding the creator fees until they are claimed
+pub fn creator_fee_vault_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"creator_fee", pool.as_ref()], &DEXERA_PROGRAM_ID)
+}
+
+pub fn batch_address(pool: &Pubkey, batch_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"batch", pool.as_ref(), batch_id.to_le_bytes().as_ref()],
+        &DEXERA_PROGRAM_ID,
+    )
+}
+
+pub fn batch_order_address(batch: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"batch_order", batch.as_ref(), owner.as_ref()],
+        &DEXERA_PROGRAM_ID,
+    )
+}
+
+// Token accounts holding batch orders' tokens until they are claimed
+pub fn batch_vault_address(pool: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"batch_vault", pool.as_ref(), mint.as_ref()],
+        &DEXERA_PROGRAM_ID,
+    )
+}
+
+pub fn twamm_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"twamm", pool.as_ref()], &DEXERA_PROGRAM_ID)
+}
+
+// Token accounts holding what TWAMM orders haven't sold yet and their proceeds
+pub fn twamm_vault_address(pool: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"twamm_vault", pool.as_ref(), mint.as_ref()],
+        &DEXERA_PROGRAM_ID,
+    )
+}
+
+pub fn twamm_order_address(pool: &Pubkey, owner: &Pubkey, order_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"twamm_order",
+            pool.as_ref(),
+            owner.as_ref(),
+            order_id.to_le_bytes().as_ref(),
+        ],
+        &DEXERA_PROGRAM_ID,
+    )
+}
+
+// LP token account holding the LP tokens wrapped into position NFTs
+pub fn position_vault_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"position_vault", pool.as_ref()], &DEXERA_PROGRAM_ID)
+}
+
+pub fn position_address(position_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"position", position_mint.as_ref()], &DEXERA_PROGRAM_ID)
+}
+
+pub fn pool_stats_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"pool_stats", pool.as_ref()], &DEXERA_PROGRAM_ID)
+}
+
+pub fn governance_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"governance"], &DEXERA_PROGRAM_ID)
+}
+
+// Token account holding the governance tokens locked by voters
+pub fn governance_vault_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"governance_vault"], &DEXERA_PROGRAM_ID)
+}
+
+pub fn voter_address(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"voter", owner.as_ref()], &DEXERA_PROGRAM_ID)
+}
+
+pub fn proposal_address(proposal_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"proposal", proposal_id.to_le_bytes().as_ref()],
+        &DEXERA_PROGRAM_ID,
+    )
+}
+
+pub fn vote_record_address(proposal: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"vote", proposal.as_ref(), owner.as_ref()],
+        &DEXERA_PROGRAM_ID,
+    )
+}
+
+// Metaplex metadata account of a mint
+pub fn metadata_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"metadata", METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &METADATA_PROGRAM_ID,
+    )
+}
+
+pub fn lp_mint_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"lp", pool.as_ref()], &DEXERA_PROGRAM_ID)
+}
+
+// Vaults are the pool's associated token accounts
+pub fn vault_address(pool: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(pool, mint, token_program)
+}
+
+pub fn user_ata(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, token_program)
+}
+
+// Every address derived from a pool's mint pair
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolKeys {
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub token_program: Pubkey,
+    pub pool: Pubkey,
+    pub mint_lp: Pubkey,
+    pub vault_x: Pubkey,
+    pub vault_y: Pubkey,
+    pub vault_lp: Pubkey,
+    pub creator_fee_vault: Pubkey,
+    pub pool_stats: Pubkey,
+    // The treasury's LP token account
+    pub treasury_lp: Pubkey,
+    pub batch_vault_x: Pubkey,
+    pub batch_vault_y: Pubkey,
+    pub twamm: Pubkey,
+    pub twamm_vault_x: Pubkey,
+    pub twamm_vault_y: Pubkey,
+    pub position_vault: Pubkey,
+    // The protocol fee account's token account for Y, paid the fees of TWAMM fills
+    pub protocol_fee_ata: Pubkey,
+    // Set while the pool has TWAMM orders, instructions pricing the pool then get the
+    // TWAMM accounts to execute them. See with_twamm
+    pub twamm_orders: bool,
+}
+
+impl PoolKeys {
+    pub fn new(
+        mint_x: Pubkey,
+        mint_y: Pubkey,
+        token_program: Pubkey,
+        protocol_fee_account: &Pubkey,
+    ) -> Self {
+        let (pool, _) = pool_address(&mint_x, &mint_y);
+        let (mint_lp, _) = lp_mint_address(&pool);
+
+        Self {
+            mint_x,
+            mint_y,
+            token_program,
+            pool,
+            mint_lp,
+            vault_x: vault_address(&pool, &mint_x, &token_program),
+            vault_y: vault_address(&pool, &mint_y, &token_program),
+            vault_lp: vault_address(&pool, &mint_lp, &token_program),
+            creator_fee_vault: creator_fee_vault_address(&pool).0,
+            pool_stats: pool_stats_address(&pool).0,
+            treasury_lp: vault_address(&treasury_address().0, &mint_lp, &token_program),
+            batch_vault_x: batch_vault_address(&pool, &mint_x).0,
+            batch_vault_y: batch_vault_address(&pool, &mint_y).0,
+            twamm: twamm_address(&pool).0,
+            twamm_vault_x: twamm_vault_address(&pool, &mint_x).0,
+            twamm_vault_y: twamm_vault_address(&pool, &mint_y).0,
+            position_vault: position_vault_address(&pool).0,
+            protocol_fee_ata: user_ata(protocol_fee_account, &mint_y, &token_program),
+            twamm_orders: false,
+        }
+    }
+
+    pub fn with_twamm(self, twamm_orders: bool) -> Self {
+        Self {
+            twamm_orders,
+            ..self
+        }
+    }
+
+    pub fn user_ata(&self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        user_ata(owner, mint, &self.token_program)
+    }
+}
@@ -0,0 +1,87 @@
+use anchor_lang::Result;
+use dexera::{
+    BatchSettlement, LiquidityQuote, Pool, PoolSnapshot, ProtocolConfig, SwapQuote, Twamm,
+    TwammExecution,
+};
+
+use crate::accounts::{decode_mint, decode_pool, decode_protocol_config, decode_token_account};
+
+// Owned copy of the on-chain state a quote depends on. Quotes run through the
+// program's own PoolSnapshot maths, so they match what a swap would do.
+#[derive(Clone)]
+pub struct PoolState {
+    pub config: ProtocolConfig,
+    pub pool: Pool,
+    pub vault_x: u64,
+    pub vault_y: u64,
+    pub lp_supply: u64,
+    // Unix timestamp quotes are computed for
+    pub now: i64,
+}
+
+pub struct PoolAccountsData<'a> {
+    pub config: &'a [u8],
+    pub pool: &'a [u8],
+    pub vault_x: &'a [u8],
+    pub vault_y: &'a [u8],
+    pub mint_lp: &'a [u8],
+    // Cluster time, the dynamic fee depends on it
+    pub now: i64,
+}
+
+impl PoolState {
+    pub fn from_accounts(data: PoolAccountsData) -> Result<Self> {
+        Ok(Self {
+            config: decode_protocol_config(data.config)?,
+            pool: decode_pool(data.pool)?,
+            vault_x: decode_token_account(data.vault_x)?.amount,
+            vault_y: decode_token_account(data.vault_y)?.amount,
+            lp_supply: decode_mint(data.mint_lp)?.supply,
+            now: data.now,
+        })
+    }
+
+    pub fn snapshot(&self) -> PoolSnapshot<'_> {
+        PoolSnapshot {
+            config: &self.config,
+            pool: &self.pool,
+            vault_x: self.vault_x,
+            vault_y: self.vault_y,
+            lp_supply: self.lp_supply,
+            now: self.now,
+        }
+    }
+
+    pub fn quote_swap(
+        &self,
+        is_x: bool,
+        amount_in: u64,
+        integrator_fee: u16,
+        referred: bool,
+    ) -> Result<SwapQuote> {
+        self.snapshot()
+            .quote_swap(is_x, amount_in, integrator_fee, referred)
+    }
+
+    pub fn settle_batch(&self, x_in: u64, y_in: u64) -> Result<BatchSettlement> {
+        self.snapshot().settle_batch(x_in, y_in)
+    }
+
+    // Runs the TWAMM orders up to `slot` as the next instruction touching the pool will,
+    // so that quotes see the reserves it will trade against
+    pub fn execute_twamm(&mut self, twamm: &mut Twamm, slot: u64) -> Result<TwammExecution> {
+        let execution = twamm.execute(self.snapshot(), slot)?;
+        self.vault_x = execution.vault_x;
+        self.vault_y = execution.vault_y;
+
+        Ok(execution)
+    }
+
+    pub fn quote_deposit(&self, lp_tokens_amount: u64) -> Result<LiquidityQuote> {
+        self.snapshot().quote_deposit(lp_tokens_amount)
+    }
+
+    pub fn quote_withdraw(&self, lp_tokens_amount: u64) -> Result<LiquidityQuote> {
+        self.snapshot().quote_withdraw(lp_tokens_amount)
+    }
+}
@@ -0,0 +1,223 @@
+mod common;
+
+use common::*;
+use dexera::{errors::ErrorCode, BatchStatus};
+use dexera_client::{batch_address, decode_batch, instructions, instructions::SwapFees};
+use litesvm::types::TransactionResult;
+use solana_sdk::signature::{Keypair, Signer};
+
+const BATCH_DURATION: u32 = 10;
+
+fn submit(env: &mut TestEnv, user: &Keypair, batch_id: u64, is_x: bool, amount_in: u64) {
+    let keys = env.keys;
+    env.send(
+        &[instructions::submit_batch_order(
+            &keys,
+            &user.pubkey(),
+            batch_id,
+            is_x,
+            amount_in,
+        )],
+        &[user],
+    )
+    .expect("submit_batch_order failed");
+}
+
+#[allow(clippy::result_large_err)]
+fn settle(env: &mut TestEnv, batch_id: u64) -> TransactionResult {
+    let keys = env.keys;
+    let fee_account = env.protocol_fee_account.pubkey();
+    let keeper = env.new_user(0, 0);
+    env.send(
+        &[instructions::settle_batch(&keys, &fee_account, batch_id, false)],
+        &[&keeper],
+    )
+}
+
+fn claim(env: &mut TestEnv, owner: &Keypair, batch_id: u64, is_x_out: bool) {
+    let keys = env.keys;
+    let payer = decode_batch(&env.data(&batch_address(&keys.pool, batch_id).0))
+        .unwrap()
+        .payer;
+    let mint_out = if is_x_out { keys.mint_x } else { keys.mint_y };
+    env.send(
+        &[instructions::claim_batch_order(
+            &keys,
+            &owner.pubkey(),
+            batch_id,
+            &payer,
+            &mint_out,
+        )],
+        &[owner],
+    )
+    .expect("claim_batch_order failed");
+}
+
+#[test]
+fn swaps_are_rejected_in_batch_mode() {
+    let mut env = TestEnv::new();
+    env.enable_batch_mode(BATCH_DURATION);
+    let trader = env.new_user(10_000, 0);
+
+    let result = env.swap(&trader, true, 10_000, 1, SwapFees::default());
+
+    assert_error(result, ErrorCode::BatchModeEnabled);
+}
+
+#[test]
+fn orders_need_batch_mode() {
+    let mut env = TestEnv::new();
+    let trader = env.new_user(10_000, 0);
+    let keys = env.keys;
+
+    let result = env.send(
+        &[instructions::submit_batch_order(
+            &keys,
+            &trader.pubkey(),
+            1,
+            true,
+            10_000,
+        )],
+        &[&trader],
+    );
+
+    assert_error(result, ErrorCode::BatchModeDisabled);
+}
+
+#[test]
+fn opposite_orders_settle_at_one_price() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    env.enable_batch_mode(BATCH_DURATION);
+    let seller_x = env.new_user(40_000, 0);
+    let seller_y = env.new_user(0, 25_000);
+    let batch = batch_address(&keys.pool, 1).0;
+
+    submit(&mut env, &seller_x, 1, true, 40_000);
+    submit(&mut env, &seller_y, 1, false, 25_000);
+    assert_error(settle(&mut env, 1), ErrorCode::BatchNotEnded);
+
+    env.warp_slots(BATCH_DURATION as u64);
+    let expected = env.pool_state().settle_batch(40_000, 25_000).unwrap();
+    let (vault_x, vault_y) = (env.balance(&keys.vault_x), env.balance(&keys.vault_y));
+    let protocol_fees = env.protocol_fee_balance();
+    settle(&mut env, 1).unwrap();
+
+    let settled = decode_batch(&env.data(&batch)).unwrap();
+    assert_eq!(settled.status, BatchStatus::Settled);
+    assert_eq!(settled.x_out, expected.x_out);
+    assert_eq!(settled.y_out, expected.y_out);
+    assert_eq!(env.protocol_fee_balance(), protocol_fees + expected.fee);
+    // X was in excess, only the imbalance went through the curve
+    assert!(expected.is_x && expected.pool_amount_in < 40_000);
+    assert_eq!(
+        env.balance(&keys.vault_x),
+        vault_x + expected.pool_amount_in
+    );
+    assert_eq!(
+        env.balance(&keys.vault_y),
+        vault_y - expected.pool_amount_out
+    );
+    let stats = env.pool_stats();
+    assert_eq!(stats.swap_count, 2);
+    assert_eq!((stats.volume_x, stats.volume_y), (40_000, 25_000));
+    assert_eq!(stats.protocol_fees, expected.fee as u128);
+    assert_error(settle(&mut env, 1), ErrorCode::BatchAlreadySettled);
+
+    claim(&mut env, &seller_x, 1, false);
+    claim(&mut env, &seller_y, 1, true);
+    assert_eq!(
+        env.balance_of(&seller_x.pubkey(), &keys.mint_y),
+        expected.y_out
+    );
+    assert_eq!(
+        env.balance_of(&seller_y.pubkey(), &keys.mint_x),
+        expected.x_out
+    );
+    // The last claim closes the batch
+    assert_eq!(env.lamports(&batch), 0);
+}
+
+#[test]
+fn orders_after_the_window_open_the_next_batch() {
+    let mut env = TestEnv::new();
+    env.enable_batch_mode(BATCH_DURATION);
+    let trader = env.new_user(30_000, 0);
+    let late = env.new_user(10_000, 0);
+    submit(&mut env, &trader, 1, true, 10_000);
+
+    env.warp_slots(BATCH_DURATION as u64);
+    let keys = env.keys;
+    let result = env.send(
+        &[instructions::submit_batch_order(
+            &keys,
+            &late.pubkey(),
+            1,
+            true,
+            10_000,
+        )],
+        &[&late],
+    );
+    assert_error(result, ErrorCode::BatchClosed);
+
+    submit(&mut env, &late, 2, true, 10_000);
+    let pool = env.pool();
+    assert_eq!(pool.batch_id, 2);
+    assert_eq!(pool.batch_end_slot, env.slot() + BATCH_DURATION as u64);
+
+    // Claims wait for the settlement
+    let payer = trader.pubkey();
+    let result = env.send(
+        &[instructions::claim_batch_order(
+            &keys,
+            &trader.pubkey(),
+            1,
+            &payer,
+            &keys.mint_y,
+        )],
+        &[&trader],
+    );
+    assert_error(result, ErrorCode::BatchNotSettled);
+}
+
+#[test]
+fn batch_past_the_price_impact_cap_is_refunded() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let admin = env.admin.insecure_clone();
+    env.send(
+        &[instructions::update_pool_max_price_impact(
+            &keys,
+            &admin.pubkey(),
+            100,
+        )],
+        &[&admin],
+    )
+    .unwrap();
+    env.enable_batch_mode(BATCH_DURATION);
+    let whale = env.new_user(500_000, 0);
+    submit(&mut env, &whale, 1, true, 500_000);
+
+    env.warp_slots(BATCH_DURATION as u64);
+    settle(&mut env, 1).unwrap();
+
+    let batch = decode_batch(&env.data(&batch_address(&keys.pool, 1).0)).unwrap();
+    assert_eq!(batch.status, BatchStatus::Cancelled);
+    claim(&mut env, &whale, 1, true);
+    assert_eq!(env.balance_of(&whale.pubkey(), &keys.mint_x), 500_000);
+}
+
+#[test]
+fn clearing_price_is_the_same_for_both_sides() {
+    let mut state = synthetic_pool(1_000_000, 2_000_000, 1_000_000);
+    state.config.fee = 0;
+
+    for (x_in, y_in) in [(50_000, 20_000), (10_000, 90_000), (30_000, 60_000)] {
+        let settlement = state.settle_batch(x_in, y_in).unwrap();
+
+        // X sellers get y_out / x_in, Y sellers pay y_in / x_out, in Y per X
+        let sellers_x = settlement.y_out as f64 / x_in as f64;
+        let sellers_y = y_in as f64 / settlement.x_out as f64;
+        assert!((sellers_x / sellers_y - 1.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::*;
+use dexera::{LiquidityQuote, SwapQuote};
+use dexera_client::{
+    config_address, decode_pool, decode_profile, decode_protocol_config, instructions,
+    instructions::SwapFees, lp_mint_address, pool_address, profile_address,
+};
+use solana_sdk::signature::Signer;
+
+#[test]
+fn pda_helpers_match_program_accounts() {
+    let env = TestEnv::new();
+    let keys = env.keys;
+
+    let (pool, pool_bump) = pool_address(&keys.mint_x, &keys.mint_y);
+    let (mint_lp, mint_lp_bump) = lp_mint_address(&pool);
+    assert_eq!(pool, keys.pool);
+    assert_eq!(mint_lp, keys.mint_lp);
+
+    let state = decode_pool(&env.data(&keys.pool)).unwrap();
+    assert_eq!(state.creator, env.creator.pubkey());
+    assert_eq!(state.mint_x, keys.mint_x);
+    assert_eq!(state.mint_y, keys.mint_y);
+    assert_eq!(state.mint_lp, mint_lp);
+    assert_eq!(state.pool_bump, pool_bump);
+    assert_eq!(state.mint_lp_bump, mint_lp_bump);
+
+    assert_eq!(env.balance(&keys.vault_x), INITIAL_LIQUIDITY);
+    assert_eq!(env.balance(&keys.vault_y), INITIAL_LIQUIDITY);
+}
+
+#[test]
+fn decodes_config_and_profile() {
+    let mut env = TestEnv::new();
+
+    let (config, config_bump) = config_address();
+    let config = decode_protocol_config(&env.data(&config)).unwrap();
+    assert_eq!(config.admin, env.admin.pubkey());
+    assert_eq!(
+        config.protocol_fee_account,
+        env.protocol_fee_account.pubkey()
+    );
+    assert_eq!(config.config_bump, config_bump);
+    assert_eq!(config.fee, FEE);
+    assert_eq!(config.max_integrator_fee, MAX_INTEGRATOR_FEE);
+
+    let referrer = env.new_user(0, 0);
+    env.send(
+        &[instructions::create_profile(&referrer.pubkey(), 42)],
+        &[&referrer],
+    )
+    .unwrap();
+
+    let (profile, profile_bump) = profile_address(&referrer.pubkey());
+    let profile = decode_profile(&env.data(&profile)).unwrap();
+    assert_eq!(profile.profile_id, 42);
+    assert_eq!(profile.creator, referrer.pubkey());
+    assert_eq!(profile.profile_bump, profile_bump);
+}
+
+#[test]
+fn off_chain_swap_quote_matches_program() {
+    let env = TestEnv::new();
+    let state = env.pool_state();
+
+    for is_x in [true, false] {
+        for amount_in in [1_000, 25_000, 400_000] {
+            for (integrator_fee, referred) in [(0, false), (50, true)] {
+                let on_chain: SwapQuote = env.simulate(instructions::quote_swap(
+                    &env.keys,
+                    is_x,
+                    amount_in,
+                    integrator_fee,
+                    referred,
+                ));
+                let off_chain = state
+                    .quote_swap(is_x, amount_in, integrator_fee, referred)
+                    .unwrap();
+
+                assert_eq!(on_chain, off_chain);
+            }
+        }
+    }
+}
+
+#[test]
+fn off_chain_liquidity_quotes_match_program() {
+    let env = TestEnv::new();
+    let state = env.pool_state();
+
+    let on_chain: LiquidityQuote = env.simulate(instructions::quote_deposit(&env.keys, 2_000_000));
+    assert_eq!(on_chain, state.quote_deposit(2_000_000).unwrap());
+
+    let on_chain: LiquidityQuote = env.simulate(instructions::quote_withdraw(&env.keys, 500_000));
+    assert_eq!(on_chain, state.quote_withdraw(500_000).unwrap());
+}
+
+#[test]
+fn swap_settles_at_quoted_amounts() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let trader = env.new_user(100_000, 0);
+    let fee_account = env.protocol_fee_account.pubkey();
+
+    let quote = env.pool_state().quote_swap(true, 50_000, 0, false).unwrap();
+
+    env.send(
+        &[instructions::swap_exact_in(
+            &keys,
+            &trader.pubkey(),
+            &fee_account,
+            50_000,
+            quote.amount_out,
+            SwapFees::default(),
+            None,
+        )],
+        &[&trader],
+    )
+    .unwrap();
+
+    assert_eq!(env.balance_of(&trader.pubkey(), &keys.mint_x), 50_000);
+    assert_eq!(
+        env.balance_of(&trader.pubkey(), &keys.mint_y),
+        quote.amount_out
+    );
+    assert_eq!(env.protocol_fee_balance(), quote.protocol_fee);
+}
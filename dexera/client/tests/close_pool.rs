@@ -0,0 +1,334 @@
+mod common;
+
+use anchor_spl::token::spl_token;
+use common::*;
+use dexera::{errors::ErrorCode, metadata::METADATA_PROGRAM_ID};
+use dexera_client::{decode_mint, instructions, instructions::SwapFees, metadata_address};
+use solana_sdk::{
+    account::Account,
+    instruction::AccountMeta,
+    signature::{Keypair, Signer},
+};
+
+fn withdraw(env: &mut TestEnv, owner: &Keypair, amount: u64) {
+    let keys = env.keys;
+    env.send(
+        &[instructions::withdraw_liquidity(
+            &keys,
+            &owner.pubkey(),
+            amount,
+            0,
+            0,
+            None,
+        )],
+        &[owner],
+    )
+    .expect("withdraw_liquidity failed");
+}
+
+// Sends X straight to the pool's vault, which anyone can do
+fn donate(env: &mut TestEnv, amount: u64) {
+    let keys = env.keys;
+    let donor = env.new_user(amount, 0);
+    env.send(
+        &[spl_token::instruction::transfer(
+            &spl_token::ID,
+            &keys.user_ata(&donor.pubkey(), &keys.mint_x),
+            &keys.vault_x,
+            &donor.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap()],
+        &[&donor],
+    )
+    .expect("transfer failed");
+}
+
+fn close_pool(env: &mut TestEnv, authority: &Keypair) {
+    let keys = env.keys;
+    let pool = env.pool();
+    env.send(
+        &[instructions::close_pool(&keys, &authority.pubkey(), &pool)],
+        &[authority],
+    )
+    .expect("close_pool failed");
+}
+
+#[test]
+fn creator_closes_an_empty_pool() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+    withdraw(&mut env, &creator, INITIAL_LIQUIDITY);
+    let before = env.lamports(&creator.pubkey());
+    let rent: u64 = [
+        keys.pool,
+        keys.vault_x,
+        keys.vault_y,
+        keys.vault_lp,
+        keys.creator_fee_vault,
+        keys.user_ata(&creator.pubkey(), &keys.mint_lp),
+    ]
+    .iter()
+    .map(|account| env.lamports(account))
+    .sum();
+
+    close_pool(&mut env, &creator);
+
+    for account in [keys.pool, keys.vault_x, keys.vault_y, keys.vault_lp] {
+        assert_eq!(env.lamports(&account), 0);
+    }
+    // Less the transaction fee
+    assert!(env.lamports(&creator.pubkey()) > before + rent - 10_000);
+
+    // The pair is free for a new pool, on the LP mint left behind
+    env.create_pool(&creator, None).unwrap();
+    assert_eq!(env.lp_balance(&creator.pubkey()), INITIAL_LIQUIDITY);
+}
+
+#[test]
+fn admin_closes_a_pool_for_the_creator() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let admin = env.admin.insecure_clone();
+    let creator = env.creator.insecure_clone();
+    env.enable_creator_fee(30);
+    let trader = env.new_user(40_000, 0);
+    env.swap(&trader, true, 40_000, 1, SwapFees::default())
+        .unwrap();
+    let unclaimed = env.balance(&keys.creator_fee_vault);
+    withdraw(&mut env, &creator, INITIAL_LIQUIDITY);
+    let y_before = env.balance_of(&creator.pubkey(), &keys.mint_y);
+
+    close_pool(&mut env, &admin);
+
+    assert_eq!(env.lamports(&keys.pool), 0);
+    // Unclaimed creator fees are swept to the creator
+    assert_eq!(
+        env.balance_of(&creator.pubkey(), &keys.mint_y),
+        y_before + unclaimed
+    );
+    // Only the creator closes their own LP account
+    assert!(env.lamports(&keys.user_ata(&creator.pubkey(), &keys.mint_lp)) > 0);
+}
+
+#[test]
+fn close_pool_with_liquidity_left() {
+    let mut env = TestEnv::new();
+    let creator = env.creator.insecure_clone();
+    withdraw(&mut env, &creator, INITIAL_LIQUIDITY - 1);
+    let keys = env.keys;
+
+    let result = env.send(
+        &[instructions::close_pool(
+            &keys,
+            &creator.pubkey(),
+            &env.pool(),
+        )],
+        &[&creator],
+    );
+
+    assert_error(result, ErrorCode::PoolNotEmpty);
+}
+
+#[test]
+fn lp_held_by_the_pool_keeps_it_open() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+    env.send(
+        &[spl_token::instruction::transfer(
+            &spl_token::ID,
+            &keys.user_ata(&creator.pubkey(), &keys.mint_lp),
+            &keys.vault_lp,
+            &creator.pubkey(),
+            &[],
+            1_000,
+        )
+        .unwrap()],
+        &[&creator],
+    )
+    .unwrap();
+    withdraw(&mut env, &creator, INITIAL_LIQUIDITY - 1_000);
+
+    let result = env.send(
+        &[instructions::close_pool(
+            &keys,
+            &creator.pubkey(),
+            &env.pool(),
+        )],
+        &[&creator],
+    );
+
+    assert_error(result, ErrorCode::PoolNotEmpty);
+    assert_eq!(decode_mint(&env.data(&keys.mint_lp)).unwrap().supply, 1_000);
+}
+
+#[test]
+fn tokens_sent_to_an_empty_pool_go_to_the_creator() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+    withdraw(&mut env, &creator, INITIAL_LIQUIDITY);
+    donate(&mut env, 1_000);
+    let x_before = env.balance_of(&creator.pubkey(), &keys.mint_x);
+
+    close_pool(&mut env, &creator);
+
+    assert_eq!(env.lamports(&keys.vault_x), 0);
+    assert_eq!(
+        env.balance_of(&creator.pubkey(), &keys.mint_x),
+        x_before + 1_000
+    );
+}
+
+#[test]
+fn tokens_sent_to_an_empty_pool_dont_block_deposits() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+    withdraw(&mut env, &creator, INITIAL_LIQUIDITY);
+    donate(&mut env, 1_000);
+
+    env.send(
+        &[instructions::deposit_liquidity(
+            &keys,
+            &creator.pubkey(),
+            INITIAL_LIQUIDITY,
+            INITIAL_LIQUIDITY,
+            INITIAL_LIQUIDITY,
+            None,
+        )],
+        &[&creator],
+    )
+    .expect("deposit_liquidity failed");
+
+    assert_eq!(env.lp_balance(&creator.pubkey()), INITIAL_LIQUIDITY);
+    assert_eq!(env.balance(&keys.vault_x), INITIAL_LIQUIDITY + 1_000);
+    assert_eq!(env.balance(&keys.vault_y), INITIAL_LIQUIDITY);
+}
+
+#[test]
+fn only_creator_or_admin_closes() {
+    let mut env = TestEnv::new();
+    let creator = env.creator.insecure_clone();
+    withdraw(&mut env, &creator, INITIAL_LIQUIDITY);
+    let intruder = env.new_user(0, 0);
+    let keys = env.keys;
+
+    let result = env.send(
+        &[instructions::close_pool(
+            &keys,
+            &intruder.pubkey(),
+            &env.pool(),
+        )],
+        &[&intruder],
+    );
+
+    assert_error(result, ErrorCode::Unauthorized);
+}
+
+#[test]
+fn admin_cant_close_the_creators_lp_account() {
+    let mut env = TestEnv::new();
+    let creator = env.creator.insecure_clone();
+    let admin = env.admin.insecure_clone();
+    withdraw(&mut env, &creator, INITIAL_LIQUIDITY);
+    let keys = env.keys;
+    // Built for the creator, which passes their LP account, then signed by the admin
+    let mut ix = instructions::close_pool(&keys, &creator.pubkey(), &env.pool());
+    ix.accounts[0] = AccountMeta::new(admin.pubkey(), true);
+
+    let result = env.send(&[ix], &[&admin]);
+
+    assert_error(result, ErrorCode::Unauthorized);
+}
+
+#[test]
+fn close_pool_waits_for_batch_claims() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+    env.enable_batch_mode(10);
+    let trader = env.new_user(10_000, 0);
+    env.send(
+        &[instructions::submit_batch_order(
+            &keys,
+            &trader.pubkey(),
+            1,
+            true,
+            10_000,
+        )],
+        &[&trader],
+    )
+    .unwrap();
+    env.warp_slots(10);
+    let fee_account = env.protocol_fee_account.pubkey();
+    env.send(
+        &[instructions::settle_batch(&keys, &fee_account, 1, false)],
+        &[&trader],
+    )
+    .unwrap();
+    withdraw(&mut env, &creator, INITIAL_LIQUIDITY);
+
+    let result = env.send(
+        &[instructions::close_pool(
+            &keys,
+            &creator.pubkey(),
+            &env.pool(),
+        )],
+        &[&creator],
+    );
+    assert_error(result, ErrorCode::BatchesOpen);
+
+    env.send(
+        &[instructions::claim_batch_order(
+            &keys,
+            &trader.pubkey(),
+            1,
+            &trader.pubkey(),
+            &keys.mint_y,
+        )],
+        &[&trader],
+    )
+    .unwrap();
+    close_pool(&mut env, &creator);
+
+    assert_eq!(env.lamports(&keys.batch_vault_x), 0);
+    assert_eq!(env.lamports(&keys.batch_vault_y), 0);
+}
+
+#[test]
+fn pools_are_recreated_on_the_old_lp_metadata() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+    withdraw(&mut env, &creator, INITIAL_LIQUIDITY);
+    close_pool(&mut env, &creator);
+    // What the Metaplex program left of the first pool's LP metadata
+    let lp_metadata = metadata_address(&keys.mint_lp).0;
+    let metadata = Account {
+        lamports: env.svm.minimum_balance_for_rent_exemption(679),
+        data: vec![4; 679],
+        owner: METADATA_PROGRAM_ID,
+        executable: false,
+        rent_epoch: 0,
+    };
+    env.svm.set_account(lp_metadata, metadata.clone()).unwrap();
+
+    let fee_account = env.protocol_fee_account.pubkey();
+    env.send(
+        &[instructions::create_pool(
+            &keys,
+            &creator.pubkey(),
+            &fee_account,
+            None,
+            true,
+        )],
+        &[&creator],
+    )
+    .expect("create_pool failed");
+
+    assert_eq!(env.data(&lp_metadata), metadata.data);
+}
@@ -0,0 +1,503 @@
+// Shared LiteSVM harness. Needs the program built first: `anchor build`.
+#![allow(dead_code, clippy::result_large_err)]
+
+use anchor_lang::{prelude::Pubkey, AccountSerialize, AnchorDeserialize};
+use anchor_spl::{
+    associated_token::spl_associated_token_account::instruction::create_associated_token_account_idempotent,
+    token::spl_token,
+};
+use dexera::{Pool, PoolStats, ProtocolConfig};
+use dexera_client::{
+    decode_pool, decode_pool_stats, instructions, instructions::SwapFees, PoolAccountsData,
+    PoolKeys, PoolState, DEXERA_PROGRAM_ID,
+};
+use litesvm::{types::TransactionResult, LiteSVM};
+use solana_sdk::{
+    clock::Clock,
+    instruction::{Instruction, InstructionError},
+    program_pack::Pack,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use solana_system_interface::instruction as system_instruction;
+
+pub const PROGRAM_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../target/deploy/dexera.so");
+
+pub const FEE: u16 = 100;
+pub const MAX_INTEGRATOR_FEE: u16 = 100;
+pub const DECIMALS: u8 = 6;
+pub const INITIAL_LIQUIDITY: u64 = 1_000_000;
+// Lifetime of a freshly created profile
+pub const ONE_MONTH: i64 = 30 * 24 * 60 * 60;
+
+pub struct TestEnv {
+    pub svm: LiteSVM,
+    pub admin: Keypair,
+    pub protocol_fee_account: Keypair,
+    pub mint_authority: Keypair,
+    pub creator: Keypair,
+    pub keys: PoolKeys,
+}
+
+impl TestEnv {
+    // Protocol initialised and an X/Y pool seeded with the initial liquidity
+    pub fn new() -> Self {
+        let mut env = Self::with_protocol();
+        let creator = env.new_user(1_000_000_000, 1_000_000_000);
+        env.create_pool(&creator, None).expect("create_pool failed");
+        env.creator = creator;
+        env
+    }
+
+    // Protocol initialised, mints created, no pool yet
+    pub fn with_protocol() -> Self {
+        let mut env = Self::without_protocol();
+
+        let admin = env.admin.insecure_clone();
+        let fee_account = env.protocol_fee_account.pubkey();
+        env.send(
+            &[instructions::initialize_protocol(
+                &admin.pubkey(),
+                &fee_account,
+                FEE,
+                MAX_INTEGRATOR_FEE,
+            )],
+            &[&admin],
+        )
+        .expect("initialize_protocol failed");
+
+        // Protocol fees are paid in Y
+        let mint_y = env.keys.mint_y;
+        env.create_ata(&fee_account, &mint_y);
+
+        env
+    }
+
+    // Program deployed and mints created, config not initialised
+    pub fn without_protocol() -> Self {
+        let mut svm = LiteSVM::new();
+        svm.add_program_from_file(DEXERA_PROGRAM_ID, PROGRAM_PATH)
+            .expect("dexera.so not found, run `anchor build` first");
+
+        let admin = Keypair::new();
+        let protocol_fee_account = Keypair::new();
+        let mint_authority = Keypair::new();
+        for account in [&admin, &mint_authority] {
+            svm.airdrop(&account.pubkey(), 100_000_000_000).unwrap();
+        }
+
+        let mut env = Self {
+            svm,
+            admin,
+            protocol_fee_account,
+            mint_authority,
+            creator: Keypair::new(),
+            keys: PoolKeys::new(
+                Pubkey::default(),
+                Pubkey::default(),
+                spl_token::ID,
+                &Pubkey::default(),
+            ),
+        };
+
+        let mint_x = env.create_mint();
+        let mint_y = env.create_mint();
+        let fee_account = env.protocol_fee_account.pubkey();
+        env.keys = PoolKeys::new(mint_x, mint_y, spl_token::ID, &fee_account);
+
+        env
+    }
+
+    pub fn send(&mut self, ixs: &[Instruction], signers: &[&Keypair]) -> TransactionResult {
+        let tx = Transaction::new_signed_with_payer(
+            ixs,
+            Some(&signers[0].pubkey()),
+            signers,
+            self.svm.latest_blockhash(),
+        );
+        let result = self.svm.send_transaction(tx);
+        // Lets identical transactions be sent twice in one test
+        self.svm.expire_blockhash();
+        result
+    }
+
+    // Runs a read-only instruction and decodes its return data
+    pub fn simulate<T: AnchorDeserialize>(&self, ix: Instruction) -> T {
+        let payer = &self.admin;
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer.pubkey()),
+            &[payer],
+            self.svm.latest_blockhash(),
+        );
+        let info = self
+            .svm
+            .simulate_transaction(tx)
+            .unwrap_or_else(|failed| panic!("simulation failed: {:?}", failed.err));
+
+        T::try_from_slice(&info.meta.return_data.data).expect("invalid return data")
+    }
+
+    pub fn create_mint(&mut self) -> Pubkey {
+        self.create_mint_with(None)
+    }
+
+    pub fn create_mint_with(&mut self, freeze_authority: Option<&Pubkey>) -> Pubkey {
+        self.create_mint_with_decimals(freeze_authority, DECIMALS)
+    }
+
+    pub fn create_mint_with_decimals(
+        &mut self,
+        freeze_authority: Option<&Pubkey>,
+        decimals: u8,
+    ) -> Pubkey {
+        let mint = Keypair::new();
+        let authority = self.mint_authority.insecure_clone();
+        let rent = self
+            .svm
+            .minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN);
+
+        self.send(
+            &[
+                system_instruction::create_account(
+                    &authority.pubkey(),
+                    &mint.pubkey(),
+                    rent,
+                    spl_token::state::Mint::LEN as u64,
+                    &spl_token::ID,
+                ),
+                spl_token::instruction::initialize_mint2(
+                    &spl_token::ID,
+                    &mint.pubkey(),
+                    &authority.pubkey(),
+                    freeze_authority,
+                    decimals,
+                )
+                .unwrap(),
+            ],
+            &[&authority, &mint],
+        )
+        .expect("create mint failed");
+
+        mint.pubkey()
+    }
+
+    pub fn create_ata(&mut self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        let payer = self.mint_authority.insecure_clone();
+        self.send(
+            &[create_associated_token_account_idempotent(
+                &payer.pubkey(),
+                owner,
+                mint,
+                &spl_token::ID,
+            )],
+            &[&payer],
+        )
+        .expect("create ata failed");
+
+        self.keys.user_ata(owner, mint)
+    }
+
+    pub fn mint_to(&mut self, mint: &Pubkey, owner: &Pubkey, amount: u64) {
+        let ata = self.create_ata(owner, mint);
+        let authority = self.mint_authority.insecure_clone();
+        self.send(
+            &[spl_token::instruction::mint_to(
+                &spl_token::ID,
+                mint,
+                &ata,
+                &authority.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap()],
+            &[&authority],
+        )
+        .expect("mint_to failed");
+    }
+
+    // Funded wallet holding X and Y
+    pub fn new_user(&mut self, x: u64, y: u64) -> Keypair {
+        let user = Keypair::new();
+        self.svm.airdrop(&user.pubkey(), 10_000_000_000).unwrap();
+
+        let (mint_x, mint_y) = (self.keys.mint_x, self.keys.mint_y);
+        self.mint_to(&mint_x, &user.pubkey(), x);
+        self.mint_to(&mint_y, &user.pubkey(), y);
+
+        user
+    }
+
+    pub fn create_pool(
+        &mut self,
+        creator: &Keypair,
+        allowlist_key: Option<Pubkey>,
+    ) -> TransactionResult {
+        let ix = instructions::create_pool(
+            &self.keys,
+            &creator.pubkey(),
+            &self.protocol_fee_account.pubkey(),
+            allowlist_key,
+            false,
+        );
+        self.send(&[ix], &[creator])
+    }
+
+    pub fn lamports(&self, address: &Pubkey) -> u64 {
+        self.svm
+            .get_account(address)
+            .map(|account| account.lamports)
+            .unwrap_or(0)
+    }
+
+    pub fn balance(&self, ata: &Pubkey) -> u64 {
+        self.svm
+            .get_account(ata)
+            .map(|account| {
+                spl_token::state::Account::unpack(&account.data)
+                    .unwrap()
+                    .amount
+            })
+            .unwrap_or(0)
+    }
+
+    pub fn balance_of(&self, owner: &Pubkey, mint: &Pubkey) -> u64 {
+        self.balance(&self.keys.user_ata(owner, mint))
+    }
+
+    pub fn protocol_fee_balance(&self) -> u64 {
+        self.balance_of(&self.protocol_fee_account.pubkey(), &self.keys.mint_y)
+    }
+
+    pub fn data(&self, address: &Pubkey) -> Vec<u8> {
+        self.svm
+            .get_account(address)
+            .unwrap_or_else(|| panic!("account {} missing", address))
+            .data
+    }
+
+    pub fn lp_balance(&self, owner: &Pubkey) -> u64 {
+        self.balance_of(owner, &self.keys.mint_lp)
+    }
+
+    pub fn pool(&self) -> Pool {
+        decode_pool(&self.data(&self.keys.pool)).unwrap()
+    }
+
+    pub fn pool_stats(&self) -> PoolStats {
+        decode_pool_stats(&self.data(&self.keys.pool_stats)).unwrap()
+    }
+
+    // Rewrites the pool account in place, for states no instruction can reach yet
+    pub fn set_pool(&mut self, update: impl FnOnce(&mut Pool)) {
+        let mut pool = self.pool();
+        update(&mut pool);
+
+        let mut account = self.svm.get_account(&self.keys.pool).unwrap();
+        let mut data = Vec::with_capacity(account.data.len());
+        pool.try_serialize(&mut data).unwrap();
+        account.data[..data.len()].copy_from_slice(&data);
+        self.svm.set_account(self.keys.pool, account).unwrap();
+    }
+
+    pub fn now(&self) -> i64 {
+        self.svm.get_sysvar::<Clock>().unix_timestamp
+    }
+
+    pub fn warp_by(&mut self, seconds: i64) {
+        let mut clock = self.svm.get_sysvar::<Clock>();
+        clock.unix_timestamp += seconds;
+        self.svm.set_sysvar(&clock);
+    }
+
+    pub fn slot(&self) -> u64 {
+        self.svm.get_sysvar::<Clock>().slot
+    }
+
+    pub fn warp_slots(&mut self, slots: u64) {
+        let slot = self.slot() + slots;
+        self.svm.warp_to_slot(slot);
+    }
+
+    pub fn create_profile(&mut self, owner: &Keypair, profile_id: u64) {
+        self.send(
+            &[instructions::create_profile(&owner.pubkey(), profile_id)],
+            &[owner],
+        )
+        .expect("create_profile failed");
+    }
+
+    // Raises the protocol cap to creator_fee and has the pool creator charge it
+    pub fn enable_creator_fee(&mut self, creator_fee: u16) {
+        let admin = self.admin.insecure_clone();
+        let creator = self.creator.insecure_clone();
+        self.send(
+            &[instructions::update_max_creator_fee(
+                &admin.pubkey(),
+                &self.protocol_fee_account.pubkey(),
+                creator_fee,
+            )],
+            &[&admin],
+        )
+        .expect("update_max_creator_fee failed");
+        self.send(
+            &[instructions::update_creator_fee(
+                &self.keys,
+                &creator.pubkey(),
+                creator_fee,
+            )],
+            &[&creator],
+        )
+        .expect("update_creator_fee failed");
+    }
+
+    pub fn enable_dynamic_fee(
+        &mut self,
+        max_variable_fee: u16,
+        variable_fee_control: u16,
+        volatility_decay_period: u32,
+    ) {
+        let admin = self.admin.insecure_clone();
+        self.send(
+            &[instructions::update_pool_dynamic_fee(
+                &self.keys,
+                &admin.pubkey(),
+                max_variable_fee,
+                variable_fee_control,
+                volatility_decay_period,
+            )],
+            &[&admin],
+        )
+        .expect("update_pool_dynamic_fee failed");
+    }
+
+    pub fn enable_batch_mode(&mut self, batch_duration: u32) {
+        let admin = self.admin.insecure_clone();
+        self.send(
+            &[instructions::update_pool_batch_mode(
+                &self.keys,
+                &admin.pubkey(),
+                batch_duration,
+            )],
+            &[&admin],
+        )
+        .expect("update_pool_batch_mode failed");
+    }
+
+    // is_x sells X for Y through swap_exact_in, otherwise Y for X through swap_exact_out
+    pub fn swap(
+        &mut self,
+        user: &Keypair,
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        fees: SwapFees,
+    ) -> TransactionResult {
+        let build = if is_x {
+            instructions::swap_exact_in
+        } else {
+            instructions::swap_exact_out
+        };
+        let ix = build(
+            &self.keys,
+            &user.pubkey(),
+            &self.protocol_fee_account.pubkey(),
+            amount_in,
+            min_amount_out,
+            fees,
+            None,
+        );
+
+        self.send(&[ix], &[user])
+    }
+
+    pub fn pool_state(&self) -> PoolState {
+        let config = self.data(&dexera_client::config_address().0);
+        let pool = self.data(&self.keys.pool);
+        let vault_x = self.data(&self.keys.vault_x);
+        let vault_y = self.data(&self.keys.vault_y);
+        let mint_lp = self.data(&self.keys.mint_lp);
+
+        PoolState::from_accounts(PoolAccountsData {
+            config: &config,
+            pool: &pool,
+            vault_x: &vault_x,
+            vault_y: &vault_y,
+            mint_lp: &mint_lp,
+            now: self.now(),
+        })
+        .unwrap()
+    }
+}
+
+// Pool state with the test config, for quote maths that needs no SVM
+pub fn synthetic_pool(vault_x: u64, vault_y: u64, lp_supply: u64) -> PoolState {
+    PoolState {
+        config: ProtocolConfig {
+            admin: Pubkey::default(),
+            protocol_fee_account: Pubkey::default(),
+            config_bump: 0,
+            fee: FEE,
+            max_integrator_fee: MAX_INTEGRATOR_FEE,
+            pool_creation_fee: 0,
+            permissioned_pool_creation: false,
+            max_creator_fee: 0,
+            version: ProtocolConfig::VERSION,
+            compound_protocol_fees: false,
+            treasury_bump: 0,
+            tier_fee_discounts: [0; 3],
+            crank_reward_bps: 0,
+            crank_interval: 0,
+            max_crank_reward: 0,
+            referrer_fee_share: ProtocolConfig::DEFAULT_REFERRER_FEE_SHARE,
+            reserved: [0; 40],
+        },
+        pool: Pool {
+            creator: Pubkey::default(),
+            mint_x: Pubkey::default(),
+            mint_y: Pubkey::default(),
+            mint_lp: Pubkey::default(),
+            pool_bump: 0,
+            mint_lp_bump: 0,
+            locked: false,
+            max_price_impact_bps: 10_000,
+            verified: false,
+            creator_fee: 0,
+            creator_fee_vault_bump: 0,
+            max_variable_fee: 0,
+            variable_fee_control: 0,
+            volatility_decay_period: 0,
+            volatility_accumulator: 0,
+            last_volatility_update: 0,
+            version: Pool::VERSION,
+            batch_duration: 0,
+            batch_id: 0,
+            batch_end_slot: 0,
+            batch_vault_x_bump: 0,
+            batch_vault_y_bump: 0,
+            open_batches: 0,
+            lp_decimals: DECIMALS,
+            twamm_vault_x_bump: 0,
+            twamm_vault_y_bump: 0,
+            twamm_orders: 0,
+            last_crank_reward_at: 0,
+            position_vault_bump: 0,
+            reserved: [0; 22],
+        },
+        vault_x,
+        vault_y,
+        lp_supply,
+        now: 0,
+    }
+}
+
+// Takes both dexera and anchor error codes
+pub fn assert_error(result: TransactionResult, expected: impl Into<u32>) {
+    let failed = result.expect_err("transaction should have failed");
+    assert_eq!(
+        failed.err,
+        TransactionError::InstructionError(0, InstructionError::Custom(expected.into())),
+        "logs: {:#?}",
+        failed.meta.logs
+    );
+}
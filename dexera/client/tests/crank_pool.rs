@@ -0,0 +1,290 @@
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use common::*;
+use dexera::{errors::ErrorCode, SECONDS_PER_DAY, TWAMM_INTERVAL};
+use dexera_client::{decode_twamm, instructions, instructions::SwapFees, treasury_address};
+use solana_sdk::signature::{Keypair, Signer};
+
+const CRANK_REWARD_BPS: u16 = 10;
+const CRANK_INTERVAL: u32 = 60 * 60;
+const MAX_CRANK_REWARD: u64 = 1_000_000;
+const DECAY_PERIOD: u32 = 600;
+
+const COMPOUND: SwapFees = SwapFees {
+    referrer: None,
+    integrator_ata: None,
+    integrator_fee: 0,
+    compound_protocol_fees: true,
+    user_profile: false,
+};
+
+fn set_crank_reward(env: &mut TestEnv, crank_reward_bps: u16, crank_interval: u32) {
+    let admin = env.admin.insecure_clone();
+    env.send(
+        &[instructions::update_crank_reward(
+            &admin.pubkey(),
+            &env.protocol_fee_account.pubkey(),
+            crank_reward_bps,
+            crank_interval,
+            MAX_CRANK_REWARD,
+        )],
+        &[&admin],
+    )
+    .expect("update_crank_reward failed");
+}
+
+// Compounds a swap's protocol fee so the treasury holds LP tokens to pay rewards with,
+// then lets a crank interval pass since the pool's creation. The swap's volatility has
+// faded out by then, which leaves the next crank work to do.
+fn fund_treasury(env: &mut TestEnv) {
+    env.enable_dynamic_fee(100, 10_000, DECAY_PERIOD);
+    let admin = env.admin.insecure_clone();
+    env.send(
+        &[instructions::update_compound_protocol_fees(
+            &admin.pubkey(),
+            &env.protocol_fee_account.pubkey(),
+            true,
+        )],
+        &[&admin],
+    )
+    .expect("update_compound_protocol_fees failed");
+    let trader = env.new_user(1_000_000, 0);
+    env.swap(&trader, true, 1_000_000, 1, COMPOUND).unwrap();
+    env.warp_by(CRANK_INTERVAL as i64);
+}
+
+// Gives the next crank volatility to clear
+fn stir(env: &mut TestEnv) {
+    let trader = env.new_user(10_000, 0);
+    env.swap(&trader, true, 10_000, 1, COMPOUND).unwrap();
+    env.warp_by(DECAY_PERIOD as i64);
+}
+
+fn crank(env: &mut TestEnv, keeper: &Keypair, rewarded: bool) {
+    let keys = env.keys;
+    env.send(
+        &[instructions::crank_pool(&keys, &keeper.pubkey(), rewarded)],
+        &[keeper],
+    )
+    .expect("crank_pool failed");
+}
+
+#[test]
+fn rewards_wait_for_the_crank_interval() {
+    let mut state = synthetic_pool(1_000, 1_000, 1_000);
+    state.config.crank_reward_bps = CRANK_REWARD_BPS;
+    state.config.crank_interval = CRANK_INTERVAL;
+    state.config.max_crank_reward = MAX_CRANK_REWARD;
+    state.pool.last_crank_reward_at = 1_000;
+
+    let early = state
+        .config
+        .crank_reward(&state.pool, 1_000 + 3_599, 100_000);
+    let due = state
+        .config
+        .crank_reward(&state.pool, 1_000 + 3_600, 100_000);
+
+    assert_eq!(early.unwrap(), 0);
+    assert_eq!(due.unwrap(), 100);
+}
+
+#[test]
+fn rewards_are_capped() {
+    let mut state = synthetic_pool(1_000, 1_000, 1_000);
+    state.config.crank_reward_bps = CRANK_REWARD_BPS;
+    state.config.crank_interval = CRANK_INTERVAL;
+    state.config.max_crank_reward = 50;
+
+    let reward = state.config.crank_reward(&state.pool, 3_600, 100_000);
+
+    assert_eq!(reward.unwrap(), 50);
+}
+
+#[test]
+fn no_rewards_while_they_are_off() {
+    let state = synthetic_pool(1_000, 1_000, 1_000);
+
+    assert_eq!(
+        state.config.crank_reward(&state.pool, 0, 100_000).unwrap(),
+        0
+    );
+}
+
+#[test]
+fn cranks_decay_the_volatility() {
+    let mut env = TestEnv::new();
+    env.enable_dynamic_fee(100, 10_000, 600);
+    let trader = env.new_user(100_000, 0);
+    env.swap(&trader, true, 100_000, 1, SwapFees::default())
+        .unwrap();
+    let swapped = env.pool();
+    assert!(swapped.volatility_accumulator > 0);
+
+    // The decay stays anchored at the swap, half way through it is at half
+    env.warp_by(300);
+    let keeper = env.new_user(0, 0);
+    crank(&mut env, &keeper, false);
+    let pool = env.pool();
+    assert_eq!(
+        pool.volatility_at(env.now()),
+        swapped.volatility_accumulator / 2
+    );
+    assert_eq!(pool.last_volatility_update, swapped.last_volatility_update);
+
+    env.warp_by(300);
+    crank(&mut env, &keeper, false);
+    assert_eq!(env.pool().volatility_accumulator, 0);
+}
+
+#[test]
+fn cranks_roll_the_daily_stats_over() {
+    let mut env = TestEnv::new();
+    let trader = env.new_user(100_000, 0);
+    env.swap(&trader, true, 100_000, 1, SwapFees::default())
+        .unwrap();
+    let day_volume_x = env.pool_stats().day_volume_x;
+
+    env.warp_by(SECONDS_PER_DAY);
+    let keeper = env.new_user(0, 0);
+    crank(&mut env, &keeper, false);
+
+    let stats = env.pool_stats();
+    assert_eq!(stats.day, env.now().div_euclid(SECONDS_PER_DAY));
+    assert_eq!(stats.day_volume_x, 0);
+    assert_eq!(stats.previous_day_volume_x, day_volume_x);
+}
+
+#[test]
+fn cranks_execute_the_twamm_orders() {
+    let mut env = TestEnv::new();
+    let seller = env.new_user(100_000, 0);
+    let keys = env.keys;
+    env.send(
+        &[instructions::place_twamm_order(
+            &keys,
+            &seller.pubkey(),
+            1,
+            true,
+            10,
+            2,
+        )],
+        &[&seller],
+    )
+    .expect("place_twamm_order failed");
+    env.keys = keys.with_twamm(true);
+
+    env.warp_slots(TWAMM_INTERVAL);
+    let keeper = env.new_user(0, 0);
+    crank(&mut env, &keeper, false);
+
+    let twamm = decode_twamm(&env.data(&env.keys.twamm)).unwrap();
+    assert_eq!(twamm.last_executed_slot, env.slot());
+}
+
+#[test]
+fn keepers_are_paid_from_the_treasury() {
+    let mut env = TestEnv::new();
+    fund_treasury(&mut env);
+    set_crank_reward(&mut env, CRANK_REWARD_BPS, CRANK_INTERVAL);
+    let treasury = treasury_address().0;
+    let treasury_lp = env.lp_balance(&treasury);
+    let keeper = env.new_user(0, 0);
+
+    crank(&mut env, &keeper, true);
+
+    let reward = (treasury_lp * CRANK_REWARD_BPS as u64 / 10_000).min(MAX_CRANK_REWARD);
+    assert!(reward > 0);
+    assert_eq!(env.lp_balance(&keeper.pubkey()), reward);
+    assert_eq!(env.lp_balance(&treasury), treasury_lp - reward);
+    assert_eq!(env.pool().last_crank_reward_at, env.now());
+}
+
+#[test]
+fn keepers_are_paid_once_per_interval() {
+    let mut env = TestEnv::new();
+    fund_treasury(&mut env);
+    set_crank_reward(&mut env, CRANK_REWARD_BPS, CRANK_INTERVAL);
+    let keeper = env.new_user(0, 0);
+    crank(&mut env, &keeper, true);
+    let first = env.lp_balance(&keeper.pubkey());
+
+    stir(&mut env);
+    crank(&mut env, &keeper, true);
+    assert_eq!(env.lp_balance(&keeper.pubkey()), first);
+
+    env.warp_by(CRANK_INTERVAL as i64);
+    stir(&mut env);
+    crank(&mut env, &keeper, true);
+    assert!(env.lp_balance(&keeper.pubkey()) > first);
+}
+
+#[test]
+fn idle_cranks_are_not_paid() {
+    let mut env = TestEnv::new();
+    fund_treasury(&mut env);
+    set_crank_reward(&mut env, CRANK_REWARD_BPS, CRANK_INTERVAL);
+    let keeper = env.new_user(0, 0);
+    crank(&mut env, &keeper, true);
+    let first = env.lp_balance(&keeper.pubkey());
+    let paid_at = env.pool().last_crank_reward_at;
+
+    env.warp_by(CRANK_INTERVAL as i64);
+    crank(&mut env, &keeper, true);
+
+    assert_eq!(env.lp_balance(&keeper.pubkey()), first);
+    assert_eq!(env.pool().last_crank_reward_at, paid_at);
+}
+
+#[test]
+fn cranks_without_a_reward_are_free() {
+    let mut env = TestEnv::new();
+    fund_treasury(&mut env);
+    let treasury_lp = env.lp_balance(&treasury_address().0);
+    let keeper = env.new_user(0, 0);
+
+    crank(&mut env, &keeper, true);
+
+    assert_eq!(env.lp_balance(&keeper.pubkey()), 0);
+    assert_eq!(env.lp_balance(&treasury_address().0), treasury_lp);
+}
+
+#[test]
+fn crank_rewards_need_an_interval_and_a_cap() {
+    let mut env = TestEnv::new();
+    let admin = env.admin.insecure_clone();
+
+    for (crank_interval, max_crank_reward) in [(0, MAX_CRANK_REWARD), (CRANK_INTERVAL, 0)] {
+        let result = env.send(
+            &[instructions::update_crank_reward(
+                &admin.pubkey(),
+                &env.protocol_fee_account.pubkey(),
+                CRANK_REWARD_BPS,
+                crank_interval,
+                max_crank_reward,
+            )],
+            &[&admin],
+        );
+
+        assert_error(result, ErrorCode::InvalidConfig);
+    }
+}
+
+#[test]
+fn only_the_admin_sets_the_crank_reward() {
+    let mut env = TestEnv::new();
+    let intruder = env.new_user(0, 0);
+
+    let result = env.send(
+        &[instructions::update_crank_reward(
+            &intruder.pubkey(),
+            &env.protocol_fee_account.pubkey(),
+            CRANK_REWARD_BPS,
+            CRANK_INTERVAL,
+            MAX_CRANK_REWARD,
+        )],
+        &[&intruder],
+    );
+
+    assert_error(result, AnchorErrorCode::ConstraintHasOne);
+}
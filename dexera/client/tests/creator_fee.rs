@@ -0,0 +1,162 @@
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use common::*;
+use dexera::errors::ErrorCode;
+use dexera_client::{instructions, instructions::SwapFees};
+use solana_sdk::signature::Signer;
+
+#[test]
+fn creator_fee_accrues_on_swaps_in_both_directions() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    env.enable_creator_fee(30);
+    let trader = env.new_user(100_000, 100_000);
+
+    let mut accrued = 0;
+    for is_x in [true, false] {
+        let quote = env.pool_state().quote_swap(is_x, 40_000, 0, false).unwrap();
+        assert!(quote.creator_fee > 0);
+
+        env.swap(&trader, is_x, 40_000, quote.amount_out, SwapFees::default())
+            .unwrap();
+
+        accrued += quote.creator_fee;
+        assert_eq!(env.balance(&keys.creator_fee_vault), accrued);
+    }
+}
+
+#[test]
+fn creator_claims_the_accrued_fee() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+    env.enable_creator_fee(30);
+    let trader = env.new_user(100_000, 0);
+    env.swap(&trader, true, 40_000, 1, SwapFees::default())
+        .unwrap();
+    let accrued = env.balance(&keys.creator_fee_vault);
+    let before = env.balance_of(&creator.pubkey(), &keys.mint_y);
+
+    env.send(
+        &[instructions::claim_creator_fee(&keys, &creator.pubkey())],
+        &[&creator],
+    )
+    .unwrap();
+
+    assert_eq!(
+        env.balance_of(&creator.pubkey(), &keys.mint_y),
+        before + accrued
+    );
+    assert_eq!(env.balance(&keys.creator_fee_vault), 0);
+
+    let result = env.send(
+        &[instructions::claim_creator_fee(&keys, &creator.pubkey())],
+        &[&creator],
+    );
+    assert_error(result, ErrorCode::InvalidAmount);
+}
+
+#[test]
+fn creator_fee_above_maximum() {
+    let mut env = TestEnv::new();
+    let creator = env.creator.insecure_clone();
+    env.enable_creator_fee(30);
+
+    let result = env.send(
+        &[instructions::update_creator_fee(
+            &env.keys,
+            &creator.pubkey(),
+            31,
+        )],
+        &[&creator],
+    );
+
+    assert_error(result, ErrorCode::CreatorFeeTooHigh);
+}
+
+#[test]
+fn lowered_maximum_caps_existing_creator_fees() {
+    let mut capped = synthetic_pool(1_000_000, 1_000_000, 1_000_000);
+    capped.pool.creator_fee = 50;
+    capped.config.max_creator_fee = 10;
+    let mut expected = synthetic_pool(1_000_000, 1_000_000, 1_000_000);
+    expected.pool.creator_fee = 10;
+    expected.config.max_creator_fee = 10;
+
+    for is_x in [true, false] {
+        assert_eq!(
+            capped.quote_swap(is_x, 40_000, 0, false).unwrap(),
+            expected.quote_swap(is_x, 40_000, 0, false).unwrap()
+        );
+    }
+}
+
+#[test]
+fn transfer_pool_creator_moves_fee_rights() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+    env.enable_creator_fee(30);
+    let new_creator = env.new_user(0, 0);
+    let trader = env.new_user(100_000, 0);
+    env.swap(&trader, true, 40_000, 1, SwapFees::default())
+        .unwrap();
+    let accrued = env.balance(&keys.creator_fee_vault);
+
+    env.send(
+        &[instructions::transfer_pool_creator(
+            &keys,
+            &creator.pubkey(),
+            &new_creator.pubkey(),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(env.pool().creator, new_creator.pubkey());
+
+    for ix in [
+        instructions::claim_creator_fee(&keys, &creator.pubkey()),
+        instructions::update_creator_fee(&keys, &creator.pubkey(), 0),
+    ] {
+        let result = env.send(&[ix], &[&creator]);
+        assert_error(result, AnchorErrorCode::ConstraintHasOne);
+    }
+
+    env.send(
+        &[instructions::claim_creator_fee(
+            &keys,
+            &new_creator.pubkey(),
+        )],
+        &[&new_creator],
+    )
+    .unwrap();
+    assert_eq!(env.balance_of(&new_creator.pubkey(), &keys.mint_y), accrued);
+}
+
+#[test]
+fn old_pools_get_a_creator_fee_vault_from_anyone() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    // Pools from before creator fees have no vault and no bump for it
+    env.svm
+        .set_account(keys.creator_fee_vault, Default::default())
+        .unwrap();
+    env.set_pool(|pool| pool.creator_fee_vault_bump = 0);
+    let trader = env.new_user(80_000, 0);
+    assert!(env
+        .swap(&trader, true, 40_000, 1, SwapFees::default())
+        .is_err());
+
+    let payer = env.new_user(0, 0);
+    env.send(
+        &[instructions::init_creator_fee_vault(&keys, &payer.pubkey())],
+        &[&payer],
+    )
+    .unwrap();
+
+    env.enable_creator_fee(30);
+    env.swap(&trader, true, 40_000, 1, SwapFees::default())
+        .unwrap();
+    assert!(env.balance(&keys.creator_fee_vault) > 0);
+}
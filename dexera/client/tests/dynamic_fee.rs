@@ -0,0 +1,122 @@
+mod common;
+
+use common::*;
+use dexera::errors::ErrorCode;
+use dexera_client::{instructions, instructions::SwapFees};
+use solana_sdk::signature::Signer;
+
+const MAX_VARIABLE_FEE: u16 = 200;
+// One basis point of fee per basis point of volatility
+const VARIABLE_FEE_CONTROL: u16 = 10_000;
+const DECAY_PERIOD: u32 = 600;
+
+#[test]
+fn dynamic_fee_is_off_by_default() {
+    let mut env = TestEnv::new();
+    let trader = env.new_user(100_000, 0);
+    let quote = env.pool_state().quote_swap(true, 40_000, 0, false).unwrap();
+    assert_eq!(quote.lp_fee, 0);
+    assert_eq!(quote.price_move_bps, 0);
+
+    env.swap(&trader, true, 40_000, 1, SwapFees::default())
+        .unwrap();
+
+    assert_eq!(env.pool().volatility_accumulator, 0);
+}
+
+#[test]
+fn price_moves_raise_the_fee_for_the_lps() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    env.enable_dynamic_fee(MAX_VARIABLE_FEE, VARIABLE_FEE_CONTROL, DECAY_PERIOD);
+    let trader = env.new_user(100_000, 100_000);
+
+    // A calm pool charges the base fee only
+    let first = env.pool_state().quote_swap(true, 10_000, 0, false).unwrap();
+    assert_eq!(first.lp_fee, 0);
+    assert!(first.price_move_bps > 0);
+    env.swap(&trader, true, 10_000, 1, SwapFees::default())
+        .unwrap();
+    assert_eq!(env.pool().volatility_accumulator, first.price_move_bps);
+
+    for is_x in [false, true] {
+        let vault_y = env.balance(&keys.vault_y);
+        let quote = env.pool_state().quote_swap(is_x, 10_000, 0, false).unwrap();
+        assert!(quote.lp_fee > 0);
+
+        env.swap(&trader, is_x, 10_000, quote.amount_out, SwapFees::default())
+            .unwrap();
+
+        // The variable fee never leaves the Y vault
+        let expected = if is_x {
+            vault_y - quote.amount_out - quote.fee
+        } else {
+            vault_y + 10_000 - quote.fee
+        };
+        assert_eq!(env.balance(&keys.vault_y), expected);
+    }
+}
+
+#[test]
+fn variable_fee_is_capped() {
+    let mut state = synthetic_pool(1_000_000, 1_000_000, 1_000_000);
+    state.pool.max_variable_fee = MAX_VARIABLE_FEE;
+    state.pool.variable_fee_control = VARIABLE_FEE_CONTROL;
+    state.pool.volatility_decay_period = DECAY_PERIOD;
+    state.pool.volatility_accumulator = 5_000;
+
+    assert_eq!(state.pool.variable_fee(0), MAX_VARIABLE_FEE);
+
+    // Accumulating past the cap is clipped so it fades as fast as a capped one
+    state.pool.accumulate_volatility(0, 5_000);
+    assert_eq!(state.pool.volatility_accumulator, MAX_VARIABLE_FEE as u32);
+}
+
+#[test]
+fn volatility_fades_out_over_the_decay_period() {
+    let mut state = synthetic_pool(1_000_000, 1_000_000, 1_000_000);
+    state.pool.max_variable_fee = MAX_VARIABLE_FEE;
+    state.pool.variable_fee_control = VARIABLE_FEE_CONTROL;
+    state.pool.volatility_decay_period = DECAY_PERIOD;
+    state.pool.volatility_accumulator = 100;
+    state.pool.last_volatility_update = 1_000;
+
+    assert_eq!(state.pool.variable_fee(1_000), 100);
+    assert_eq!(state.pool.variable_fee(1_300), 50);
+    assert_eq!(state.pool.variable_fee(1_600), 0);
+
+    state.now = 1_300;
+    let half = state.quote_swap(true, 40_000, 0, false).unwrap();
+    state.now = 1_600;
+    let calm = state.quote_swap(true, 40_000, 0, false).unwrap();
+    assert!(half.lp_fee > 0);
+    assert_eq!(calm.lp_fee, 0);
+    assert!(half.amount_out < calm.amount_out);
+}
+
+#[test]
+fn update_pool_dynamic_fee_bounds() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let admin = env.admin.insecure_clone();
+
+    for (max_variable_fee, volatility_decay_period) in [(10_000, DECAY_PERIOD), (100, 0)] {
+        let result = env.send(
+            &[instructions::update_pool_dynamic_fee(
+                &keys,
+                &admin.pubkey(),
+                max_variable_fee,
+                VARIABLE_FEE_CONTROL,
+                volatility_decay_period,
+            )],
+            &[&admin],
+        );
+        assert_error(result, ErrorCode::InvalidConfig);
+    }
+
+    env.enable_dynamic_fee(MAX_VARIABLE_FEE, VARIABLE_FEE_CONTROL, DECAY_PERIOD);
+    let pool = env.pool();
+    assert_eq!(pool.max_variable_fee, MAX_VARIABLE_FEE);
+    assert_eq!(pool.variable_fee_control, VARIABLE_FEE_CONTROL);
+    assert_eq!(pool.volatility_decay_period, DECAY_PERIOD);
+}
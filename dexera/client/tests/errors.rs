@@ -0,0 +1,166 @@
+mod common;
+
+use anchor_lang::Result;
+use common::*;
+use constant_product_curve::CurveError;
+use dexera::errors::ErrorCode;
+use dexera_client::{instructions, instructions::SwapFees};
+use solana_sdk::signature::{Keypair, Signer};
+
+fn assert_quote_error<T: std::fmt::Debug>(result: Result<T>, expected: ErrorCode) {
+    assert_eq!(result.unwrap_err(), expected.into());
+}
+
+#[test]
+fn curve_errors_keep_their_reason() {
+    for (curve_error, expected) in [
+        (CurveError::InvalidPrecision, ErrorCode::InvalidPrecision),
+        (CurveError::Overflow, ErrorCode::Overflow),
+        (CurveError::Underflow, ErrorCode::Underflow),
+        (CurveError::InvalidFeeAmount, ErrorCode::InvalidFee),
+        (
+            CurveError::InsufficientBalance,
+            ErrorCode::InsufficientBalance,
+        ),
+        (CurveError::ZeroBalance, ErrorCode::ZeroLiquidity),
+        (
+            CurveError::SlippageLimitExceeded,
+            ErrorCode::SlippageExceeded,
+        ),
+    ] {
+        assert_eq!(u32::from(ErrorCode::from(curve_error)), u32::from(expected));
+    }
+}
+
+#[test]
+fn quote_errors() {
+    assert_quote_error(
+        synthetic_pool(0, 0, 0).quote_swap(true, 1_000, 0, false),
+        ErrorCode::ZeroLiquidity,
+    );
+    assert_quote_error(
+        synthetic_pool(1_000_000, 1_000_000, 1_000_000).quote_swap(false, 1, 0, false),
+        ErrorCode::AmountTooSmall,
+    );
+    assert_quote_error(
+        synthetic_pool(u64::MAX - 1, 2, 1_000_000).quote_swap(true, u64::MAX, 0, false),
+        ErrorCode::Overflow,
+    );
+
+    let mut state = synthetic_pool(1_000_000, 1_000_000, 1_000_000);
+    state.config.fee = 10_000;
+    assert_quote_error(
+        state.quote_swap(true, 1_000, 0, false),
+        ErrorCode::InvalidFee,
+    );
+
+    assert_quote_error(
+        synthetic_pool(0, 0, 0).quote_deposit(1_000),
+        ErrorCode::ZeroLiquidity,
+    );
+    assert_quote_error(
+        synthetic_pool(2_000_000, 2_000_000, 1_000_000).quote_deposit(u64::MAX),
+        ErrorCode::Overflow,
+    );
+    assert_quote_error(
+        synthetic_pool(1_000_000, 1_000_000, 1_000_000).quote_withdraw(1_000_001),
+        ErrorCode::InsufficientBalance,
+    );
+}
+
+#[test]
+fn initialize_protocol_with_invalid_fee() {
+    let mut env = TestEnv::without_protocol();
+    let admin = env.admin.insecure_clone();
+
+    let result = env.send(
+        &[instructions::initialize_protocol(
+            &admin.pubkey(),
+            &Keypair::new().pubkey(),
+            10_000,
+            MAX_INTEGRATOR_FEE,
+        )],
+        &[&admin],
+    );
+
+    assert_error(result, ErrorCode::InvalidFee);
+}
+
+#[test]
+fn swap_too_small_to_trade() {
+    let mut env = TestEnv::new();
+    let trader = env.new_user(0, 100);
+
+    let result = env.swap(&trader, false, 1, 1, SwapFees::default());
+
+    assert_error(result, ErrorCode::AmountTooSmall);
+}
+
+#[test]
+fn swap_in_drained_pool() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+    env.send(
+        &[instructions::withdraw_liquidity(
+            &keys,
+            &creator.pubkey(),
+            INITIAL_LIQUIDITY,
+            0,
+            0,
+            None,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let trader = env.new_user(100_000, 0);
+
+    let result = env.swap(&trader, true, 40_000, 1, SwapFees::default());
+
+    assert_error(result, ErrorCode::ZeroLiquidity);
+}
+
+#[test]
+fn deposit_overflowing_the_vaults() {
+    let mut env = TestEnv::new();
+    let trader = env.new_user(0, 500_000);
+    env.swap(&trader, false, 500_000, 1, SwapFees::default())
+        .unwrap();
+    let depositor = env.new_user(0, 0);
+
+    // Y now outweighs the LP supply, so the Y owed for u64::MAX LP tokens overflows
+    let result = env.send(
+        &[instructions::deposit_liquidity(
+            &env.keys,
+            &depositor.pubkey(),
+            u64::MAX,
+            u64::MAX,
+            u64::MAX,
+            None,
+        )],
+        &[&depositor],
+    );
+
+    assert_error(result, ErrorCode::Overflow);
+}
+
+#[test]
+fn withdraw_more_than_supply() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+
+    let result = env.send(
+        &[instructions::withdraw_liquidity(
+            &keys,
+            &creator.pubkey(),
+            INITIAL_LIQUIDITY + 1,
+            0,
+            0,
+            None,
+        )],
+        &[&creator],
+    );
+
+    assert_error(result, ErrorCode::InsufficientBalance);
+}
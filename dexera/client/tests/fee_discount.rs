@@ -0,0 +1,163 @@
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use common::*;
+use dexera::errors::ErrorCode;
+use dexera_client::{instructions, instructions::SwapFees};
+use solana_sdk::signature::{Keypair, Signer};
+
+const TIER_FEE_DISCOUNTS: [u16; 3] = [1_000, 5_000, 10_000];
+
+const WITH_PROFILE: SwapFees = SwapFees {
+    referrer: None,
+    integrator_ata: None,
+    integrator_fee: 0,
+    compound_protocol_fees: false,
+    user_profile: true,
+};
+
+// A trader with a profile of the given tier, and the discounts turned on
+fn tiered_trader(env: &mut TestEnv, tier: u8) -> Keypair {
+    let admin = env.admin.insecure_clone();
+    let trader = env.new_user(100_000, 0);
+    env.create_profile(&trader, 7);
+    env.send(
+        &[
+            instructions::update_tier_fee_discounts(
+                &admin.pubkey(),
+                &env.protocol_fee_account.pubkey(),
+                TIER_FEE_DISCOUNTS,
+            ),
+            instructions::update_profile_tier(&admin.pubkey(), &trader.pubkey(), tier),
+        ],
+        &[&admin],
+    )
+    .expect("tier setup failed");
+
+    trader
+}
+
+#[test]
+fn tiered_traders_get_part_of_the_protocol_fee_back() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let trader = tiered_trader(&mut env, 2);
+
+    let quote = env
+        .pool_state()
+        .quote_swap(true, 100_000, 0, false)
+        .unwrap();
+    env.swap(&trader, true, 100_000, quote.amount_out, WITH_PROFILE)
+        .unwrap();
+
+    let discount = quote.protocol_fee / 2;
+    assert!(discount > 0);
+    assert_eq!(
+        env.balance_of(&trader.pubkey(), &keys.mint_y),
+        quote.amount_out + discount
+    );
+    assert_eq!(env.protocol_fee_balance(), quote.protocol_fee - discount);
+    assert_eq!(
+        env.pool_stats().protocol_fees,
+        (quote.protocol_fee - discount) as u128
+    );
+}
+
+#[test]
+fn top_tier_pays_no_protocol_fee() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let trader = tiered_trader(&mut env, 3);
+
+    let quote = env
+        .pool_state()
+        .quote_swap(true, 100_000, 0, false)
+        .unwrap();
+    env.swap(&trader, true, 100_000, 1, WITH_PROFILE).unwrap();
+
+    assert_eq!(env.protocol_fee_balance(), 0);
+    assert_eq!(
+        env.balance_of(&trader.pubkey(), &keys.mint_y),
+        quote.amount_out + quote.protocol_fee
+    );
+}
+
+#[test]
+fn expired_profiles_get_no_discount() {
+    let mut env = TestEnv::new();
+    let trader = tiered_trader(&mut env, 3);
+    env.warp_by(ONE_MONTH);
+
+    let quote = env
+        .pool_state()
+        .quote_swap(true, 100_000, 0, false)
+        .unwrap();
+    env.swap(&trader, true, 100_000, 1, WITH_PROFILE).unwrap();
+
+    assert_eq!(env.protocol_fee_balance(), quote.protocol_fee);
+}
+
+#[test]
+fn tiers_stop_at_the_highest() {
+    let mut env = TestEnv::new();
+    let admin = env.admin.insecure_clone();
+    let trader = env.new_user(0, 0);
+    env.create_profile(&trader, 7);
+
+    let result = env.send(
+        &[instructions::update_profile_tier(
+            &admin.pubkey(),
+            &trader.pubkey(),
+            4,
+        )],
+        &[&admin],
+    );
+
+    assert_error(result, ErrorCode::InvalidProfileTier);
+}
+
+#[test]
+fn only_admin_sets_tiers() {
+    let mut env = TestEnv::new();
+    let trader = env.new_user(0, 0);
+    env.create_profile(&trader, 7);
+
+    let result = env.send(
+        &[instructions::update_profile_tier(
+            &trader.pubkey(),
+            &trader.pubkey(),
+            3,
+        )],
+        &[&trader],
+    );
+
+    assert_error(result, AnchorErrorCode::ConstraintHasOne);
+}
+
+#[test]
+fn discounts_are_capped_at_the_whole_protocol_fee() {
+    let mut env = TestEnv::new();
+    let admin = env.admin.insecure_clone();
+
+    let result = env.send(
+        &[instructions::update_tier_fee_discounts(
+            &admin.pubkey(),
+            &env.protocol_fee_account.pubkey(),
+            [0, 0, 10_001],
+        )],
+        &[&admin],
+    );
+
+    assert_error(result, ErrorCode::InvalidConfig);
+}
+
+#[test]
+fn tier_zero_and_unknown_tiers_get_nothing() {
+    let mut config = synthetic_pool(0, 0, 0).config;
+    config.tier_fee_discounts = TIER_FEE_DISCOUNTS;
+
+    assert_eq!(config.tier_fee_discount(0, 1_000).unwrap(), 0);
+    assert_eq!(config.tier_fee_discount(1, 1_000).unwrap(), 100);
+    assert_eq!(config.tier_fee_discount(3, 1_000).unwrap(), 1_000);
+    assert_eq!(config.tier_fee_discount(4, 1_000).unwrap(), 0);
+}
@@ -0,0 +1,343 @@
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use common::*;
+use dexera::{errors::ErrorCode, Proposal, ProposalAction};
+use dexera_client::{
+    config_address, decode_proposal, decode_protocol_config, decode_voter, instructions,
+    instructions::SwapFees, proposal_address, voter_address,
+};
+use litesvm::types::TransactionResult;
+use solana_sdk::signature::{Keypair, Signer};
+
+const VOTING_PERIOD: i64 = 3 * 24 * 60 * 60;
+const EXECUTION_DELAY: i64 = 24 * 60 * 60;
+const QUORUM: u64 = 1_000;
+const PROPOSAL_THRESHOLD: u64 = 500;
+
+// Governance over X, so new_user's X balance is voting power
+fn with_governance() -> TestEnv {
+    let mut env = TestEnv::new();
+    let admin = env.admin.insecure_clone();
+    let keys = env.keys;
+    env.send(
+        &[instructions::init_governance(
+            &admin.pubkey(),
+            &keys.mint_x,
+            &keys.token_program,
+            VOTING_PERIOD,
+            EXECUTION_DELAY,
+            QUORUM,
+            PROPOSAL_THRESHOLD,
+        )],
+        &[&admin],
+    )
+    .expect("init_governance failed");
+    env
+}
+
+fn voter(env: &mut TestEnv, amount: u64) -> Keypair {
+    let voter = env.new_user(amount, 0);
+    let keys = env.keys;
+    env.send(
+        &[instructions::lock_governance_tokens(
+            &voter.pubkey(),
+            &keys.mint_x,
+            &keys.token_program,
+            amount,
+        )],
+        &[&voter],
+    )
+    .expect("lock_governance_tokens failed");
+    voter
+}
+
+#[allow(clippy::result_large_err)]
+fn propose(
+    env: &mut TestEnv,
+    proposer: &Keypair,
+    proposal_id: u64,
+    action: ProposalAction,
+) -> TransactionResult {
+    env.send(
+        &[instructions::create_proposal(
+            &proposer.pubkey(),
+            proposal_id,
+            action,
+        )],
+        &[proposer],
+    )
+}
+
+#[allow(clippy::result_large_err)]
+fn vote(env: &mut TestEnv, owner: &Keypair, proposal_id: u64, support: bool) -> TransactionResult {
+    env.send(
+        &[instructions::cast_vote(
+            &owner.pubkey(),
+            proposal_id,
+            support,
+        )],
+        &[owner],
+    )
+}
+
+#[allow(clippy::result_large_err)]
+fn execute(env: &mut TestEnv, proposal_id: u64) -> TransactionResult {
+    let keeper = env.new_user(0, 0);
+    env.send(
+        &[instructions::execute_proposal(proposal_id, None)],
+        &[&keeper],
+    )
+}
+
+fn fee(env: &TestEnv) -> u16 {
+    decode_protocol_config(&env.data(&config_address().0))
+        .unwrap()
+        .fee
+}
+
+fn proposal(votes_for: u64, votes_against: u64) -> Proposal {
+    Proposal {
+        proposal_id: 0,
+        proposer: Default::default(),
+        action: ProposalAction::Fee(FEE),
+        voting_ends_at: 0,
+        executable_at: 0,
+        votes_for,
+        votes_against,
+        executed: false,
+        proposal_bump: 0,
+    }
+}
+
+#[test]
+fn proposals_pass_with_a_majority_and_quorum() {
+    assert!(proposal(600, 400).passed(QUORUM));
+    assert!(!proposal(500, 500).passed(QUORUM));
+    assert!(!proposal(900, 0).passed(QUORUM));
+    assert!(!proposal(400, 600).passed(QUORUM));
+}
+
+#[test]
+fn actions_are_bounded_like_admin_updates() {
+    assert_eq!(
+        ProposalAction::Fee(10_000).validate().unwrap_err(),
+        ErrorCode::InvalidFee.into()
+    );
+    assert_eq!(
+        ProposalAction::TierFeeDiscounts([0, 100, 10_001])
+            .validate()
+            .unwrap_err(),
+        ErrorCode::InvalidConfig.into()
+    );
+    assert_eq!(
+        ProposalAction::ReferrerFeeShare(10_001)
+            .validate()
+            .unwrap_err(),
+        ErrorCode::InvalidConfig.into()
+    );
+    assert!(ProposalAction::MaxCreatorFee(10_000).validate().is_ok());
+}
+
+#[test]
+fn pool_actions_need_their_pool() {
+    let mut state = synthetic_pool(1_000, 1_000, 1_000);
+    let action = ProposalAction::PoolLocked {
+        pool: Default::default(),
+        locked: true,
+    };
+
+    assert_eq!(
+        action.apply(&mut state.config, None).unwrap_err(),
+        ErrorCode::InvalidProposalPool.into()
+    );
+    action
+        .apply(&mut state.config, Some(&mut state.pool))
+        .unwrap();
+    assert!(state.pool.locked);
+}
+
+#[test]
+fn passed_proposals_execute_after_the_delay() {
+    let mut env = with_governance();
+    let proposer = voter(&mut env, 1_000);
+    propose(&mut env, &proposer, 0, ProposalAction::Fee(30)).unwrap();
+    vote(&mut env, &proposer, 0, true).unwrap();
+
+    env.warp_by(VOTING_PERIOD);
+    let early = execute(&mut env, 0);
+    assert_error(early, ErrorCode::ProposalTimelocked);
+
+    env.warp_by(EXECUTION_DELAY);
+    execute(&mut env, 0).unwrap();
+    assert_eq!(fee(&env), 30);
+    let proposal = decode_proposal(&env.data(&proposal_address(0).0)).unwrap();
+    assert!(proposal.executed);
+
+    let again = execute(&mut env, 0);
+    assert_error(again, ErrorCode::ProposalAlreadyExecuted);
+}
+
+#[test]
+fn rejected_proposals_do_not_execute() {
+    let mut env = with_governance();
+    let proposer = voter(&mut env, 600);
+    let opponent = voter(&mut env, 700);
+    propose(&mut env, &proposer, 0, ProposalAction::Fee(30)).unwrap();
+    vote(&mut env, &proposer, 0, true).unwrap();
+    vote(&mut env, &opponent, 0, false).unwrap();
+    env.warp_by(VOTING_PERIOD + EXECUTION_DELAY);
+
+    let result = execute(&mut env, 0);
+
+    assert_error(result, ErrorCode::ProposalNotPassed);
+    assert_eq!(fee(&env), FEE);
+}
+
+#[test]
+fn proposals_need_a_quorum() {
+    let mut env = with_governance();
+    let proposer = voter(&mut env, 600);
+    propose(&mut env, &proposer, 0, ProposalAction::Fee(30)).unwrap();
+    vote(&mut env, &proposer, 0, true).unwrap();
+    env.warp_by(VOTING_PERIOD + EXECUTION_DELAY);
+
+    let result = execute(&mut env, 0);
+
+    assert_error(result, ErrorCode::ProposalNotPassed);
+}
+
+#[test]
+fn votes_close_with_the_voting_period() {
+    let mut env = with_governance();
+    let proposer = voter(&mut env, 1_000);
+    propose(&mut env, &proposer, 0, ProposalAction::Fee(30)).unwrap();
+    env.warp_by(VOTING_PERIOD);
+
+    let result = vote(&mut env, &proposer, 0, true);
+
+    assert_error(result, ErrorCode::VotingClosed);
+}
+
+#[test]
+fn voters_vote_once_per_proposal() {
+    let mut env = with_governance();
+    let proposer = voter(&mut env, 1_000);
+    propose(&mut env, &proposer, 0, ProposalAction::Fee(30)).unwrap();
+    vote(&mut env, &proposer, 0, true).unwrap();
+
+    let result = vote(&mut env, &proposer, 0, false);
+
+    assert!(result.is_err());
+    let proposal = decode_proposal(&env.data(&proposal_address(0).0)).unwrap();
+    assert_eq!((proposal.votes_for, proposal.votes_against), (1_000, 0));
+}
+
+#[test]
+fn tokens_stay_locked_until_voting_ends() {
+    let mut env = with_governance();
+    let proposer = voter(&mut env, 1_000);
+    propose(&mut env, &proposer, 0, ProposalAction::Fee(30)).unwrap();
+    vote(&mut env, &proposer, 0, true).unwrap();
+    let keys = env.keys;
+    let unlock = instructions::unlock_governance_tokens(
+        &proposer.pubkey(),
+        &keys.mint_x,
+        &keys.token_program,
+        1_000,
+    );
+
+    let result = env.send(std::slice::from_ref(&unlock), &[&proposer]);
+    assert_error(result, ErrorCode::VotesActive);
+
+    env.warp_by(VOTING_PERIOD);
+    env.send(&[unlock], &[&proposer]).unwrap();
+    assert_eq!(env.balance_of(&proposer.pubkey(), &keys.mint_x), 1_000);
+    let voter = decode_voter(&env.data(&voter_address(&proposer.pubkey()).0)).unwrap();
+    assert_eq!(voter.locked_amount, 0);
+}
+
+#[test]
+fn proposers_need_the_threshold() {
+    let mut env = with_governance();
+    let proposer = voter(&mut env, PROPOSAL_THRESHOLD - 1);
+
+    let result = propose(&mut env, &proposer, 0, ProposalAction::Fee(30));
+
+    assert_error(result, ErrorCode::ProposalThresholdNotMet);
+}
+
+#[test]
+fn invalid_actions_are_rejected_when_proposed() {
+    let mut env = with_governance();
+    let proposer = voter(&mut env, 1_000);
+
+    let result = propose(&mut env, &proposer, 0, ProposalAction::Fee(10_000));
+
+    assert_error(result, ErrorCode::InvalidFee);
+}
+
+#[test]
+fn proposals_pause_pools() {
+    let mut env = with_governance();
+    let proposer = voter(&mut env, 1_000);
+    let pool = env.keys.pool;
+    let action = ProposalAction::PoolLocked { pool, locked: true };
+    propose(&mut env, &proposer, 0, action).unwrap();
+    vote(&mut env, &proposer, 0, true).unwrap();
+    env.warp_by(VOTING_PERIOD + EXECUTION_DELAY);
+
+    let keeper = env.new_user(0, 0);
+    let without_pool = env.send(&[instructions::execute_proposal(0, None)], &[&keeper]);
+    assert_error(without_pool, ErrorCode::InvalidProposalPool);
+    env.send(&[instructions::execute_proposal(0, Some(pool))], &[&keeper])
+        .unwrap();
+
+    assert!(env.pool().locked);
+    let trader = env.new_user(10_000, 0);
+    let result = env.swap(&trader, true, 10_000, 1, SwapFees::default());
+    assert_error(result, ErrorCode::AMMLocked);
+}
+
+#[test]
+fn proposals_set_the_referrer_fee_share() {
+    let mut env = with_governance();
+    let proposer = voter(&mut env, 1_000);
+    propose(
+        &mut env,
+        &proposer,
+        0,
+        ProposalAction::ReferrerFeeShare(5_000),
+    )
+    .unwrap();
+    vote(&mut env, &proposer, 0, true).unwrap();
+    env.warp_by(VOTING_PERIOD + EXECUTION_DELAY);
+
+    execute(&mut env, 0).unwrap();
+
+    let config = decode_protocol_config(&env.data(&config_address().0)).unwrap();
+    assert_eq!(config.referrer_fee_share, 5_000);
+    assert_eq!(config.split_fee(1_000, true), (500, 500));
+}
+
+#[test]
+fn only_the_admin_sets_up_governance() {
+    let mut env = TestEnv::new();
+    let intruder = env.new_user(0, 0);
+    let keys = env.keys;
+
+    let result = env.send(
+        &[instructions::init_governance(
+            &intruder.pubkey(),
+            &keys.mint_x,
+            &keys.token_program,
+            VOTING_PERIOD,
+            EXECUTION_DELAY,
+            QUORUM,
+            PROPOSAL_THRESHOLD,
+        )],
+        &[&intruder],
+    );
+
+    assert_error(result, AnchorErrorCode::ConstraintHasOne);
+}
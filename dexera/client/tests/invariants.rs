@@ -0,0 +1,417 @@
+// Property tests for the pool maths: the pure quote engine over random reserves,
+// and random sequences of deposits, withdrawals and swaps on LiteSVM.
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use dexera::LiquidityQuote;
+use dexera_client::{instructions, instructions::SwapFees, PoolState};
+use proptest::prelude::*;
+use solana_sdk::signature::{Keypair, Signer};
+
+const USER_FUNDS: u64 = 1_000_000_000;
+const CREATOR_FEE: u16 = 20;
+const MAX_VARIABLE_FEE: u16 = 100;
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Deposit {
+        lp_tokens_amount: u64,
+    },
+    // Share of the active LP's position, in basis points
+    Withdraw {
+        share_bps: u64,
+    },
+    Swap {
+        is_x: bool,
+        amount_in: u64,
+        referred: bool,
+        integrator_fee: u16,
+    },
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (1..2_000_000u64).prop_map(|lp_tokens_amount| Op::Deposit { lp_tokens_amount }),
+        (1..=10_000u64).prop_map(|share_bps| Op::Withdraw { share_bps }),
+        (
+            any::<bool>(),
+            1..500_000u64,
+            any::<bool>(),
+            0..=MAX_INTEGRATOR_FEE
+        )
+            .prop_map(|(is_x, amount_in, referred, integrator_fee)| Op::Swap {
+                is_x,
+                amount_in,
+                referred,
+                integrator_fee,
+            }),
+    ]
+}
+
+fn k(state: &PoolState) -> u128 {
+    state.vault_x as u128 * state.vault_y as u128
+}
+
+// sqrt(k) / lp_supply is what one LP token is worth; compared squared to stay in integers
+fn assert_lp_value_kept(before: &PoolState, after: &PoolState) {
+    if after.lp_supply == 0 {
+        return;
+    }
+
+    assert!(
+        k(after) * (before.lp_supply as u128).pow(2)
+            >= k(before) * (after.lp_supply as u128).pow(2),
+        "LP value dropped: {:?} -> {:?}",
+        (before.vault_x, before.vault_y, before.lp_supply),
+        (after.vault_x, after.vault_y, after.lp_supply),
+    );
+}
+
+struct Harness {
+    env: TestEnv,
+    lp: Keypair,
+    trader: Keypair,
+    referrer: Keypair,
+    integrator: Keypair,
+}
+
+impl Harness {
+    fn new() -> Self {
+        let mut env = TestEnv::new();
+        env.enable_creator_fee(CREATOR_FEE);
+        env.enable_dynamic_fee(MAX_VARIABLE_FEE, 10_000, 600);
+        let lp = env.new_user(USER_FUNDS, USER_FUNDS);
+        let trader = env.new_user(USER_FUNDS, USER_FUNDS);
+        let referrer = env.new_user(0, 0);
+        env.create_profile(&referrer, 1);
+        let integrator = env.new_user(0, 0);
+
+        Self {
+            env,
+            lp,
+            trader,
+            referrer,
+            integrator,
+        }
+    }
+
+    // Every account that can hold X or Y in this test
+    fn holders(&self) -> Vec<Pubkey> {
+        vec![
+            self.env.keys.pool,
+            self.env.creator.pubkey(),
+            self.env.protocol_fee_account.pubkey(),
+            self.lp.pubkey(),
+            self.trader.pubkey(),
+            self.referrer.pubkey(),
+            self.integrator.pubkey(),
+        ]
+    }
+
+    fn total(&self, mint: &Pubkey) -> u64 {
+        let held: u64 = self
+            .holders()
+            .iter()
+            .map(|owner| self.env.balance_of(owner, mint))
+            .sum();
+
+        // Creator fees sit in a pool owned account in Y until claimed
+        if *mint == self.env.keys.mint_y {
+            held + self.env.balance(&self.env.keys.creator_fee_vault)
+        } else {
+            held
+        }
+    }
+
+    fn apply(&mut self, op: Op) {
+        let before = self.env.pool_state();
+        let totals = (
+            self.total(&self.env.keys.mint_x),
+            self.total(&self.env.keys.mint_y),
+        );
+
+        match op {
+            Op::Deposit { lp_tokens_amount } => self.deposit(&before, lp_tokens_amount),
+            Op::Withdraw { share_bps } => self.withdraw(&before, share_bps),
+            Op::Swap {
+                is_x,
+                amount_in,
+                referred,
+                integrator_fee,
+            } => self.swap(&before, is_x, amount_in, referred, integrator_fee),
+        }
+
+        let after = self.env.pool_state();
+        assert_lp_value_kept(&before, &after);
+        assert!(after.vault_x > 0 && after.vault_y > 0, "vault drained");
+        assert_eq!(
+            (
+                self.total(&self.env.keys.mint_x),
+                self.total(&self.env.keys.mint_y)
+            ),
+            totals,
+            "tokens created or lost"
+        );
+    }
+
+    fn deposit(&mut self, before: &PoolState, lp_tokens_amount: u64) {
+        let keys = self.env.keys;
+        let quote = before.quote_deposit(lp_tokens_amount).unwrap();
+        let lp = self.lp.pubkey();
+        let (x, y) = (
+            self.env.balance_of(&lp, &keys.mint_x),
+            self.env.balance_of(&lp, &keys.mint_y),
+        );
+        let position = self.env.lp_balance(&lp);
+
+        let result = self.env.send(
+            &[instructions::deposit_liquidity(
+                &keys,
+                &lp,
+                lp_tokens_amount,
+                quote.x,
+                quote.y,
+                None,
+            )],
+            &[&self.lp],
+        );
+
+        if quote.x > x || quote.y > y {
+            assert!(result.is_err());
+            return;
+        }
+        result.unwrap();
+
+        assert_eq!(self.env.lp_balance(&lp), position + lp_tokens_amount);
+        assert_eq!(self.env.balance_of(&lp, &keys.mint_x), x - quote.x);
+        assert_eq!(self.env.balance_of(&lp, &keys.mint_y), y - quote.y);
+    }
+
+    fn withdraw(&mut self, before: &PoolState, share_bps: u64) {
+        let keys = self.env.keys;
+        let lp = self.lp.pubkey();
+        let position = self.env.lp_balance(&lp);
+        if position == 0 {
+            return;
+        }
+
+        let amount = (position * share_bps / 10_000).max(1);
+        let quote = before.quote_withdraw(amount).unwrap();
+        let (x, y) = (
+            self.env.balance_of(&lp, &keys.mint_x),
+            self.env.balance_of(&lp, &keys.mint_y),
+        );
+
+        self.env
+            .send(
+                &[instructions::withdraw_liquidity(
+                    &keys, &lp, amount, quote.x, quote.y, None,
+                )],
+                &[&self.lp],
+            )
+            .unwrap();
+
+        assert_eq!(self.env.lp_balance(&lp), position - amount);
+        assert_eq!(self.env.balance_of(&lp, &keys.mint_x), x + quote.x);
+        assert_eq!(self.env.balance_of(&lp, &keys.mint_y), y + quote.y);
+    }
+
+    fn swap(
+        &mut self,
+        before: &PoolState,
+        is_x: bool,
+        amount_in: u64,
+        referred: bool,
+        integrator_fee: u16,
+    ) {
+        let keys = self.env.keys;
+        let trader = self.trader.pubkey();
+        let (mint_in, mint_out) = if is_x {
+            (keys.mint_x, keys.mint_y)
+        } else {
+            (keys.mint_y, keys.mint_x)
+        };
+        let integrator_ata = keys.user_ata(&self.integrator.pubkey(), &mint_in);
+        if integrator_fee > 0 {
+            self.env.create_ata(&self.integrator.pubkey(), &mint_in);
+        }
+
+        let balances = |env: &TestEnv| {
+            (
+                env.balance_of(&trader, &mint_in),
+                env.balance_of(&trader, &mint_out),
+                env.protocol_fee_balance(),
+                env.balance_of(&self.referrer.pubkey(), &keys.mint_y),
+                env.balance(&integrator_ata),
+                env.balance(&keys.creator_fee_vault),
+            )
+        };
+        let (user_in, user_out, protocol, referrer, integrator, creator) = balances(&self.env);
+
+        let quote = before.quote_swap(is_x, amount_in, integrator_fee, referred);
+        let result = self.env.swap(
+            &self.trader,
+            is_x,
+            amount_in,
+            1,
+            SwapFees {
+                referrer: referred.then(|| self.referrer.pubkey()),
+                integrator_ata: (integrator_fee > 0).then_some(integrator_ata),
+                integrator_fee,
+                ..SwapFees::default()
+            },
+        );
+
+        let quote = match quote {
+            Ok(quote) if quote.amount_out > 0 => quote,
+            _ => {
+                assert!(result.is_err(), "swap the quote rejects went through");
+                return;
+            }
+        };
+        result.unwrap();
+
+        assert_eq!(quote.fee, quote.referrer_fee + quote.protocol_fee);
+        assert_eq!(
+            balances(&self.env),
+            (
+                user_in - amount_in,
+                user_out + quote.amount_out,
+                protocol + quote.protocol_fee,
+                referrer + quote.referrer_fee,
+                integrator + quote.integrator_fee,
+                creator + quote.creator_fee,
+            )
+        );
+
+        let after = self.env.pool_state();
+        assert!(k(&after) >= k(before), "k decreased");
+    }
+}
+
+proptest! {
+    #[test]
+    fn swap_quotes_never_shrink_k(
+        vault_x in 1..1u64 << 40,
+        vault_y in 1..1u64 << 40,
+        is_x: bool,
+        amount_in in 1..1u64 << 40,
+        integrator_fee in 0..=MAX_INTEGRATOR_FEE,
+        creator_fee in 0..=100u16,
+        volatility in 0..1_000u32,
+        referred: bool,
+    ) {
+        let mut state = synthetic_pool(vault_x, vault_y, 1_000_000);
+        state.config.max_creator_fee = creator_fee;
+        state.pool.creator_fee = creator_fee;
+        state.pool.max_variable_fee = MAX_VARIABLE_FEE;
+        state.pool.variable_fee_control = 10_000;
+        state.pool.volatility_decay_period = 600;
+        state.pool.volatility_accumulator = volatility;
+        let Ok(quote) = state.quote_swap(is_x, amount_in, integrator_fee, referred) else {
+            return Ok(());
+        };
+
+        prop_assert_eq!(quote.fee, quote.referrer_fee + quote.protocol_fee);
+
+        let curve_in = (amount_in - quote.integrator_fee) as u128;
+        let (x, y) = (vault_x as u128, vault_y as u128);
+        let (out, fee) = (quote.amount_out as u128, quote.fee as u128);
+        let creator_fee = quote.creator_fee as u128;
+        // The LP fee stays in the vaults, so it only adds to k
+        let k_after = if is_x {
+            (x + curve_in) * (y - out - fee - creator_fee)
+        } else {
+            (y + curve_in - fee - creator_fee) * (x - out)
+        };
+        prop_assert!(k_after >= x * y);
+    }
+
+    #[test]
+    fn batch_settlements_balance_and_never_shrink_k(
+        vault_x in 1..1u64 << 40,
+        vault_y in 1..1u64 << 40,
+        x_in in 0..1u64 << 40,
+        y_in in 0..1u64 << 40,
+        creator_fee in 0..=100u16,
+    ) {
+        let mut state = synthetic_pool(vault_x, vault_y, 1_000_000);
+        state.config.max_creator_fee = creator_fee;
+        state.pool.creator_fee = creator_fee;
+        let settlement = state.settle_batch(x_in, y_in).unwrap();
+
+        let (pool_in, pool_out) = (settlement.pool_amount_in, settlement.pool_amount_out);
+        let (x_from_pool, y_from_pool, x_to_pool, y_to_pool) = if settlement.is_x {
+            (0, pool_out, pool_in, 0)
+        } else {
+            (pool_out, 0, 0, pool_in)
+        };
+        // Everything the orders put in is paid out, to the pool, the orders or as fees
+        prop_assert_eq!(x_in + x_from_pool, settlement.x_out + x_to_pool);
+        prop_assert_eq!(
+            y_in + y_from_pool,
+            settlement.y_out + settlement.fee + settlement.creator_fee + y_to_pool
+        );
+
+        let (x, y) = (vault_x as u128, vault_y as u128);
+        let k_after = (x + x_to_pool as u128 - x_from_pool as u128)
+            * (y + y_to_pool as u128 - y_from_pool as u128);
+        prop_assert!(k_after >= x * y);
+    }
+
+    #[test]
+    fn liquidity_quotes_round_in_the_pools_favour(
+        vault_x in 0..1u64 << 40,
+        vault_y in 0..1u64 << 40,
+        lp_supply in 1..1u64 << 40,
+        lp_tokens_amount in 1..1u64 << 40,
+    ) {
+        let state = synthetic_pool(vault_x, vault_y, lp_supply);
+        let fair = |reserve: u64| reserve as u128 * lp_tokens_amount as u128;
+        let supply = lp_supply as u128;
+
+        // Rounded up, but by no more than one token
+        if let Ok(deposit) = state.quote_deposit(lp_tokens_amount) {
+            for (paid, reserve) in [(deposit.x, vault_x), (deposit.y, vault_y)] {
+                prop_assert!(paid as u128 * supply >= fair(reserve));
+                prop_assert!((paid as u128).saturating_sub(1) * supply < fair(reserve).max(1));
+            }
+        }
+
+        if let Ok(withdraw) = state.quote_withdraw(lp_tokens_amount) {
+            for (received, reserve) in [(withdraw.x, vault_x), (withdraw.y, vault_y)] {
+                prop_assert!(received as u128 * supply <= fair(reserve));
+            }
+        } else {
+            prop_assert!(lp_tokens_amount > lp_supply);
+        }
+    }
+
+    #[test]
+    fn compounded_fees_never_dilute_lps(
+        vault_x in 1..1u64 << 28,
+        vault_y in 1..1u64 << 28,
+        lp_supply in 1..1u64 << 28,
+        fee in 0..1u64 << 28,
+    ) {
+        let compound = LiquidityQuote::compound(vault_y, lp_supply, fee).unwrap();
+        let before = synthetic_pool(vault_x, vault_y, lp_supply);
+        let after = synthetic_pool(vault_x, vault_y + fee, lp_supply + compound.lp_tokens_amount);
+
+        assert_lp_value_kept(&before, &after);
+    }
+}
+
+proptest! {
+    // Every case boots a fresh SVM, so keep the count low
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn random_operations_keep_pool_invariants(ops in prop::collection::vec(op(), 1..40)) {
+        let mut harness = Harness::new();
+        for op in ops {
+            harness.apply(op);
+        }
+    }
+}
@@ -0,0 +1,384 @@
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use anchor_spl::token::spl_token;
+use common::*;
+use dexera::{errors::ErrorCode, Pool};
+use dexera_client::{decode_mint, instructions, instructions::SwapFees, PoolKeys};
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signature::Signer};
+
+#[test]
+fn create_pool_seeds_initial_liquidity() {
+    let env = TestEnv::new();
+    let creator = env.creator.pubkey();
+
+    assert_eq!(env.lp_balance(&creator), INITIAL_LIQUIDITY);
+    assert_eq!(
+        env.balance_of(&creator, &env.keys.mint_x),
+        1_000_000_000 - INITIAL_LIQUIDITY
+    );
+    assert_eq!(
+        env.balance_of(&creator, &env.keys.mint_y),
+        1_000_000_000 - INITIAL_LIQUIDITY
+    );
+    assert_eq!(env.balance(&env.keys.vault_lp), 0);
+    assert!(!env.pool().locked);
+}
+
+#[test]
+fn lp_mint_takes_the_more_precise_decimals() {
+    let mut env = TestEnv::with_protocol();
+    let mint_x = env.create_mint_with_decimals(None, 9);
+    let fee_account = env.protocol_fee_account.pubkey();
+    env.keys = PoolKeys::new(mint_x, env.keys.mint_y, spl_token::ID, &fee_account);
+    let creator = env.new_user(1_000_000_000, 1_000_000_000);
+
+    env.create_pool(&creator, None).unwrap();
+
+    let mint_lp = decode_mint(&env.data(&env.keys.mint_lp)).unwrap();
+    assert_eq!(mint_lp.decimals, 9);
+    assert_eq!(env.pool().lp_decimals, 9);
+
+    let user = env.new_user(1_000_000, 1_000_000);
+    let keys = env.keys;
+    env.send(
+        &[instructions::deposit_liquidity(
+            &keys,
+            &user.pubkey(),
+            100_000,
+            1_000_000,
+            1_000_000,
+            None,
+        )],
+        &[&user],
+    )
+    .unwrap();
+    env.swap(&user, true, 10_000, 1, SwapFees::default())
+        .unwrap();
+}
+
+#[test]
+fn lp_decimals_are_capped_for_the_curve() {
+    assert_eq!(Pool::lp_decimals_for(6, 6), 6);
+    assert_eq!(Pool::lp_decimals_for(0, 6), 6);
+    assert_eq!(Pool::lp_decimals_for(18, 6), Pool::MAX_LP_DECIMALS);
+}
+
+#[test]
+fn create_pool_only_once_per_pair() {
+    let mut env = TestEnv::new();
+    let creator = env.new_user(INITIAL_LIQUIDITY, INITIAL_LIQUIDITY);
+
+    let result = env.create_pool(&creator, None);
+
+    assert!(result.is_err());
+    assert_eq!(env.pool().creator, env.creator.pubkey());
+}
+
+#[test]
+fn create_pool_pays_the_creation_fee() {
+    let mut env = TestEnv::with_protocol();
+    let admin = env.admin.insecure_clone();
+    let fee_account = env.protocol_fee_account.pubkey();
+    env.send(
+        &[instructions::update_pool_creation_fee(
+            &admin.pubkey(),
+            &fee_account,
+            LAMPORTS_PER_SOL,
+        )],
+        &[&admin],
+    )
+    .unwrap();
+    let creator = env.new_user(INITIAL_LIQUIDITY, INITIAL_LIQUIDITY);
+    let before = env.lamports(&fee_account);
+
+    env.create_pool(&creator, None).unwrap();
+
+    assert_eq!(env.lamports(&fee_account), before + LAMPORTS_PER_SOL);
+}
+
+#[test]
+fn permissioned_pool_creation() {
+    let mut env = TestEnv::with_protocol();
+    let admin = env.admin.insecure_clone();
+    let creator = env.new_user(INITIAL_LIQUIDITY, INITIAL_LIQUIDITY);
+    let other = env.new_user(0, 0);
+    env.send(
+        &[
+            instructions::update_permissioned_pool_creation(
+                &admin.pubkey(),
+                &env.protocol_fee_account.pubkey(),
+                true,
+            ),
+            instructions::add_to_allowlist(&admin.pubkey(), &other.pubkey()),
+        ],
+        &[&admin],
+    )
+    .unwrap();
+
+    let result = env.create_pool(&creator, None);
+    assert_error(result, ErrorCode::PoolCreationNotAllowed);
+
+    // Someone else's approval does not carry over
+    let result = env.create_pool(&creator, Some(other.pubkey()));
+    assert_error(result, ErrorCode::PoolCreationNotAllowed);
+
+    env.send(
+        &[instructions::add_to_allowlist(
+            &admin.pubkey(),
+            &creator.pubkey(),
+        )],
+        &[&admin],
+    )
+    .unwrap();
+    env.create_pool(&creator, Some(creator.pubkey())).unwrap();
+}
+
+#[test]
+fn allowlisted_mint_pair_lets_anyone_create_the_pool() {
+    let mut env = TestEnv::with_protocol();
+    let admin = env.admin.insecure_clone();
+    let creator = env.new_user(INITIAL_LIQUIDITY, INITIAL_LIQUIDITY);
+    let pool = env.keys.pool;
+    env.send(
+        &[
+            instructions::update_permissioned_pool_creation(
+                &admin.pubkey(),
+                &env.protocol_fee_account.pubkey(),
+                true,
+            ),
+            instructions::add_to_allowlist(&admin.pubkey(), &pool),
+            instructions::remove_from_allowlist(&admin.pubkey(), &pool),
+        ],
+        &[&admin],
+    )
+    .unwrap();
+
+    let result = env.create_pool(&creator, Some(pool));
+    assert_error(result, AnchorErrorCode::AccountNotInitialized);
+
+    env.send(
+        &[instructions::add_to_allowlist(&admin.pubkey(), &pool)],
+        &[&admin],
+    )
+    .unwrap();
+    env.create_pool(&creator, Some(pool)).unwrap();
+}
+
+#[test]
+fn deposit_liquidity_at_pool_ratio() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let depositor = env.new_user(1_000_000, 1_000_000);
+    let quote = env.pool_state().quote_deposit(500_000).unwrap();
+
+    env.send(
+        &[instructions::deposit_liquidity(
+            &keys,
+            &depositor.pubkey(),
+            500_000,
+            quote.x,
+            quote.y,
+            None,
+        )],
+        &[&depositor],
+    )
+    .unwrap();
+
+    assert_eq!(env.lp_balance(&depositor.pubkey()), 500_000);
+    assert_eq!(
+        env.balance_of(&depositor.pubkey(), &keys.mint_x),
+        1_000_000 - quote.x
+    );
+    assert_eq!(
+        env.balance_of(&depositor.pubkey(), &keys.mint_y),
+        1_000_000 - quote.y
+    );
+    assert_eq!(env.balance(&keys.vault_x), INITIAL_LIQUIDITY + quote.x);
+    assert_eq!(env.balance(&keys.vault_y), INITIAL_LIQUIDITY + quote.y);
+}
+
+#[test]
+fn deposit_liquidity_respects_max_amounts() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let depositor = env.new_user(1_000_000, 1_000_000);
+    let quote = env.pool_state().quote_deposit(500_000).unwrap();
+
+    let result = env.send(
+        &[instructions::deposit_liquidity(
+            &keys,
+            &depositor.pubkey(),
+            500_000,
+            quote.x - 1,
+            quote.y,
+            None,
+        )],
+        &[&depositor],
+    );
+    assert_error(result, ErrorCode::InsufficientTokenX);
+
+    let result = env.send(
+        &[instructions::deposit_liquidity(
+            &keys,
+            &depositor.pubkey(),
+            500_000,
+            quote.x,
+            quote.y - 1,
+            None,
+        )],
+        &[&depositor],
+    );
+    assert_error(result, ErrorCode::InsufficientTokenY);
+}
+
+#[test]
+fn deposit_liquidity_into_locked_pool() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let depositor = env.new_user(1_000_000, 1_000_000);
+    env.set_pool(|pool| pool.locked = true);
+
+    let result = env.send(
+        &[instructions::deposit_liquidity(
+            &keys,
+            &depositor.pubkey(),
+            500_000,
+            1_000_000,
+            1_000_000,
+            None,
+        )],
+        &[&depositor],
+    );
+
+    assert_error(result, ErrorCode::AMMLocked);
+}
+
+#[test]
+fn withdraw_liquidity_burns_lp_tokens() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+    let (x_before, y_before) = (
+        env.balance_of(&creator.pubkey(), &keys.mint_x),
+        env.balance_of(&creator.pubkey(), &keys.mint_y),
+    );
+    let quote = env.pool_state().quote_withdraw(250_000).unwrap();
+
+    env.send(
+        &[instructions::withdraw_liquidity(
+            &keys,
+            &creator.pubkey(),
+            250_000,
+            quote.x,
+            quote.y,
+            None,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    assert_eq!(
+        env.lp_balance(&creator.pubkey()),
+        INITIAL_LIQUIDITY - 250_000
+    );
+    assert_eq!(
+        env.balance_of(&creator.pubkey(), &keys.mint_x),
+        x_before + quote.x
+    );
+    assert_eq!(
+        env.balance_of(&creator.pubkey(), &keys.mint_y),
+        y_before + quote.y
+    );
+    assert_eq!(env.pool_state().lp_supply, INITIAL_LIQUIDITY - 250_000);
+}
+
+#[test]
+fn withdraw_liquidity_respects_min_amounts() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+    let quote = env.pool_state().quote_withdraw(250_000).unwrap();
+
+    let result = env.send(
+        &[instructions::withdraw_liquidity(
+            &keys,
+            &creator.pubkey(),
+            250_000,
+            quote.x + 1,
+            quote.y,
+            None,
+        )],
+        &[&creator],
+    );
+    assert_error(result, ErrorCode::InsufficientTokenX);
+
+    let result = env.send(
+        &[instructions::withdraw_liquidity(
+            &keys,
+            &creator.pubkey(),
+            250_000,
+            quote.x,
+            quote.y + 1,
+            None,
+        )],
+        &[&creator],
+    );
+    assert_error(result, ErrorCode::InsufficientTokenY);
+}
+
+#[test]
+fn withdraw_zero_liquidity() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+
+    let result = env.send(
+        &[instructions::withdraw_liquidity(
+            &keys,
+            &creator.pubkey(),
+            0,
+            0,
+            0,
+            None,
+        )],
+        &[&creator],
+    );
+
+    assert_error(result, ErrorCode::InvalidAmount);
+}
+
+#[test]
+fn liquidity_after_deadline() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+    let deadline = Some(env.now() + 60);
+    env.warp_by(61);
+
+    let result = env.send(
+        &[instructions::deposit_liquidity(
+            &keys,
+            &creator.pubkey(),
+            100_000,
+            1_000_000,
+            1_000_000,
+            deadline,
+        )],
+        &[&creator],
+    );
+    assert_error(result, ErrorCode::DeadlineExceeded);
+
+    let result = env.send(
+        &[instructions::withdraw_liquidity(
+            &keys,
+            &creator.pubkey(),
+            100_000,
+            0,
+            0,
+            deadline,
+        )],
+        &[&creator],
+    );
+    assert_error(result, ErrorCode::DeadlineExceeded);
+}
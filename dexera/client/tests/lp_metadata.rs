@@ -0,0 +1,87 @@
+use anchor_lang::{prelude::Pubkey, solana_program::program_pack::Pack, AnchorSerialize};
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        extension::{BaseStateWithExtensionsMut, StateWithExtensionsMut},
+        state::{Account, Mint},
+    },
+    token_2022_extensions::spl_token_metadata_interface::state::TokenMetadata,
+};
+use dexera::metadata::{lp_name, metaplex_symbol, token_2022_symbol};
+
+fn metaplex_account(key: u8, name: &str, symbol: &str) -> Vec<u8> {
+    let mut data = vec![key];
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    // Metaplex pads name and symbol to their max length with zeros
+    format!("{:\0<32}", name).serialize(&mut data).unwrap();
+    format!("{:\0<10}", symbol).serialize(&mut data).unwrap();
+    "https://example.com".serialize(&mut data).unwrap();
+    data
+}
+
+fn token_2022_mint(symbol: &str) -> Vec<u8> {
+    let metadata = TokenMetadata {
+        name: "Wrapped SOL".to_string(),
+        symbol: symbol.to_string(),
+        ..Default::default()
+    };
+    // Mints with extensions are padded to the account length, then carry the account type
+    let space = Account::LEN + 1 + metadata.tlv_size_of().unwrap();
+    let mut data = vec![0; space];
+
+    let mut mint = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+    mint.base.is_initialized = true;
+    mint.pack_base();
+    mint.init_account_type().unwrap();
+    mint.init_variable_len_extension(&metadata, false).unwrap();
+
+    data
+}
+
+#[test]
+fn lp_name_joins_both_symbols() {
+    assert_eq!(lp_name("SOL", "USDC"), "DEXERA SOL-USDC LP");
+}
+
+#[test]
+fn lp_name_fits_metaplex_limit() {
+    let name = lp_name("ABCDEFGHIJKL", "MNOPQRSTUVWX");
+
+    assert_eq!(name.len(), 32);
+    assert_eq!(name, "DEXERA ABCDEFGHIJKL-MNOPQRSTUVWX");
+}
+
+#[test]
+fn metaplex_symbol_strips_padding() {
+    let data = metaplex_account(4, "USD Coin", "USDC");
+
+    assert_eq!(metaplex_symbol(&data), Some("USDC".to_string()));
+}
+
+#[test]
+fn metaplex_symbol_rejects_other_accounts() {
+    let data = metaplex_account(6, "USD Coin", "USDC");
+
+    assert_eq!(metaplex_symbol(&data), None);
+    assert_eq!(metaplex_symbol(&[]), None);
+    assert_eq!(metaplex_symbol(&[4; 40]), None);
+}
+
+#[test]
+fn token_2022_symbol_reads_metadata_extension() {
+    let data = token_2022_mint("wSOL");
+
+    assert_eq!(token_2022_symbol(&data), Some("wSOL".to_string()));
+}
+
+#[test]
+fn token_2022_symbol_ignores_mints_without_metadata() {
+    let mint = Mint {
+        is_initialized: true,
+        ..Default::default()
+    };
+    let mut data = vec![0; Mint::LEN];
+    Mint::pack(mint, &mut data).unwrap();
+
+    assert_eq!(token_2022_symbol(&data), None);
+}
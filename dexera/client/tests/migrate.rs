@@ -0,0 +1,188 @@
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use common::*;
+use dexera::{errors::ErrorCode, Pool, Profile, ProtocolConfig, BASIS_POINTS};
+use dexera_client::{
+    config_address, decode_pool, decode_profile, decode_protocol_config, instructions,
+    instructions::SwapFees, profile_address,
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+// Bytes from the version byte on, which layouts from before versioning lack
+const POOL_TAIL: usize = 1 + 64;
+const CONFIG_TAIL: usize = 1 + 64;
+const PROFILE_TAIL: usize = 1 + 32;
+
+// Pool of the first release: discriminator, creator, the three mints, both bumps and locked
+const BASELINE_POOL_LEN: usize = 8 + 4 * 32 + 3;
+
+// Cuts the account back to its layout from before versioning, rent exempt at that size
+fn downgrade(env: &mut TestEnv, address: &Pubkey, tail: usize) {
+    let mut account = env.svm.get_account(address).unwrap();
+    let old_len = account.data.len() - tail;
+    account.data.truncate(old_len);
+    account.lamports = env.svm.minimum_balance_for_rent_exemption(old_len);
+    env.svm.set_account(*address, account).unwrap();
+}
+
+fn assert_migrated(env: &TestEnv, address: &Pubkey, space: usize) {
+    assert_eq!(env.data(address).len(), space);
+    assert!(env.lamports(address) >= env.svm.minimum_balance_for_rent_exemption(space));
+}
+
+#[test]
+fn migrate_old_pool() {
+    let mut env = TestEnv::new();
+    let admin = env.admin.insecure_clone();
+    let keys = env.keys;
+    let before = env.pool();
+    downgrade(&mut env, &keys.pool, POOL_TAIL);
+
+    env.send(
+        &[instructions::migrate_pool(&admin.pubkey(), &keys.pool)],
+        &[&admin],
+    )
+    .unwrap();
+
+    assert_migrated(&env, &keys.pool, Pool::INIT_SPACE);
+    let pool = decode_pool(&env.data(&keys.pool)).unwrap();
+    assert_eq!(pool.version, Pool::VERSION);
+    assert_eq!(pool.lp_decimals, Pool::LEGACY_LP_DECIMALS);
+    assert_eq!(pool.creator, before.creator);
+    assert_eq!(pool.mint_lp, before.mint_lp);
+    assert_eq!(pool.pool_bump, before.pool_bump);
+
+    let trader = env.new_user(10_000, 0);
+    env.swap(&trader, true, 10_000, 1, SwapFees::default())
+        .unwrap();
+}
+
+#[test]
+fn migrate_baseline_pool() {
+    let mut env = TestEnv::new();
+    let admin = env.admin.insecure_clone();
+    let keys = env.keys;
+    let pool_len = env.data(&keys.pool).len();
+    downgrade(&mut env, &keys.pool, pool_len - BASELINE_POOL_LEN);
+    // Nor did the first release have creator fee vaults or pool stats
+    for account in [keys.creator_fee_vault, keys.pool_stats] {
+        env.svm.set_account(account, Default::default()).unwrap();
+    }
+
+    env.send(
+        &[instructions::migrate_pool(&admin.pubkey(), &keys.pool)],
+        &[&admin],
+    )
+    .unwrap();
+    let payer = env.new_user(0, 0);
+    env.send(
+        &[
+            instructions::init_creator_fee_vault(&keys, &payer.pubkey()),
+            instructions::init_pool_stats(&keys, &payer.pubkey()),
+        ],
+        &[&payer],
+    )
+    .unwrap();
+
+    assert_migrated(&env, &keys.pool, Pool::INIT_SPACE);
+    let pool = decode_pool(&env.data(&keys.pool)).unwrap();
+    assert_eq!(pool.max_price_impact_bps, BASIS_POINTS);
+    assert_eq!(pool.lp_decimals, Pool::LEGACY_LP_DECIMALS);
+
+    let trader = env.new_user(10_000, 0);
+    let quote = env.pool_state().quote_swap(true, 10_000, 0, false).unwrap();
+    assert!(quote.price_impact_bps > 0);
+    env.swap(&trader, true, 10_000, 1, SwapFees::default())
+        .unwrap();
+    assert_eq!(env.pool_stats().swap_count, 1);
+}
+
+#[test]
+fn migrate_old_config() {
+    let mut env = TestEnv::new();
+    let admin = env.admin.insecure_clone();
+    let config = config_address().0;
+    let fee_account = env.protocol_fee_account.pubkey();
+    downgrade(&mut env, &config, CONFIG_TAIL);
+
+    env.send(&[instructions::migrate_config(&admin.pubkey())], &[&admin])
+        .unwrap();
+
+    assert_migrated(&env, &config, ProtocolConfig::INIT_SPACE);
+    let migrated = decode_protocol_config(&env.data(&config)).unwrap();
+    assert_eq!(migrated.version, ProtocolConfig::VERSION);
+    assert_eq!(migrated.admin, admin.pubkey());
+    assert_eq!(migrated.protocol_fee_account, fee_account);
+    assert_eq!(migrated.fee, FEE);
+    assert_eq!(
+        migrated.referrer_fee_share,
+        ProtocolConfig::DEFAULT_REFERRER_FEE_SHARE
+    );
+}
+
+#[test]
+fn migrate_old_profile() {
+    let mut env = TestEnv::new();
+    let admin = env.admin.insecure_clone();
+    let owner = env.new_user(0, 0);
+    env.create_profile(&owner, 7);
+    let profile = profile_address(&owner.pubkey()).0;
+    downgrade(&mut env, &profile, PROFILE_TAIL);
+
+    env.send(
+        &[instructions::migrate_profile(
+            &admin.pubkey(),
+            &owner.pubkey(),
+        )],
+        &[&admin],
+    )
+    .unwrap();
+
+    assert_migrated(&env, &profile, Profile::INIT_SPACE);
+    let migrated = decode_profile(&env.data(&profile)).unwrap();
+    assert_eq!(migrated.version, Profile::VERSION);
+    assert_eq!(migrated.profile_id, 7);
+    assert_eq!(migrated.creator, owner.pubkey());
+}
+
+#[test]
+fn migrate_current_accounts() {
+    let mut env = TestEnv::new();
+    let admin = env.admin.insecure_clone();
+    let owner = env.new_user(0, 0);
+    env.create_profile(&owner, 7);
+
+    for ix in [
+        instructions::migrate_config(&admin.pubkey()),
+        instructions::migrate_pool(&admin.pubkey(), &env.keys.pool),
+        instructions::migrate_profile(&admin.pubkey(), &owner.pubkey()),
+    ] {
+        let result = env.send(&[ix], &[&admin]);
+        assert_error(result, ErrorCode::AlreadyMigrated);
+    }
+}
+
+#[test]
+fn only_admin_migrates() {
+    let mut env = TestEnv::new();
+    let intruder = env.new_user(0, 0);
+
+    let result = env.send(
+        &[instructions::migrate_pool(
+            &intruder.pubkey(),
+            &env.keys.pool,
+        )],
+        &[&intruder],
+    );
+    assert_error(result, AnchorErrorCode::ConstraintHasOne);
+
+    // The old config can only be checked once it is upgraded
+    let config = config_address().0;
+    downgrade(&mut env, &config, CONFIG_TAIL);
+    let result = env.send(
+        &[instructions::migrate_config(&intruder.pubkey())],
+        &[&intruder],
+    );
+    assert_error(result, AnchorErrorCode::ConstraintHasOne);
+}
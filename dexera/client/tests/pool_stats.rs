@@ -0,0 +1,134 @@
+mod common;
+
+use common::*;
+use dexera::{PoolStats, SwapQuote, SECONDS_PER_DAY};
+use dexera_client::instructions::{self, SwapFees};
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+fn quote_with_lp_fee(lp_fee: u64) -> SwapQuote {
+    SwapQuote {
+        amount_in: 1_000,
+        amount_out: 900,
+        fee: 0,
+        referrer_fee: 0,
+        protocol_fee: 0,
+        integrator_fee: 0,
+        creator_fee: 0,
+        lp_fee,
+        price_impact_bps: 0,
+        price_move_bps: 0,
+    }
+}
+
+#[test]
+fn create_pool_starts_empty_stats() {
+    let env = TestEnv::new();
+
+    let stats = env.pool_stats();
+    assert_eq!(stats.pool, env.keys.pool);
+    assert_eq!(stats.swap_count, 0);
+    assert_eq!(stats.volume_x, 0);
+    assert_eq!(stats.day, env.now().div_euclid(SECONDS_PER_DAY));
+}
+
+#[test]
+fn swaps_update_volume_and_fees() {
+    let mut env = TestEnv::new();
+    env.enable_creator_fee(50);
+    let trader = env.new_user(10_000, 20_000);
+
+    let sell_x = env.pool_state().quote_swap(true, 10_000, 0, false).unwrap();
+    env.swap(&trader, true, 10_000, 1, SwapFees::default())
+        .unwrap();
+    let sell_y = env
+        .pool_state()
+        .quote_swap(false, 20_000, 0, false)
+        .unwrap();
+    env.swap(&trader, false, 20_000, 1, SwapFees::default())
+        .unwrap();
+
+    let stats = env.pool_stats();
+    assert_eq!(stats.swap_count, 2);
+    assert_eq!(stats.volume_x, 10_000);
+    assert_eq!(stats.volume_y, 20_000);
+    assert_eq!(stats.day_volume_x, 10_000);
+    assert_eq!(stats.day_volume_y, 20_000);
+    assert_eq!(
+        stats.protocol_fees,
+        (sell_x.protocol_fee + sell_y.protocol_fee) as u128
+    );
+    assert_eq!(
+        stats.creator_fees,
+        (sell_x.creator_fee + sell_y.creator_fee) as u128
+    );
+    assert_eq!(stats.last_trade_timestamp, env.now());
+}
+
+#[test]
+fn daily_bucket_rolls_over() {
+    let mut env = TestEnv::new();
+    let trader = env.new_user(30_000, 0);
+
+    env.swap(&trader, true, 10_000, 1, SwapFees::default())
+        .unwrap();
+    env.warp_by(SECONDS_PER_DAY);
+    env.swap(&trader, true, 5_000, 1, SwapFees::default())
+        .unwrap();
+
+    let stats = env.pool_stats();
+    assert_eq!(stats.previous_day_volume_x, 10_000);
+    assert_eq!(stats.day_volume_x, 5_000);
+    assert_eq!(stats.volume_x, 15_000);
+
+    // A quiet day in between leaves nothing for the day before
+    env.warp_by(2 * SECONDS_PER_DAY);
+    env.swap(&trader, true, 1_000, 1, SwapFees::default())
+        .unwrap();
+
+    let stats = env.pool_stats();
+    assert_eq!(stats.previous_day_volume_x, 0);
+    assert_eq!(stats.day_volume_x, 1_000);
+}
+
+#[test]
+fn old_pools_get_stats_from_anyone() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    // Pools from before the stats existed have no account
+    env.svm
+        .set_account(keys.pool_stats, Default::default())
+        .unwrap();
+    let trader = env.new_user(10_000, 0);
+    assert!(env
+        .swap(&trader, true, 10_000, 1, SwapFees::default())
+        .is_err());
+
+    let payer = env.new_user(0, 0);
+    env.send(
+        &[instructions::init_pool_stats(&keys, &payer.pubkey())],
+        &[&payer],
+    )
+    .unwrap();
+
+    env.swap(&trader, true, 10_000, 1, SwapFees::default())
+        .unwrap();
+    assert_eq!(env.pool_stats().swap_count, 1);
+}
+
+#[test]
+fn apr_comes_from_the_last_complete_day() {
+    let now = 10 * SECONDS_PER_DAY;
+    let mut stats = PoolStats::new(Pubkey::default(), 0, now);
+    stats.record_swap(now, true, &quote_with_lp_fee(100));
+
+    // The day isn't over yet
+    assert_eq!(stats.last_day_lp_fees(now), 0);
+
+    let tomorrow = now + SECONDS_PER_DAY;
+    assert_eq!(stats.last_day_lp_fees(tomorrow), 100);
+    // 100 a day on a pool worth 2 * 365_000
+    assert_eq!(stats.lp_fee_apr_bps(tomorrow, 365_000), 500);
+
+    assert_eq!(stats.last_day_lp_fees(tomorrow + SECONDS_PER_DAY), 0);
+    assert_eq!(stats.lp_fee_apr_bps(tomorrow, 0), 0);
+}
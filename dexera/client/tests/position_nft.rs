@@ -0,0 +1,197 @@
+mod common;
+
+use anchor_spl::token::spl_token;
+use common::*;
+use dexera::errors::ErrorCode;
+use dexera_client::{decode_mint, decode_position, instructions, position_address};
+use litesvm::types::TransactionResult;
+use solana_sdk::signature::{Keypair, Signer};
+
+const WRAPPED: u64 = 400_000;
+
+#[allow(clippy::result_large_err)]
+fn try_wrap(env: &mut TestEnv, owner: &Keypair, lp_amount: u64) -> (Keypair, TransactionResult) {
+    let keys = env.keys;
+    let position_mint = Keypair::new();
+    let result = env.send(
+        &[instructions::wrap_position(
+            &keys,
+            &owner.pubkey(),
+            &position_mint.pubkey(),
+            lp_amount,
+        )],
+        &[owner, &position_mint],
+    );
+
+    (position_mint, result)
+}
+
+fn wrap(env: &mut TestEnv, owner: &Keypair, lp_amount: u64) -> Keypair {
+    let (position_mint, result) = try_wrap(env, owner, lp_amount);
+    result.expect("wrap_position failed");
+
+    position_mint
+}
+
+#[allow(clippy::result_large_err)]
+fn unwrap(env: &mut TestEnv, owner: &Keypair, position_mint: &Keypair) -> TransactionResult {
+    let keys = env.keys;
+    env.send(
+        &[instructions::unwrap_position(
+            &keys,
+            &owner.pubkey(),
+            &position_mint.pubkey(),
+        )],
+        &[owner],
+    )
+}
+
+#[test]
+fn wrapping_locks_the_lp_behind_an_nft() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+
+    let position_mint = wrap(&mut env, &creator, WRAPPED);
+
+    assert_eq!(
+        env.lp_balance(&creator.pubkey()),
+        INITIAL_LIQUIDITY - WRAPPED
+    );
+    assert_eq!(env.balance(&keys.position_vault), WRAPPED);
+    assert_eq!(
+        env.balance_of(&creator.pubkey(), &position_mint.pubkey()),
+        1
+    );
+
+    let mint = decode_mint(&env.data(&position_mint.pubkey())).unwrap();
+    assert_eq!(mint.supply, 1);
+    assert_eq!(mint.decimals, 0);
+    assert!(mint.mint_authority.is_none());
+
+    let position =
+        decode_position(&env.data(&position_address(&position_mint.pubkey()).0)).unwrap();
+    assert_eq!(position.pool, keys.pool);
+    assert_eq!(position.position_mint, position_mint.pubkey());
+    assert_eq!(position.lp_amount, WRAPPED);
+    assert_eq!(position.created_at, env.now());
+}
+
+#[test]
+fn unwrapping_returns_the_lp_and_burns_the_nft() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+    let position_mint = wrap(&mut env, &creator, WRAPPED);
+    let position = position_address(&position_mint.pubkey()).0;
+
+    unwrap(&mut env, &creator, &position_mint).expect("unwrap_position failed");
+
+    assert_eq!(env.lp_balance(&creator.pubkey()), INITIAL_LIQUIDITY);
+    assert_eq!(env.balance(&keys.position_vault), 0);
+    let mint = decode_mint(&env.data(&position_mint.pubkey())).unwrap();
+    assert_eq!(mint.supply, 0);
+    assert_eq!(env.lamports(&position), 0);
+}
+
+#[test]
+fn positions_follow_the_nft() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+    let position_mint = wrap(&mut env, &creator, WRAPPED);
+    let buyer = env.new_user(0, 0);
+    let buyer_ata = env.create_ata(&buyer.pubkey(), &position_mint.pubkey());
+    env.send(
+        &[spl_token::instruction::transfer(
+            &spl_token::ID,
+            &keys.user_ata(&creator.pubkey(), &position_mint.pubkey()),
+            &buyer_ata,
+            &creator.pubkey(),
+            &[],
+            1,
+        )
+        .unwrap()],
+        &[&creator],
+    )
+    .unwrap();
+
+    let result = unwrap(&mut env, &creator, &position_mint);
+    assert_error(result, ErrorCode::NotPositionHolder);
+
+    unwrap(&mut env, &buyer, &position_mint).expect("unwrap_position failed");
+    assert_eq!(env.lp_balance(&buyer.pubkey()), WRAPPED);
+}
+
+#[test]
+fn only_the_holder_unwraps() {
+    let mut env = TestEnv::new();
+    let creator = env.creator.insecure_clone();
+    let position_mint = wrap(&mut env, &creator, WRAPPED);
+    let intruder = env.new_user(0, 0);
+    env.create_ata(&intruder.pubkey(), &position_mint.pubkey());
+
+    let result = unwrap(&mut env, &intruder, &position_mint);
+
+    assert_error(result, ErrorCode::NotPositionHolder);
+    assert_eq!(env.balance(&env.keys.position_vault), WRAPPED);
+}
+
+#[test]
+fn empty_positions_are_rejected() {
+    let mut env = TestEnv::new();
+    let creator = env.creator.insecure_clone();
+
+    let (_, result) = try_wrap(&mut env, &creator, 0);
+
+    assert_error(result, ErrorCode::InvalidAmount);
+}
+
+#[test]
+fn wrapped_lp_keeps_the_pool_open() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+    wrap(&mut env, &creator, INITIAL_LIQUIDITY);
+
+    let result = env.send(
+        &[instructions::close_pool(
+            &keys,
+            &creator.pubkey(),
+            &env.pool(),
+        )],
+        &[&creator],
+    );
+
+    assert_error(result, ErrorCode::PoolNotEmpty);
+}
+
+#[test]
+fn closing_the_pool_closes_the_position_vault() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let creator = env.creator.insecure_clone();
+    let position_mint = wrap(&mut env, &creator, WRAPPED);
+    unwrap(&mut env, &creator, &position_mint).expect("unwrap_position failed");
+    env.send(
+        &[instructions::withdraw_liquidity(
+            &keys,
+            &creator.pubkey(),
+            INITIAL_LIQUIDITY,
+            0,
+            0,
+            None,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    let pool = env.pool();
+    env.send(
+        &[instructions::close_pool(&keys, &creator.pubkey(), &pool)],
+        &[&creator],
+    )
+    .expect("close_pool failed");
+
+    assert_eq!(env.lamports(&keys.position_vault), 0);
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::*;
+use dexera_client::{
+    decode_profile_id_entry, instructions, profile_address, profile_id_address,
+    profile_id_to_referral_code, referral_code_to_profile_id,
+};
+use solana_sdk::signature::Signer;
+
+#[test]
+fn create_profile_reserves_its_id() {
+    let mut env = TestEnv::new();
+    let owner = env.new_user(0, 0);
+
+    env.create_profile(&owner, 7);
+
+    let entry = decode_profile_id_entry(&env.data(&profile_id_address(7).0)).unwrap();
+    assert_eq!(entry.profile_id, 7);
+    assert_eq!(entry.profile, profile_address(&owner.pubkey()).0);
+    assert_eq!(entry.creator, owner.pubkey());
+}
+
+#[test]
+fn profile_ids_are_unique() {
+    let mut env = TestEnv::new();
+    let first = env.new_user(0, 0);
+    let second = env.new_user(0, 0);
+    env.create_profile(&first, 7);
+
+    let result = env.send(
+        &[instructions::create_profile(&second.pubkey(), 7)],
+        &[&second],
+    );
+
+    assert!(result.is_err());
+    assert_eq!(env.lamports(&profile_address(&second.pubkey()).0), 0);
+}
+
+#[test]
+fn older_profiles_register_their_id_once() {
+    let mut env = TestEnv::new();
+    let owner = env.new_user(0, 0);
+    env.create_profile(&owner, 7);
+    // Profiles created before the registry have no entry
+    let entry = profile_id_address(7).0;
+    env.svm.set_account(entry, Default::default()).unwrap();
+
+    env.send(
+        &[instructions::register_profile_id(&owner.pubkey(), 7)],
+        &[&owner],
+    )
+    .unwrap();
+    let again = env.send(
+        &[instructions::register_profile_id(&owner.pubkey(), 7)],
+        &[&owner],
+    );
+
+    assert!(again.is_err());
+    let entry = decode_profile_id_entry(&env.data(&entry)).unwrap();
+    assert_eq!(entry.creator, owner.pubkey());
+}
+
+#[test]
+fn referral_codes_resolve_to_the_referrer() {
+    let mut env = TestEnv::new();
+    let referrer = env.new_user(0, 0);
+    let profile_id = referral_code_to_profile_id("ALICE7").unwrap();
+    env.create_profile(&referrer, profile_id);
+
+    let code_from_link = referral_code_to_profile_id("alice7").unwrap();
+    let entry = decode_profile_id_entry(&env.data(&profile_id_address(code_from_link).0)).unwrap();
+
+    assert_eq!(entry.creator, referrer.pubkey());
+}
+
+#[test]
+fn referral_codes_round_trip() {
+    let profile_id = referral_code_to_profile_id("dex42").unwrap();
+
+    assert_eq!(
+        profile_id_to_referral_code(profile_id),
+        Some("DEX42".to_string())
+    );
+    assert_eq!(profile_id_to_referral_code(7), None);
+    assert_eq!(profile_id_to_referral_code(0), None);
+}
+
+#[test]
+fn referral_codes_are_short_and_alphanumeric() {
+    assert_eq!(referral_code_to_profile_id(""), None);
+    assert_eq!(referral_code_to_profile_id("ABCDEFGHI"), None);
+    assert_eq!(referral_code_to_profile_id("AB-CD"), None);
+    assert!(referral_code_to_profile_id("ABCDEFGH").is_some());
+}
@@ -0,0 +1,204 @@
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use common::*;
+use dexera::errors::ErrorCode;
+use dexera_client::{
+    config_address, decode_profile, decode_protocol_config, instructions, profile_address,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+#[test]
+fn initialize_protocol_only_once() {
+    let mut env = TestEnv::with_protocol();
+    let admin = env.admin.insecure_clone();
+
+    let result = env.send(
+        &[instructions::initialize_protocol(
+            &admin.pubkey(),
+            &Keypair::new().pubkey(),
+            FEE,
+            MAX_INTEGRATOR_FEE,
+        )],
+        &[&admin],
+    );
+
+    assert!(result.is_err());
+    let config = decode_protocol_config(&env.data(&config_address().0)).unwrap();
+    assert_eq!(
+        config.protocol_fee_account,
+        env.protocol_fee_account.pubkey()
+    );
+}
+
+#[test]
+fn update_protocol_fee_account() {
+    let mut env = TestEnv::with_protocol();
+    let admin = env.admin.insecure_clone();
+    let new_fee_account = Keypair::new().pubkey();
+
+    env.send(
+        &[instructions::update_protocol_fee_account(
+            &admin.pubkey(),
+            &new_fee_account,
+        )],
+        &[&admin],
+    )
+    .unwrap();
+
+    let config = decode_protocol_config(&env.data(&config_address().0)).unwrap();
+    assert_eq!(config.protocol_fee_account, new_fee_account);
+}
+
+#[test]
+fn update_max_integrator_fee() {
+    let mut env = TestEnv::with_protocol();
+    let admin = env.admin.insecure_clone();
+    let fee_account = env.protocol_fee_account.pubkey();
+
+    env.send(
+        &[instructions::update_max_integrator_fee(
+            &admin.pubkey(),
+            &fee_account,
+            25,
+        )],
+        &[&admin],
+    )
+    .unwrap();
+
+    let config = decode_protocol_config(&env.data(&config_address().0)).unwrap();
+    assert_eq!(config.max_integrator_fee, 25);
+
+    let result = env.send(
+        &[instructions::update_max_integrator_fee(
+            &admin.pubkey(),
+            &fee_account,
+            10_001,
+        )],
+        &[&admin],
+    );
+    assert_error(result, ErrorCode::InvalidConfig);
+}
+
+#[test]
+fn update_max_creator_fee() {
+    let mut env = TestEnv::with_protocol();
+    let admin = env.admin.insecure_clone();
+    let fee_account = env.protocol_fee_account.pubkey();
+
+    env.send(
+        &[instructions::update_max_creator_fee(
+            &admin.pubkey(),
+            &fee_account,
+            50,
+        )],
+        &[&admin],
+    )
+    .unwrap();
+
+    let config = decode_protocol_config(&env.data(&config_address().0)).unwrap();
+    assert_eq!(config.max_creator_fee, 50);
+
+    let result = env.send(
+        &[instructions::update_max_creator_fee(
+            &admin.pubkey(),
+            &fee_account,
+            10_001,
+        )],
+        &[&admin],
+    );
+    assert_error(result, ErrorCode::InvalidConfig);
+}
+
+#[test]
+fn only_admin_updates_config() {
+    let mut env = TestEnv::with_protocol();
+    let intruder = env.new_user(0, 0);
+
+    for ix in [
+        instructions::update_protocol_fee_account(&intruder.pubkey(), &intruder.pubkey()),
+        instructions::update_max_creator_fee(&intruder.pubkey(), &intruder.pubkey(), 0),
+    ] {
+        let result = env.send(&[ix], &[&intruder]);
+        assert_error(result, AnchorErrorCode::ConstraintHasOne);
+    }
+}
+
+#[test]
+fn only_admin_manages_pool_creation() {
+    let mut env = TestEnv::with_protocol();
+    let intruder = env.new_user(0, 0);
+
+    for ix in [
+        instructions::update_pool_creation_fee(&intruder.pubkey(), &intruder.pubkey(), 0),
+        instructions::update_permissioned_pool_creation(
+            &intruder.pubkey(),
+            &intruder.pubkey(),
+            false,
+        ),
+        instructions::add_to_allowlist(&intruder.pubkey(), &intruder.pubkey()),
+    ] {
+        let result = env.send(&[ix], &[&intruder]);
+        assert_error(result, AnchorErrorCode::ConstraintHasOne);
+    }
+}
+
+#[test]
+fn create_profile_expires_after_a_month() {
+    let mut env = TestEnv::with_protocol();
+    let owner = env.new_user(0, 0);
+
+    env.create_profile(&owner, 7);
+
+    let profile = decode_profile(&env.data(&profile_address(&owner.pubkey()).0)).unwrap();
+    assert_eq!(profile.profile_id, 7);
+    assert_eq!(profile.creator, owner.pubkey());
+    assert_eq!(
+        profile.expiration_timestamp - profile.created_timestamp,
+        ONE_MONTH
+    );
+    assert!(!profile.locked);
+}
+
+#[test]
+fn create_profile_only_once() {
+    let mut env = TestEnv::with_protocol();
+    let owner = env.new_user(0, 0);
+    env.create_profile(&owner, 7);
+
+    let result = env.send(
+        &[instructions::create_profile(&owner.pubkey(), 8)],
+        &[&owner],
+    );
+
+    assert!(result.is_err());
+    let profile = decode_profile(&env.data(&profile_address(&owner.pubkey()).0)).unwrap();
+    assert_eq!(profile.profile_id, 7);
+}
+
+#[test]
+fn only_admin_updates_pool_config() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let admin = env.admin.insecure_clone();
+    let intruder = env.new_user(0, 0);
+
+    for ix in [
+        instructions::update_pool_max_price_impact(&keys, &intruder.pubkey(), 100),
+        instructions::update_pool_dynamic_fee(&keys, &intruder.pubkey(), 100, 10_000, 600),
+    ] {
+        let result = env.send(&[ix], &[&intruder]);
+        assert_error(result, AnchorErrorCode::ConstraintHasOne);
+    }
+
+    let result = env.send(
+        &[instructions::update_pool_max_price_impact(
+            &keys,
+            &admin.pubkey(),
+            10_001,
+        )],
+        &[&admin],
+    );
+    assert_error(result, ErrorCode::InvalidConfig);
+    assert_eq!(env.pool().max_price_impact_bps, 10_000);
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use common::*;
+use dexera::{errors::ErrorCode, LiquidityQuote};
+use dexera_client::{
+    config_address, decode_protocol_config, instructions, instructions::SwapFees, treasury_address,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+const COMPOUND: SwapFees = SwapFees {
+    referrer: None,
+    integrator_ata: None,
+    integrator_fee: 0,
+    compound_protocol_fees: true,
+    user_profile: false,
+};
+
+fn enable_compounding(env: &mut TestEnv) {
+    let admin = env.admin.insecure_clone();
+    env.send(
+        &[instructions::update_compound_protocol_fees(
+            &admin.pubkey(),
+            &env.protocol_fee_account.pubkey(),
+            true,
+        )],
+        &[&admin],
+    )
+    .expect("update_compound_protocol_fees failed");
+}
+
+// Sells X with compounding on and returns the LP tokens the treasury got
+fn compound_swap(env: &mut TestEnv, trader: &Keypair, amount_in: u64) -> u64 {
+    let treasury = treasury_address().0;
+    let before = env.lp_balance(&treasury);
+    env.swap(trader, true, amount_in, 1, COMPOUND).unwrap();
+
+    env.lp_balance(&treasury) - before
+}
+
+#[test]
+fn admin_enables_compounding() {
+    let mut env = TestEnv::new();
+
+    enable_compounding(&mut env);
+
+    let config = decode_protocol_config(&env.data(&config_address().0)).unwrap();
+    assert!(config.compound_protocol_fees);
+    assert_eq!(config.treasury_bump, treasury_address().1);
+}
+
+#[test]
+fn protocol_fee_is_compounded_into_the_pool() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    enable_compounding(&mut env);
+    let trader = env.new_user(100_000, 0);
+
+    let before = env.pool_state();
+    let quote = before.quote_swap(true, 100_000, 0, false).unwrap();
+    let protocol_fees = env.protocol_fee_balance();
+    let minted = compound_swap(&mut env, &trader, 100_000);
+
+    let vault_y = env.balance(&keys.vault_y);
+    let expected = LiquidityQuote::compound(
+        vault_y - quote.protocol_fee,
+        before.lp_supply,
+        quote.protocol_fee,
+    )
+    .unwrap();
+    assert!(quote.protocol_fee > 0);
+    assert_eq!(minted, expected.lp_tokens_amount);
+    assert!(minted > 0);
+    assert_eq!(env.protocol_fee_balance(), protocol_fees);
+    // The fee stays in the vault instead of going to the protocol fee account
+    assert_eq!(vault_y, before.vault_y - quote.amount_out);
+    assert_eq!(env.pool_state().lp_supply, before.lp_supply + minted);
+}
+
+#[test]
+fn compounding_needs_the_treasury_accounts() {
+    let mut env = TestEnv::new();
+    enable_compounding(&mut env);
+    let trader = env.new_user(10_000, 0);
+
+    let result = env.swap(&trader, true, 10_000, 1, SwapFees::default());
+
+    assert_error(result, ErrorCode::InvalidTreasury);
+}
+
+#[test]
+fn admin_withdraws_protocol_liquidity() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    enable_compounding(&mut env);
+    let trader = env.new_user(100_000, 0);
+    let minted = compound_swap(&mut env, &trader, 100_000);
+
+    let admin = env.admin.insecure_clone();
+    let fee_account = env.protocol_fee_account.pubkey();
+    let expected = env.pool_state().quote_withdraw(minted).unwrap();
+    let protocol_x = env.balance_of(&fee_account, &keys.mint_x);
+    let protocol_y = env.protocol_fee_balance();
+    env.send(
+        &[instructions::withdraw_protocol_liquidity(
+            &keys,
+            &admin.pubkey(),
+            &fee_account,
+            minted,
+            expected.x,
+            expected.y,
+        )],
+        &[&admin],
+    )
+    .unwrap();
+
+    assert_eq!(env.lp_balance(&treasury_address().0), 0);
+    assert_eq!(
+        env.balance_of(&fee_account, &keys.mint_x),
+        protocol_x + expected.x
+    );
+    assert_eq!(env.protocol_fee_balance(), protocol_y + expected.y);
+}
+
+#[test]
+fn only_admin_withdraws_protocol_liquidity() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    enable_compounding(&mut env);
+    let trader = env.new_user(100_000, 0);
+    let minted = compound_swap(&mut env, &trader, 100_000);
+
+    let intruder = env.new_user(0, 0);
+    let result = env.send(
+        &[instructions::withdraw_protocol_liquidity(
+            &keys,
+            &intruder.pubkey(),
+            &env.protocol_fee_account.pubkey(),
+            minted,
+            0,
+            0,
+        )],
+        &[&intruder],
+    );
+
+    assert_error(result, AnchorErrorCode::ConstraintHasOne);
+}
+
+#[test]
+fn admin_redirects_protocol_liquidity() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    enable_compounding(&mut env);
+    let trader = env.new_user(100_000, 0);
+    let minted = compound_swap(&mut env, &trader, 100_000);
+
+    let admin = env.admin.insecure_clone();
+    let recipient = Keypair::new();
+    env.send(
+        &[instructions::redirect_protocol_liquidity(
+            &keys,
+            &admin.pubkey(),
+            &recipient.pubkey(),
+            minted,
+        )],
+        &[&admin],
+    )
+    .unwrap();
+
+    assert_eq!(env.lp_balance(&treasury_address().0), 0);
+    assert_eq!(env.lp_balance(&recipient.pubkey()), minted);
+}
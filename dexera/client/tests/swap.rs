@@ -0,0 +1,380 @@
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use common::*;
+use dexera::errors::ErrorCode;
+use dexera_client::{instructions, instructions::SwapFees, profile_address};
+use solana_sdk::signature::{Keypair, Signer};
+
+#[test]
+fn swap_exact_in_sells_x_for_y() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let trader = env.new_user(100_000, 0);
+    let quote = env.pool_state().quote_swap(true, 40_000, 0, false).unwrap();
+
+    env.swap(&trader, true, 40_000, quote.amount_out, SwapFees::default())
+        .unwrap();
+
+    assert_eq!(env.balance_of(&trader.pubkey(), &keys.mint_x), 60_000);
+    assert_eq!(
+        env.balance_of(&trader.pubkey(), &keys.mint_y),
+        quote.amount_out
+    );
+    assert_eq!(env.balance(&keys.vault_x), INITIAL_LIQUIDITY + 40_000);
+    assert_eq!(
+        env.balance(&keys.vault_y),
+        INITIAL_LIQUIDITY - quote.amount_out - quote.protocol_fee
+    );
+    assert_eq!(env.protocol_fee_balance(), quote.protocol_fee);
+    assert_eq!(quote.referrer_fee, 0);
+}
+
+#[test]
+fn swap_exact_out_sells_y_for_x() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let trader = env.new_user(0, 100_000);
+    let quote = env
+        .pool_state()
+        .quote_swap(false, 40_000, 0, false)
+        .unwrap();
+
+    env.swap(
+        &trader,
+        false,
+        40_000,
+        quote.amount_out,
+        SwapFees::default(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        env.balance_of(&trader.pubkey(), &keys.mint_x),
+        quote.amount_out
+    );
+    assert_eq!(env.balance_of(&trader.pubkey(), &keys.mint_y), 60_000);
+    assert_eq!(
+        env.balance(&keys.vault_x),
+        INITIAL_LIQUIDITY - quote.amount_out
+    );
+    assert_eq!(
+        env.balance(&keys.vault_y),
+        INITIAL_LIQUIDITY + 40_000 - quote.protocol_fee
+    );
+    assert_eq!(env.protocol_fee_balance(), quote.protocol_fee);
+}
+
+#[test]
+fn referred_swaps_pay_the_referrer() {
+    for is_x in [true, false] {
+        let mut env = TestEnv::new();
+        let keys = env.keys;
+        let referrer = env.new_user(0, 0);
+        env.create_profile(&referrer, 1);
+        let trader = env.new_user(100_000, 100_000);
+        let quote = env.pool_state().quote_swap(is_x, 40_000, 0, true).unwrap();
+
+        env.swap(
+            &trader,
+            is_x,
+            40_000,
+            quote.amount_out,
+            SwapFees {
+                referrer: Some(referrer.pubkey()),
+                ..SwapFees::default()
+            },
+        )
+        .unwrap();
+
+        assert!(quote.referrer_fee > 0);
+        assert_eq!(
+            env.balance_of(&referrer.pubkey(), &keys.mint_y),
+            quote.referrer_fee
+        );
+        assert_eq!(env.protocol_fee_balance(), quote.protocol_fee);
+    }
+}
+
+#[test]
+fn swaps_create_the_protocol_fee_ata() {
+    let mut env = TestEnv::new();
+    let admin = env.admin.insecure_clone();
+    let fee_account = Keypair::new();
+    env.send(
+        &[instructions::update_protocol_fee_account(
+            &admin.pubkey(),
+            &fee_account.pubkey(),
+        )],
+        &[&admin],
+    )
+    .unwrap();
+    env.protocol_fee_account = fee_account;
+    let trader = env.new_user(100_000, 0);
+    let quote = env.pool_state().quote_swap(true, 40_000, 0, false).unwrap();
+
+    env.swap(&trader, true, 40_000, 1, SwapFees::default())
+        .unwrap();
+
+    assert!(quote.protocol_fee > 0);
+    assert_eq!(env.protocol_fee_balance(), quote.protocol_fee);
+}
+
+#[test]
+fn integrator_is_paid_in_the_input_mint() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let integrator = env.new_user(0, 0);
+    let integrator_ata = keys.user_ata(&integrator.pubkey(), &keys.mint_x);
+    let trader = env.new_user(100_000, 0);
+    let quote = env
+        .pool_state()
+        .quote_swap(true, 40_000, MAX_INTEGRATOR_FEE, false)
+        .unwrap();
+
+    env.swap(
+        &trader,
+        true,
+        40_000,
+        quote.amount_out,
+        SwapFees {
+            integrator_ata: Some(integrator_ata),
+            integrator_fee: MAX_INTEGRATOR_FEE,
+            ..SwapFees::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(quote.integrator_fee, 400);
+    assert_eq!(env.balance(&integrator_ata), quote.integrator_fee);
+    assert_eq!(env.balance_of(&trader.pubkey(), &keys.mint_x), 60_000);
+    assert_eq!(
+        env.balance(&keys.vault_x),
+        INITIAL_LIQUIDITY + 40_000 - quote.integrator_fee
+    );
+}
+
+#[test]
+fn integrator_fee_above_maximum() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let integrator = env.new_user(0, 0);
+    let trader = env.new_user(100_000, 0);
+
+    let result = env.swap(
+        &trader,
+        true,
+        40_000,
+        1,
+        SwapFees {
+            integrator_ata: Some(keys.user_ata(&integrator.pubkey(), &keys.mint_x)),
+            integrator_fee: MAX_INTEGRATOR_FEE + 1,
+            ..SwapFees::default()
+        },
+    );
+
+    assert_error(result, ErrorCode::IntegratorFeeTooHigh);
+}
+
+#[test]
+fn swap_beyond_slippage() {
+    for is_x in [true, false] {
+        let mut env = TestEnv::new();
+        let trader = env.new_user(100_000, 100_000);
+        let quote = env.pool_state().quote_swap(is_x, 40_000, 0, false).unwrap();
+
+        let result = env.swap(
+            &trader,
+            is_x,
+            40_000,
+            quote.amount_out + 1,
+            SwapFees::default(),
+        );
+
+        assert_error(result, ErrorCode::SlippageExceeded);
+    }
+}
+
+#[test]
+fn swap_without_minimum_output() {
+    let mut env = TestEnv::new();
+    let trader = env.new_user(100_000, 100_000);
+
+    for is_x in [true, false] {
+        let result = env.swap(&trader, is_x, 40_000, 0, SwapFees::default());
+        assert_error(result, ErrorCode::InvalidAmount);
+    }
+}
+
+#[test]
+fn swap_in_locked_pool() {
+    let mut env = TestEnv::new();
+    let trader = env.new_user(100_000, 100_000);
+    env.set_pool(|pool| pool.locked = true);
+
+    for is_x in [true, false] {
+        let result = env.swap(&trader, is_x, 40_000, 1, SwapFees::default());
+        assert_error(result, ErrorCode::AMMLocked);
+    }
+}
+
+#[test]
+fn referrer_without_profile() {
+    let mut env = TestEnv::new();
+    let referrer = env.new_user(0, 0);
+    let trader = env.new_user(100_000, 0);
+
+    let result = env.swap(
+        &trader,
+        true,
+        40_000,
+        1,
+        SwapFees {
+            referrer: Some(referrer.pubkey()),
+            ..SwapFees::default()
+        },
+    );
+
+    assert_error(result, AnchorErrorCode::AccountNotInitialized);
+}
+
+#[test]
+fn referrer_with_expired_profile() {
+    let mut env = TestEnv::new();
+    let referrer = env.new_user(0, 0);
+    env.create_profile(&referrer, 1);
+    let trader = env.new_user(100_000, 0);
+    env.warp_by(ONE_MONTH + 1);
+
+    let result = env.swap(
+        &trader,
+        true,
+        40_000,
+        1,
+        SwapFees {
+            referrer: Some(referrer.pubkey()),
+            ..SwapFees::default()
+        },
+    );
+
+    assert_error(result, ErrorCode::InvalidReferrerProfile);
+}
+
+#[test]
+fn referrer_must_own_the_profile() {
+    let mut env = TestEnv::new();
+    let referrer = env.new_user(0, 0);
+    let other = env.new_user(0, 0);
+    env.create_profile(&other, 2);
+    let trader = env.new_user(100_000, 0);
+
+    // Someone else's valid profile paired with the referrer's wallet
+    let mut ix = instructions::swap_exact_in(
+        &env.keys,
+        &trader.pubkey(),
+        &env.protocol_fee_account.pubkey(),
+        40_000,
+        1,
+        SwapFees {
+            referrer: Some(referrer.pubkey()),
+            ..SwapFees::default()
+        },
+        None,
+    );
+    let referrer_profile = profile_address(&referrer.pubkey()).0;
+    for meta in ix.accounts.iter_mut() {
+        if meta.pubkey == referrer_profile {
+            meta.pubkey = profile_address(&other.pubkey()).0;
+        }
+    }
+
+    let result = env.send(&[ix], &[&trader]);
+
+    assert_error(result, ErrorCode::ReferrerMismatch);
+}
+
+#[test]
+fn traders_cant_refer_themselves() {
+    let mut env = TestEnv::new();
+    let trader = env.new_user(100_000, 0);
+    env.create_profile(&trader, 1);
+
+    let result = env.swap(
+        &trader,
+        true,
+        40_000,
+        1,
+        SwapFees {
+            referrer: Some(trader.pubkey()),
+            ..SwapFees::default()
+        },
+    );
+
+    assert_error(result, ErrorCode::SelfReferral);
+}
+
+#[test]
+fn swap_after_deadline() {
+    let mut env = TestEnv::new();
+    let trader = env.new_user(100_000, 100_000);
+    let deadline = env.now() + 60;
+
+    let swap = |env: &TestEnv, is_x: bool| {
+        let build = if is_x {
+            instructions::swap_exact_in
+        } else {
+            instructions::swap_exact_out
+        };
+        build(
+            &env.keys,
+            &trader.pubkey(),
+            &env.protocol_fee_account.pubkey(),
+            10_000,
+            1,
+            SwapFees::default(),
+            Some(deadline),
+        )
+    };
+
+    for is_x in [true, false] {
+        let ix = swap(&env, is_x);
+        env.send(&[ix], &[&trader]).unwrap();
+    }
+
+    env.warp_by(61);
+    for is_x in [true, false] {
+        let ix = swap(&env, is_x);
+        let result = env.send(&[ix], &[&trader]);
+        assert_error(result, ErrorCode::DeadlineExceeded);
+    }
+}
+
+#[test]
+fn swap_above_max_price_impact() {
+    let mut env = TestEnv::new();
+    let trader = env.new_user(100_000, 100_000);
+    let admin = env.admin.insecure_clone();
+    env.send(
+        &[instructions::update_pool_max_price_impact(
+            &env.keys,
+            &admin.pubkey(),
+            100,
+        )],
+        &[&admin],
+    )
+    .unwrap();
+    assert_eq!(env.pool().max_price_impact_bps, 100);
+
+    for is_x in [true, false] {
+        // Around 0.5% of the reserves moves the price by roughly as much
+        let small = env.pool_state().quote_swap(is_x, 5_000, 0, false).unwrap();
+        assert!(small.price_impact_bps <= 100);
+        env.swap(&trader, is_x, 5_000, 1, SwapFees::default())
+            .unwrap();
+
+        let large = env.pool_state().quote_swap(is_x, 50_000, 0, false).unwrap();
+        assert!(large.price_impact_bps > 100);
+        let result = env.swap(&trader, is_x, 50_000, 1, SwapFees::default());
+        assert_error(result, ErrorCode::PriceImpactTooHigh);
+    }
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use anchor_spl::token::spl_token;
+use common::*;
+use dexera::{errors::ErrorCode, TokenStatus};
+use dexera_client::{decode_token_registry_entry, instructions, token_registry_address, PoolKeys};
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+fn register(env: &mut TestEnv, mints: &[Pubkey], status: TokenStatus) {
+    let admin = env.admin.insecure_clone();
+    let ixs: Vec<_> = mints
+        .iter()
+        .map(|mint| instructions::register_token(&admin.pubkey(), mint, status))
+        .collect();
+    env.send(&ixs, &[&admin]).unwrap();
+}
+
+#[test]
+fn register_and_unregister_token() {
+    let mut env = TestEnv::with_protocol();
+    let admin = env.admin.insecure_clone();
+    let mint = env.keys.mint_x;
+    let entry = token_registry_address(&mint).0;
+
+    register(&mut env, &[mint], TokenStatus::Verified);
+    register(&mut env, &[mint], TokenStatus::Blocked);
+
+    let registered = decode_token_registry_entry(&env.data(&entry)).unwrap();
+    assert_eq!(registered.mint, mint);
+    assert_eq!(registered.status, TokenStatus::Blocked);
+
+    env.send(
+        &[instructions::unregister_token(&admin.pubkey(), &mint)],
+        &[&admin],
+    )
+    .unwrap();
+    assert_eq!(env.lamports(&entry), 0);
+}
+
+#[test]
+fn only_admin_manages_the_registry() {
+    let mut env = TestEnv::new();
+    let intruder = env.new_user(0, 0);
+    let mint = env.keys.mint_x;
+
+    for ix in [
+        instructions::register_token(&intruder.pubkey(), &mint, TokenStatus::Blocked),
+        instructions::update_pool_verified(&env.keys, &intruder.pubkey(), true),
+    ] {
+        let result = env.send(&[ix], &[&intruder]);
+        assert_error(result, AnchorErrorCode::ConstraintHasOne);
+    }
+}
+
+#[test]
+fn create_pool_with_blocked_token() {
+    let mut env = TestEnv::with_protocol();
+    let mint_y = env.keys.mint_y;
+    register(&mut env, &[mint_y], TokenStatus::Blocked);
+    let creator = env.new_user(INITIAL_LIQUIDITY, INITIAL_LIQUIDITY);
+
+    let result = env.create_pool(&creator, None);
+
+    assert_error(result, ErrorCode::TokenBlocked);
+}
+
+#[test]
+fn create_pool_with_freeze_authority_needs_verification() {
+    let mut env = TestEnv::with_protocol();
+    let authority = env.mint_authority.pubkey();
+    let freezable = env.create_mint_with(Some(&authority));
+    let fee_account = env.protocol_fee_account.pubkey();
+    env.keys = PoolKeys::new(freezable, env.keys.mint_y, spl_token::ID, &fee_account);
+    let creator = env.new_user(INITIAL_LIQUIDITY, INITIAL_LIQUIDITY);
+
+    let result = env.create_pool(&creator, None);
+    assert_error(result, ErrorCode::UnsafeMintAuthority);
+
+    register(&mut env, &[freezable], TokenStatus::Verified);
+    env.create_pool(&creator, None).unwrap();
+
+    // Only one side of the pair is verified
+    assert!(!env.pool().verified);
+}
+
+#[test]
+fn pool_of_verified_tokens_is_verified() {
+    let mut env = TestEnv::with_protocol();
+    let admin = env.admin.insecure_clone();
+    let keys = env.keys;
+    register(&mut env, &[keys.mint_x, keys.mint_y], TokenStatus::Verified);
+    let creator = env.new_user(INITIAL_LIQUIDITY, INITIAL_LIQUIDITY);
+
+    env.create_pool(&creator, None).unwrap();
+    assert!(env.pool().verified);
+
+    env.send(
+        &[instructions::update_pool_verified(
+            &keys,
+            &admin.pubkey(),
+            false,
+        )],
+        &[&admin],
+    )
+    .unwrap();
+    assert!(!env.pool().verified);
+}
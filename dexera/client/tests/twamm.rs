@@ -0,0 +1,456 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use dexera::{errors::ErrorCode, SwapQuote, Twamm, TwammOrder, TWAMM_INTERVAL};
+use dexera_client::{
+    decode_twamm, decode_twamm_order, instructions, instructions::SwapFees, twamm_order_address,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+const SELL_RATE: u64 = 10;
+
+// Sells X from the current slot until the end of the second interval
+fn place_order(env: &mut TestEnv, seller: &Keypair, order_id: u64) {
+    let keys = env.keys;
+    env.send(
+        &[instructions::place_twamm_order(
+            &keys,
+            &seller.pubkey(),
+            order_id,
+            true,
+            SELL_RATE,
+            2,
+        )],
+        &[seller],
+    )
+    .expect("place_twamm_order failed");
+    env.keys = keys.with_twamm(true);
+}
+
+fn twamm(env: &TestEnv) -> Twamm {
+    decode_twamm(&env.data(&env.keys.twamm)).unwrap()
+}
+
+fn order(env: &TestEnv, owner: &Keypair, order_id: u64) -> TwammOrder {
+    let address = twamm_order_address(&env.keys.pool, &owner.pubkey(), order_id).0;
+
+    decode_twamm_order(&env.data(&address)).unwrap()
+}
+
+// Pure state with one order selling X from slot 0 until the end of interval `intervals`
+fn twamm_with_order(is_x: bool, intervals: u64) -> (Twamm, TwammOrder) {
+    let mut twamm = Twamm::new(Pubkey::default(), 0, 0);
+    twamm.add_order(is_x, SELL_RATE, intervals).unwrap();
+    let order = TwammOrder {
+        pool: Pubkey::default(),
+        owner: Pubkey::default(),
+        order_id: 0,
+        is_x,
+        sell_rate: SELL_RATE,
+        start_slot: 0,
+        end_slot: intervals * TWAMM_INTERVAL,
+        earnings_per_rate: 0,
+        twamm_order_bump: 0,
+    };
+
+    (twamm, order)
+}
+
+#[test]
+fn orders_sell_at_a_constant_rate() {
+    let (mut twamm, order) = twamm_with_order(true, 2);
+
+    let execution = twamm
+        .execute(
+            synthetic_pool(1_000_000, 1_000_000, 1_000_000).snapshot(),
+            TWAMM_INTERVAL,
+        )
+        .unwrap();
+
+    assert_eq!(execution.x_in, SELL_RATE * TWAMM_INTERVAL);
+    assert_eq!(execution.vault_x, 1_000_000 + execution.x_in);
+    assert!(execution.y_out > 0);
+    // Proceeds round down, the dust stays in the vault
+    let proceeds = twamm.proceeds(&order).unwrap();
+    assert!(proceeds <= execution.y_out && proceeds + 1 >= execution.y_out);
+    assert_eq!(twamm.unsold(&order), SELL_RATE * TWAMM_INTERVAL);
+}
+
+#[test]
+fn orders_stop_selling_at_their_end() {
+    let (mut twamm, order) = twamm_with_order(true, 1);
+    let pool = synthetic_pool(1_000_000, 1_000_000, 1_000_000);
+
+    let execution = twamm.execute(pool.snapshot(), 3 * TWAMM_INTERVAL).unwrap();
+    let proceeds = twamm.proceeds(&order).unwrap();
+
+    assert_eq!(execution.x_in, SELL_RATE * TWAMM_INTERVAL);
+    assert_eq!(twamm.sell_rate_x, 0);
+    assert_eq!(twamm.last_executed_slot, 3 * TWAMM_INTERVAL);
+    assert_eq!(twamm.unsold(&order), 0);
+    // Later orders don't change what an ended one is owed
+    twamm.add_order(true, SELL_RATE, 4).unwrap();
+    twamm.execute(pool.snapshot(), 4 * TWAMM_INTERVAL).unwrap();
+    assert_eq!(twamm.proceeds(&order).unwrap(), proceeds);
+}
+
+#[test]
+fn fees_are_taken_out_of_the_pool() {
+    let (mut twamm, _) = twamm_with_order(true, 1);
+
+    let execution = twamm
+        .execute(
+            synthetic_pool(1_000_000, 1_000_000, 1_000_000).snapshot(),
+            TWAMM_INTERVAL,
+        )
+        .unwrap();
+
+    assert!(execution.fee > 0);
+    assert_eq!(
+        execution.vault_y + execution.y_out + execution.fee + execution.creator_fee,
+        1_000_000
+    );
+}
+
+#[test]
+fn opposite_orders_trade_with_each_other_first() {
+    let pool = synthetic_pool(1_000_000, 1_000_000, 1_000_000);
+    let (mut one_sided, _) = twamm_with_order(true, 1);
+    let (mut both_sides, _) = twamm_with_order(true, 1);
+    both_sides.add_order(false, SELL_RATE, 1).unwrap();
+
+    let one_sided = one_sided.execute(pool.snapshot(), TWAMM_INTERVAL).unwrap();
+    let both_sides = both_sides.execute(pool.snapshot(), TWAMM_INTERVAL).unwrap();
+
+    assert!(both_sides.vault_x - 1_000_000 < (one_sided.vault_x - 1_000_000) / 10);
+    assert!(both_sides.x_out > 0 && both_sides.y_out > 0);
+}
+
+#[test]
+fn locked_pools_stall_the_orders() {
+    let (mut twamm, order) = twamm_with_order(true, 2);
+    let mut pool = synthetic_pool(1_000_000, 1_000_000, 1_000_000);
+    pool.pool.locked = true;
+
+    let execution = twamm.execute(pool.snapshot(), TWAMM_INTERVAL).unwrap();
+
+    assert_eq!(execution.x_in, 0);
+    assert_eq!(twamm.last_executed_slot, 0);
+    assert_eq!(twamm.unsold(&order), SELL_RATE * 2 * TWAMM_INTERVAL);
+}
+
+#[test]
+fn durations_are_capped() {
+    let mut twamm = Twamm::new(Pubkey::default(), 0, 0);
+
+    assert_eq!(
+        twamm.add_order(true, SELL_RATE, 0).unwrap_err(),
+        ErrorCode::InvalidTwammDuration.into()
+    );
+    assert_eq!(
+        twamm.add_order(true, SELL_RATE, 64).unwrap_err(),
+        ErrorCode::InvalidTwammDuration.into()
+    );
+    assert_eq!(
+        twamm.add_order(true, SELL_RATE, 63).unwrap(),
+        SELL_RATE * 63 * TWAMM_INTERVAL
+    );
+}
+
+#[test]
+fn expiries_are_reused_once_their_orders_close() {
+    let (mut twamm, order) = twamm_with_order(true, 1);
+    let pool = synthetic_pool(1_000_000, 1_000_000, 1_000_000);
+    twamm.execute(pool.snapshot(), 2 * TWAMM_INTERVAL).unwrap();
+
+    // Interval 65 shares interval 1's entry
+    assert_eq!(
+        twamm.add_order(true, SELL_RATE, 65).unwrap_err(),
+        ErrorCode::TwammExpiryBusy.into()
+    );
+    twamm.remove_order(&order);
+    assert!(twamm.add_order(true, SELL_RATE, 65).is_ok());
+}
+
+#[test]
+fn orders_execute_lazily() {
+    let mut env = TestEnv::new();
+    let seller = env.new_user(100_000, 0);
+    place_order(&mut env, &seller, 1);
+    assert_eq!(
+        env.balance_of(&seller.pubkey(), &env.keys.mint_x),
+        100_000 - 2 * SELL_RATE * TWAMM_INTERVAL
+    );
+
+    env.warp_slots(TWAMM_INTERVAL);
+    let mut expected = env.pool_state();
+    let mut twamm_state = twamm(&env);
+    let execution = expected
+        .execute_twamm(&mut twamm_state, env.slot())
+        .unwrap();
+    let keeper = env.new_user(0, 0);
+    let keys = env.keys;
+    env.send(&[instructions::execute_twamm(&keys)], &[&keeper])
+        .unwrap();
+
+    let pool = env.pool_state();
+    assert_eq!(execution.x_in, SELL_RATE * TWAMM_INTERVAL);
+    assert_eq!(
+        (pool.vault_x, pool.vault_y),
+        (expected.vault_x, expected.vault_y)
+    );
+    assert_eq!(twamm(&env).last_executed_slot, env.slot());
+}
+
+#[test]
+fn fills_pay_the_protocol_and_the_creator() {
+    let mut env = TestEnv::new();
+    env.enable_creator_fee(30);
+    let seller = env.new_user(100_000, 0);
+    place_order(&mut env, &seller, 1);
+    let keys = env.keys;
+    let protocol_fees = env.balance(&keys.protocol_fee_ata);
+    let stats = env.pool_stats();
+
+    env.warp_slots(TWAMM_INTERVAL);
+    let mut expected = env.pool_state();
+    let execution = expected
+        .execute_twamm(&mut twamm(&env), env.slot())
+        .unwrap();
+    let keeper = env.new_user(0, 0);
+    env.send(&[instructions::execute_twamm(&keys)], &[&keeper])
+        .unwrap();
+
+    assert!(execution.fee > 0 && execution.creator_fee > 0);
+    assert_eq!(
+        env.balance(&keys.protocol_fee_ata),
+        protocol_fees + execution.fee
+    );
+    assert_eq!(env.balance(&keys.creator_fee_vault), execution.creator_fee);
+    let recorded = env.pool_stats();
+    assert_eq!(recorded.volume_x, stats.volume_x + execution.x_in as u128);
+    assert_eq!(
+        recorded.protocol_fees,
+        stats.protocol_fees + execution.fee as u128
+    );
+    assert_eq!(
+        recorded.creator_fees,
+        stats.creator_fees + execution.creator_fee as u128
+    );
+}
+
+#[test]
+fn sellers_withdraw_their_proceeds() {
+    let mut env = TestEnv::new();
+    let seller = env.new_user(100_000, 0);
+    place_order(&mut env, &seller, 1);
+    env.warp_slots(TWAMM_INTERVAL);
+
+    let keys = env.keys;
+    env.send(
+        &[instructions::withdraw_twamm_proceeds(
+            &keys,
+            &seller.pubkey(),
+            1,
+        )],
+        &[&seller],
+    )
+    .unwrap();
+
+    let proceeds = env.balance_of(&seller.pubkey(), &keys.mint_y);
+    assert!(proceeds > 0);
+    let order = order(&env, &seller, 1);
+    assert_eq!(order.earnings_per_rate, twamm(&env).earnings_per_rate_x);
+    assert_eq!(env.pool().twamm_orders, 1);
+}
+
+#[test]
+fn cancelled_orders_refund_what_they_did_not_sell() {
+    let mut env = TestEnv::new();
+    let seller = env.new_user(100_000, 0);
+    place_order(&mut env, &seller, 1);
+    env.warp_slots(TWAMM_INTERVAL);
+
+    let keys = env.keys;
+    env.send(
+        &[instructions::cancel_twamm_order(&keys, &seller.pubkey(), 1)],
+        &[&seller],
+    )
+    .unwrap();
+
+    assert_eq!(
+        env.balance_of(&seller.pubkey(), &keys.mint_x),
+        100_000 - SELL_RATE * TWAMM_INTERVAL
+    );
+    assert!(env.balance_of(&seller.pubkey(), &keys.mint_y) > 0);
+    let address = twamm_order_address(&keys.pool, &seller.pubkey(), 1).0;
+    assert_eq!(env.lamports(&address), 0);
+    assert_eq!(twamm(&env).sell_rate_x, 0);
+    assert_eq!(env.pool().twamm_orders, 0);
+}
+
+#[test]
+fn ended_orders_close_on_withdrawal() {
+    let mut env = TestEnv::new();
+    let seller = env.new_user(100_000, 0);
+    place_order(&mut env, &seller, 1);
+    env.warp_slots(3 * TWAMM_INTERVAL);
+
+    let keys = env.keys;
+    env.send(
+        &[instructions::withdraw_twamm_proceeds(
+            &keys,
+            &seller.pubkey(),
+            1,
+        )],
+        &[&seller],
+    )
+    .unwrap();
+
+    let address = twamm_order_address(&keys.pool, &seller.pubkey(), 1).0;
+    assert_eq!(env.lamports(&address), 0);
+    assert_eq!(
+        env.balance_of(&seller.pubkey(), &keys.mint_x),
+        100_000 - 2 * SELL_RATE * TWAMM_INTERVAL
+    );
+    assert_eq!(env.pool().twamm_orders, 0);
+}
+
+#[test]
+fn swaps_execute_the_orders_first() {
+    let mut env = TestEnv::new();
+    let seller = env.new_user(100_000, 0);
+    place_order(&mut env, &seller, 1);
+    env.warp_slots(TWAMM_INTERVAL);
+    let trader = env.new_user(10_000, 0);
+
+    env.swap(&trader, true, 10_000, 1, SwapFees::default())
+        .unwrap();
+
+    assert_eq!(twamm(&env).last_executed_slot, env.slot());
+}
+
+#[test]
+fn quotes_see_the_orders_executed() {
+    let mut env = TestEnv::new();
+    let seller = env.new_user(100_000, 0);
+    place_order(&mut env, &seller, 1);
+    env.warp_slots(TWAMM_INTERVAL);
+
+    let mut expected = env.pool_state();
+    expected
+        .execute_twamm(&mut twamm(&env), env.slot())
+        .unwrap();
+    let quote: SwapQuote =
+        env.simulate(instructions::quote_swap(&env.keys, true, 10_000, 0, false));
+
+    assert_eq!(quote, expected.quote_swap(true, 10_000, 0, false).unwrap());
+    assert_ne!(
+        quote,
+        env.pool_state().quote_swap(true, 10_000, 0, false).unwrap()
+    );
+}
+
+#[test]
+fn settling_batches_executes_the_orders_first() {
+    let mut env = TestEnv::new();
+    let seller = env.new_user(100_000, 0);
+    place_order(&mut env, &seller, 1);
+    env.enable_batch_mode(10);
+    let trader = env.new_user(10_000, 0);
+    let keys = env.keys;
+    env.send(
+        &[instructions::submit_batch_order(
+            &keys,
+            &trader.pubkey(),
+            1,
+            true,
+            10_000,
+        )],
+        &[&trader],
+    )
+    .unwrap();
+    env.warp_slots(TWAMM_INTERVAL);
+
+    let fee_account = env.protocol_fee_account.pubkey();
+    env.send(
+        &[instructions::settle_batch(&keys, &fee_account, 1, false)],
+        &[&trader],
+    )
+    .unwrap();
+
+    assert_eq!(twamm(&env).last_executed_slot, env.slot());
+}
+
+#[test]
+fn closing_the_pool_closes_the_twamm() {
+    let mut env = TestEnv::new();
+    let keys = env.keys;
+    let seller = env.new_user(100_000, 0);
+    let creator = env.creator.insecure_clone();
+    place_order(&mut env, &seller, 1);
+    env.warp_slots(TWAMM_INTERVAL);
+    env.send(
+        &[instructions::cancel_twamm_order(&keys, &seller.pubkey(), 1)],
+        &[&seller],
+    )
+    .unwrap();
+    env.send(
+        &[instructions::withdraw_liquidity(
+            &keys,
+            &creator.pubkey(),
+            INITIAL_LIQUIDITY,
+            0,
+            0,
+            None,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    let pool = env.pool();
+    env.send(
+        &[instructions::close_pool(&keys, &creator.pubkey(), &pool)],
+        &[&creator],
+    )
+    .unwrap();
+
+    for account in [keys.twamm, keys.twamm_vault_x, keys.twamm_vault_y] {
+        assert_eq!(env.lamports(&account), 0);
+    }
+}
+
+#[test]
+fn pools_with_orders_need_the_twamm_accounts() {
+    let mut env = TestEnv::new();
+    let seller = env.new_user(100_000, 0);
+    place_order(&mut env, &seller, 1);
+    env.keys = env.keys.with_twamm(false);
+    let trader = env.new_user(10_000, 0);
+
+    let result = env.swap(&trader, true, 10_000, 1, SwapFees::default());
+
+    assert_error(result, ErrorCode::MissingTwamm);
+}
+
+#[test]
+fn orders_run_for_at_most_63_intervals() {
+    let mut env = TestEnv::new();
+    let seller = env.new_user(10_000_000, 0);
+    let keys = env.keys;
+
+    let result = env.send(
+        &[instructions::place_twamm_order(
+            &keys,
+            &seller.pubkey(),
+            1,
+            true,
+            1,
+            64,
+        )],
+        &[&seller],
+    );
+
+    assert_error(result, ErrorCode::InvalidTwammDuration);
+}
@@ -32,19 +32,142 @@ pub enum ErrorCode {
 
     #[msg("Invalid Referrer Ata")]
     InvalidReferrerAta,
+
+    #[msg("Integrator fee exceeds the protocol maximum")]
+    IntegratorFeeTooHigh,
+
+    #[msg("Invalid Integrator Ata")]
+    InvalidIntegratorAta,
+
+    #[msg("Arithmetic overflow")]
+    Overflow,
+
+    #[msg("Arithmetic underflow")]
+    Underflow,
+
+    #[msg("Invalid Precision")]
+    InvalidPrecision,
+
+    #[msg("Invalid Fee")]
+    InvalidFee,
+
+    #[msg("Pool has no liquidity")]
+    ZeroLiquidity,
+
+    #[msg("Amount too small to trade")]
+    AmountTooSmall,
+
+    #[msg("Transaction deadline exceeded")]
+    DeadlineExceeded,
+
+    #[msg("Price impact exceeds the pool maximum")]
+    PriceImpactTooHigh,
+
+    #[msg("Creator or mint pair is not allowlisted")]
+    PoolCreationNotAllowed,
+
+    #[msg("Token is blocked")]
+    TokenBlocked,
+
+    #[msg("Mint has a freeze authority")]
+    UnsafeMintAuthority,
+
+    #[msg("Mint has an extension the pool cannot safely hold")]
+    UnsafeMintExtension,
+
+    #[msg("Creator fee above the protocol maximum")]
+    CreatorFeeTooHigh,
+
+    #[msg("Account is already on the current layout")]
+    AlreadyMigrated,
+
+    #[msg("Pool is in batch auction mode, swaps go through batch orders")]
+    BatchModeEnabled,
+
+    #[msg("Pool is not in batch auction mode")]
+    BatchModeDisabled,
+
+    #[msg("Batch no longer takes orders")]
+    BatchClosed,
+
+    #[msg("Batch is still taking orders")]
+    BatchNotEnded,
+
+    #[msg("Batch is already settled")]
+    BatchAlreadySettled,
+
+    #[msg("Batch is not settled yet")]
+    BatchNotSettled,
+
+    #[msg("Only the pool creator or the admin can do this")]
+    Unauthorized,
+
+    #[msg("Pool still has liquidity")]
+    PoolNotEmpty,
+
+    #[msg("Pool has batch orders left to claim")]
+    BatchesOpen,
+
+    #[msg("Protocol fee compounding needs the treasury and its LP token account")]
+    InvalidTreasury,
+
+    #[msg("Profile tier is above the highest tier")]
+    InvalidProfileTier,
+
+    #[msg("Pool has TWAMM orders, the TWAMM accounts are needed to execute them")]
+    MissingTwamm,
+
+    #[msg("TWAMM orders run for 1 to 63 intervals")]
+    InvalidTwammDuration,
+
+    #[msg("Expired TWAMM orders 64 intervals earlier are still unclaimed, pick another duration")]
+    TwammExpiryBusy,
+
+    #[msg("Pool has TWAMM orders left to close")]
+    TwammOrdersOpen,
+
+    #[msg("Proposal is for another pool")]
+    InvalidProposalPool,
+
+    #[msg("Not enough governance tokens locked to propose")]
+    ProposalThresholdNotMet,
+
+    #[msg("Voting on this proposal has ended")]
+    VotingClosed,
+
+    #[msg("Proposal did not pass")]
+    ProposalNotPassed,
+
+    #[msg("Proposal was already executed")]
+    ProposalAlreadyExecuted,
+
+    #[msg("Proposal execution delay has not passed")]
+    ProposalTimelocked,
+
+    #[msg("Governance tokens are locked until the voter's proposals finish voting")]
+    VotesActive,
+
+    #[msg("Signer does not hold the position NFT")]
+    NotPositionHolder,
+
+    #[msg("Traders can't refer themselves")]
+    SelfReferral,
+
+    #[msg("Referrer does not own the referral profile")]
+    ReferrerMismatch,
 }
 
-// TODO: Understand how the From trait works in Rust
+// Keeps the curve's failure reason so clients can tell them apart
 impl From<CurveError> for ErrorCode {
     fn from(error: CurveError) -> ErrorCode {
         match error {
-            CurveError::InvalidPrecision => ErrorCode::InvalidAmount,
-            CurveError::Overflow => ErrorCode::InvalidAmount,
-            CurveError::Underflow => ErrorCode::InvalidAmount,
-            CurveError::InvalidFeeAmount => ErrorCode::InvalidAmount,
+            CurveError::InvalidPrecision => ErrorCode::InvalidPrecision,
+            CurveError::Overflow => ErrorCode::Overflow,
+            CurveError::Underflow => ErrorCode::Underflow,
+            CurveError::InvalidFeeAmount => ErrorCode::InvalidFee,
             CurveError::InsufficientBalance => ErrorCode::InsufficientBalance,
-            CurveError::ZeroBalance => ErrorCode::InvalidAmount,
-            CurveError::SlippageLimitExceeded => ErrorCode::InvalidAmount,
+            CurveError::ZeroBalance => ErrorCode::ZeroLiquidity,
+            CurveError::SlippageLimitExceeded => ErrorCode::SlippageExceeded,
         }
     }
 }
@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct SwapEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub mint_in: Pubkey,
+    // Everything the user paid, including the integrator fee
+    pub amount_in: u64,
+    pub amount_out: u64,
+    // Curve fee, split between referrer and protocol
+    pub fee: u64,
+    pub referrer_fee: u64,
+    // What the protocol kept, after the trader's discount
+    pub protocol_fee: u64,
+    // Refunded to a trader with a profile tier out of the protocol fee, on top of amount_out
+    pub fee_discount: u64,
+    pub referrer: Option<Pubkey>,
+    pub integrator_fee: u64,
+    pub integrator: Option<Pubkey>,
+    pub creator_fee: u64,
+    // Variable fee of the dynamic fee mode, left in the pool
+    pub lp_fee: u64,
+}
+
+#[event]
+pub struct BatchSettledEvent {
+    pub pool: Pubkey,
+    pub batch_id: u64,
+    pub x_in: u64,
+    pub y_in: u64,
+    pub x_out: u64,
+    pub y_out: u64,
+    pub fee: u64,
+    pub creator_fee: u64,
+    pub price_impact_bps: u16,
+    // Past the pool's price impact cap, the orders are refunded instead
+    pub cancelled: bool,
+}
+
+#[event]
+pub struct TwammExecutedEvent {
+    pub pool: Pubkey,
+    // Orders have run up to this slot
+    pub slot: u64,
+    pub x_in: u64,
+    pub y_in: u64,
+    pub x_out: u64,
+    pub y_out: u64,
+    pub fee: u64,
+    pub creator_fee: u64,
+}
+
+#[event]
+pub struct PoolCrankedEvent {
+    pub pool: Pubkey,
+    pub keeper: Pubkey,
+    // Accumulated volatility after the decay up to now
+    pub volatility_accumulator: u32,
+    // LP tokens paid to the keeper out of the treasury's
+    pub reward: u64,
+}
@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+
+use crate::{AllowlistEntry, ProtocolConfig};
+
+// Approving a creator wallet or a mint pair for permissioned pool creation
+#[derive(Accounts)]
+#[instruction(key: Pubkey)]
+pub struct AddToAllowlist<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin,
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"allowlist", key.as_ref()],
+        bump,
+        space = AllowlistEntry::INIT_SPACE
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl AddToAllowlist<'_> {
+    pub fn add_to_allowlist(&mut self, key: Pubkey, bumps: AddToAllowlistBumps) -> Result<()> {
+        self.allowlist_entry.set_inner(AllowlistEntry {
+            key,
+            allowlist_bump: bumps.allowlist_entry,
+        });
+
+        Ok(())
+    }
+}
+
+// Revoking an approval, the rent goes back to the admin
+#[derive(Accounts)]
+pub struct RemoveFromAllowlist<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin,
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"allowlist", allowlist_entry.key.as_ref()],
+        bump = allowlist_entry.allowlist_bump,
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+}
+
+impl RemoveFromAllowlist<'_> {
+    pub fn remove_from_allowlist(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
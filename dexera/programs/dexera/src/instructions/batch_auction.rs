@@ -0,0 +1,629 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::{transfer_checked, TransferChecked},
+    token_interface::{mint_to, Mint, MintTo, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::ErrorCode, events::BatchSettledEvent, Batch, BatchOrder, BatchStatus, LiquidityQuote,
+    Pool, PoolSnapshot, PoolStats, ProtocolConfig, Twamm, VirtualOrders,
+};
+
+// Queueing a swap in the pool's open batch, starting the next batch once the latest one
+// stopped taking orders
+#[derive(Accounts)]
+#[instruction(batch_id: u64)]
+pub struct SubmitBatchOrder<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub mint_x: InterfaceAccount<'info, Mint>,
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+        ],
+        bump = pool.pool_bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"batch", pool.key().as_ref(), batch_id.to_le_bytes().as_ref()],
+        space = Batch::INIT_SPACE,
+        bump,
+    )]
+    pub batch: Box<Account<'info, Batch>>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"batch_order", batch.key().as_ref(), user.key().as_ref()],
+        space = BatchOrder::INIT_SPACE,
+        bump,
+    )]
+    pub batch_order: Account<'info, BatchOrder>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"batch_vault", pool.key().as_ref(), mint_x.key().as_ref()],
+        bump,
+        token::mint = mint_x,
+        token::authority = pool,
+        token::token_program = token_program,
+    )]
+    pub batch_vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"batch_vault", pool.key().as_ref(), mint_y.key().as_ref()],
+        bump,
+        token::mint = mint_y,
+        token::authority = pool,
+        token::token_program = token_program,
+    )]
+    pub batch_vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // In the mint sold, checked by the transfer
+    #[account(mut)]
+    pub user_ata_in: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl SubmitBatchOrder<'_> {
+    pub fn submit_batch_order(
+        &mut self,
+        batch_id: u64,
+        is_x: bool,
+        amount_in: u64,
+        bumps: SubmitBatchOrderBumps,
+    ) -> Result<()> {
+        require!(self.pool.batch_duration > 0, ErrorCode::BatchModeDisabled);
+        require!(!self.pool.locked, ErrorCode::AMMLocked);
+        require!(amount_in > 0, ErrorCode::InvalidAmount);
+
+        let slot = Clock::get()?.slot;
+        require!(
+            batch_id == self.pool.open_batch_id(slot),
+            ErrorCode::BatchClosed
+        );
+
+        if batch_id != self.pool.batch_id {
+            let end_slot = slot + self.pool.batch_duration as u64;
+            self.pool.batch_id = batch_id;
+            self.pool.batch_end_slot = end_slot;
+            self.pool.open_batches += 1;
+            self.batch.set_inner(Batch {
+                pool: self.pool.key(),
+                batch_id,
+                payer: self.user.key(),
+                end_slot,
+                status: BatchStatus::Open,
+                orders: 0,
+                x_in: 0,
+                y_in: 0,
+                x_out: 0,
+                y_out: 0,
+                batch_bump: bumps.batch,
+            });
+        }
+        self.pool.batch_vault_x_bump = bumps.batch_vault_x;
+        self.pool.batch_vault_y_bump = bumps.batch_vault_y;
+
+        let batch = &mut self.batch;
+        batch.orders += 1;
+        if is_x {
+            batch.x_in = batch
+                .x_in
+                .checked_add(amount_in)
+                .ok_or(ErrorCode::Overflow)?;
+        } else {
+            batch.y_in = batch
+                .y_in
+                .checked_add(amount_in)
+                .ok_or(ErrorCode::Overflow)?;
+        }
+
+        self.batch_order.set_inner(BatchOrder {
+            batch: self.batch.key(),
+            owner: self.user.key(),
+            is_x,
+            amount_in,
+            batch_order_bump: bumps.batch_order,
+        });
+
+        let (mint, to, decimals) = if is_x {
+            (&self.mint_x, &self.batch_vault_x, self.mint_x.decimals)
+        } else {
+            (&self.mint_y, &self.batch_vault_y, self.mint_y.decimals)
+        };
+        let cpi_accounts = TransferChecked {
+            from: self.user_ata_in.to_account_info(),
+            mint: mint.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+        let cpi_context = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+        transfer_checked(cpi_context, amount_in, decimals)
+    }
+}
+
+// Settling a batch once it stopped taking orders, open to anyone
+#[derive(Accounts)]
+pub struct SettleBatch<'info> {
+    pub mint_x: InterfaceAccount<'info, Mint>,
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.mint_lp_bump,
+    )]
+    pub mint_lp: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+        ],
+        bump = pool.pool_bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        mut,
+        seeds = [b"batch", pool.key().as_ref(), batch.batch_id.to_le_bytes().as_ref()],
+        bump = batch.batch_bump,
+    )]
+    pub batch: Box<Account<'info, Batch>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_vault_x_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_vault_y_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"batch_vault", pool.key().as_ref(), mint_x.key().as_ref()],
+        bump = pool.batch_vault_x_bump,
+    )]
+    pub batch_vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"batch_vault", pool.key().as_ref(), mint_y.key().as_ref()],
+        bump = pool.batch_vault_y_bump,
+    )]
+    pub batch_vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config.protocol_fee_account,
+        associated_token::token_program = token_program,
+    )]
+    pub protocol_fee_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"creator_fee", pool.key().as_ref()],
+        bump = pool.creator_fee_vault_bump,
+    )]
+    pub creator_fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_stats", pool.key().as_ref()],
+        bump = pool_stats.stats_bump,
+    )]
+    pub pool_stats: Box<Account<'info, PoolStats>>,
+
+    /// CHECK: Owns the protocol's LP tokens, only needed while protocol fees are compounded
+    #[account(
+        seeds = [b"treasury"],
+        bump = config.treasury_bump,
+    )]
+    pub treasury: Option<UncheckedAccount<'info>>,
+
+    // Settling is open to anyone and has no payer, the account has to exist already
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub treasury_lp_ata: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    // The pool's TWAMM, executed up to now before the batch is priced while it has orders
+    #[account(
+        mut,
+        seeds = [b"twamm", pool.key().as_ref()],
+        bump = twamm.twamm_bump,
+    )]
+    pub twamm: Option<Box<Account<'info, Twamm>>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_x.key().as_ref()],
+        bump = pool.twamm_vault_x_bump,
+    )]
+    pub twamm_vault_x: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_y.key().as_ref()],
+        bump = pool.twamm_vault_y_bump,
+    )]
+    pub twamm_vault_y: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> SettleBatch<'info> {
+    pub fn settle_batch(&mut self) -> Result<()> {
+        require!(
+            self.batch.status == BatchStatus::Open,
+            ErrorCode::BatchAlreadySettled
+        );
+        require!(
+            Clock::get()?.slot >= self.batch.end_slot,
+            ErrorCode::BatchNotEnded
+        );
+
+        VirtualOrders {
+            config: &self.config,
+            pool: &self.pool,
+            mint_x: &self.mint_x,
+            mint_y: &self.mint_y,
+            lp_supply: self.mint_lp.supply,
+            pool_vault_x_ata: &mut self.pool_vault_x_ata,
+            pool_vault_y_ata: &mut self.pool_vault_y_ata,
+            twamm: self.twamm.as_deref_mut(),
+            twamm_vault_x: self.twamm_vault_x.as_deref(),
+            twamm_vault_y: self.twamm_vault_y.as_deref(),
+            protocol_fee_ata: Some(&*self.protocol_fee_ata),
+            creator_fee_vault: Some(&*self.creator_fee_vault),
+            pool_stats: Some(&mut *self.pool_stats),
+            token_program: &self.token_program,
+        }
+        .execute()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let settlement = PoolSnapshot {
+            config: &self.config,
+            pool: &self.pool,
+            vault_x: self.pool_vault_x_ata.amount,
+            vault_y: self.pool_vault_y_ata.amount,
+            lp_supply: self.mint_lp.supply,
+            now,
+        }
+        .settle_batch(self.batch.x_in, self.batch.y_in)?;
+
+        // A stuck batch would lock the orders' tokens for good, so it is cancelled instead
+        let cancelled = settlement.price_impact_bps > self.pool.max_price_impact_bps;
+        if cancelled {
+            self.batch.status = BatchStatus::Cancelled;
+        } else {
+            if settlement.is_x {
+                self.transfer(
+                    &self.batch_vault_x,
+                    &self.pool_vault_x_ata,
+                    true,
+                    settlement.pool_amount_in,
+                )?;
+                self.transfer(
+                    &self.pool_vault_y_ata,
+                    &self.batch_vault_y,
+                    false,
+                    settlement.pool_amount_out,
+                )?;
+            } else {
+                self.transfer(
+                    &self.batch_vault_y,
+                    &self.pool_vault_y_ata,
+                    false,
+                    settlement.pool_amount_in,
+                )?;
+                self.transfer(
+                    &self.pool_vault_x_ata,
+                    &self.batch_vault_x,
+                    true,
+                    settlement.pool_amount_out,
+                )?;
+            }
+            if self.config.compound_protocol_fees {
+                self.transfer(
+                    &self.batch_vault_y,
+                    &self.pool_vault_y_ata,
+                    false,
+                    settlement.fee,
+                )?;
+            } else {
+                self.transfer(
+                    &self.batch_vault_y,
+                    &self.protocol_fee_ata,
+                    false,
+                    settlement.fee,
+                )?;
+            }
+            self.transfer(
+                &self.batch_vault_y,
+                &self.creator_fee_vault,
+                false,
+                settlement.creator_fee,
+            )?;
+            if self.config.compound_protocol_fees && settlement.fee > 0 {
+                self.compound_protocol_fee(settlement.fee)?;
+            }
+
+            self.pool_stats.record_batch(now, &self.batch, &settlement);
+
+            let batch = &mut self.batch;
+            batch.status = BatchStatus::Settled;
+            batch.x_out = settlement.x_out;
+            batch.y_out = settlement.y_out;
+        }
+
+        emit!(BatchSettledEvent {
+            pool: self.pool.key(),
+            batch_id: self.batch.batch_id,
+            x_in: self.batch.x_in,
+            y_in: self.batch.y_in,
+            x_out: self.batch.x_out,
+            y_out: self.batch.y_out,
+            fee: if cancelled { 0 } else { settlement.fee },
+            creator_fee: if cancelled { 0 } else { settlement.creator_fee },
+            price_impact_bps: settlement.price_impact_bps,
+            cancelled,
+        });
+
+        Ok(())
+    }
+
+    // Every account involved is owned by the pool
+    fn transfer(
+        &self,
+        from: &InterfaceAccount<'info, TokenAccount>,
+        to: &InterfaceAccount<'info, TokenAccount>,
+        is_x: bool,
+        amount: u64,
+    ) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let (mint, decimals) = if is_x {
+            (self.mint_x.to_account_info(), self.mint_x.decimals)
+        } else {
+            (self.mint_y.to_account_info(), self.mint_y.decimals)
+        };
+
+        let mint_x_bytes = self.mint_x.key().to_bytes();
+        let mint_y_bytes = self.mint_y.key().to_bytes();
+
+        let seeds = [
+            b"pool",
+            mint_x_bytes.as_ref(),
+            mint_y_bytes.as_ref(),
+            &[self.pool.pool_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: from.to_account_info(),
+            mint,
+            to: to.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer_checked(cpi_context, amount, decimals)
+    }
+
+    // Like a swap's, the protocol fee is left in the Y vault for LP tokens to the treasury
+    fn compound_protocol_fee(&mut self, amount: u64) -> Result<()> {
+        let treasury_lp_ata = match (&self.treasury, &self.treasury_lp_ata) {
+            (Some(_), Some(treasury_lp_ata)) => treasury_lp_ata.to_account_info(),
+            _ => return err!(ErrorCode::InvalidTreasury),
+        };
+
+        self.pool_vault_y_ata.reload()?;
+        let quote = LiquidityQuote::compound(
+            self.pool_vault_y_ata.amount - amount,
+            self.mint_lp.supply,
+            amount,
+        )?;
+        if quote.lp_tokens_amount == 0 {
+            return Ok(());
+        }
+
+        let mint_x_bytes = self.mint_x.key().to_bytes();
+        let mint_y_bytes = self.mint_y.key().to_bytes();
+
+        let seeds = [
+            b"pool",
+            mint_x_bytes.as_ref(),
+            mint_y_bytes.as_ref(),
+            &[self.pool.pool_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = MintTo {
+            mint: self.mint_lp.to_account_info(),
+            to: treasury_lp_ata,
+            authority: self.pool.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        mint_to(cpi_context, quote.lp_tokens_amount)
+    }
+}
+
+// Paying out an order's share of its settled batch, or refunding it if the batch was cancelled.
+// The last claim closes the batch.
+#[derive(Accounts)]
+pub struct ClaimBatchOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub mint_x: InterfaceAccount<'info, Mint>,
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+        ],
+        bump = pool.pool_bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        mut,
+        seeds = [b"batch", pool.key().as_ref(), batch.batch_id.to_le_bytes().as_ref()],
+        bump = batch.batch_bump,
+    )]
+    pub batch: Box<Account<'info, Batch>>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds = [b"batch_order", batch.key().as_ref(), owner.key().as_ref()],
+        bump = batch_order.batch_order_bump,
+    )]
+    pub batch_order: Account<'info, BatchOrder>,
+
+    /// CHECK: Receives the batch rent back, checked against batch.payer
+    #[account(mut, address = batch.payer)]
+    pub batch_payer: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"batch_vault", pool.key().as_ref(), mint_x.key().as_ref()],
+        bump = pool.batch_vault_x_bump,
+    )]
+    pub batch_vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"batch_vault", pool.key().as_ref(), mint_y.key().as_ref()],
+        bump = pool.batch_vault_y_bump,
+    )]
+    pub batch_vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // In the mint bought, or in the mint sold when the batch was cancelled. Checked by the transfer
+    #[account(mut)]
+    pub owner_ata_out: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl ClaimBatchOrder<'_> {
+    pub fn claim_batch_order(&mut self) -> Result<()> {
+        let order = &self.batch_order;
+        let batch = &self.batch;
+
+        // Paid in X when the order sold Y, unless refunded
+        let (pays_x, amount) = match batch.status {
+            BatchStatus::Open => return err!(ErrorCode::BatchNotSettled),
+            BatchStatus::Settled if order.is_x => (
+                false,
+                pro_rata_share(batch.y_out, order.amount_in, batch.x_in)?,
+            ),
+            BatchStatus::Settled => (
+                true,
+                pro_rata_share(batch.x_out, order.amount_in, batch.y_in)?,
+            ),
+            BatchStatus::Cancelled => (order.is_x, order.amount_in),
+        };
+
+        let (from, mint) = if pays_x {
+            (&self.batch_vault_x, &self.mint_x)
+        } else {
+            (&self.batch_vault_y, &self.mint_y)
+        };
+        if amount > 0 {
+            let mint_x_bytes = self.mint_x.key().to_bytes();
+            let mint_y_bytes = self.mint_y.key().to_bytes();
+
+            let seeds = [
+                b"pool",
+                mint_x_bytes.as_ref(),
+                mint_y_bytes.as_ref(),
+                &[self.pool.pool_bump],
+            ];
+            let signer_seeds = &[&seeds[..]];
+
+            let cpi_accounts = TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: self.owner_ata_out.to_account_info(),
+                authority: self.pool.to_account_info(),
+            };
+            let cpi_context = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            );
+            transfer_checked(cpi_context, amount, mint.decimals)?;
+        }
+
+        // Rounding dust stays in the batch vaults
+        self.batch.orders -= 1;
+        if self.batch.orders == 0 {
+            self.batch.close(self.batch_payer.to_account_info())?;
+            self.pool.open_batches -= 1;
+        }
+
+        Ok(())
+    }
+}
+
+// total * amount / amounts, rounded down
+fn pro_rata_share(total: u64, amount: u64, amounts: u64) -> Result<u64> {
+    let share = (total as u128)
+        .checked_mul(amount as u128)
+        .ok_or(ErrorCode::Overflow)?
+        / amounts as u128;
+
+    Ok(share as u64)
+}
@@ -0,0 +1,288 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{errors::ErrorCode, Pool, PoolStats, ProtocolConfig, Twamm};
+
+// Closing a pool nobody provides liquidity to anymore, by its creator or the admin. Rent,
+// unclaimed creator fees, tokens sent to the reserves since the last withdrawal and the
+// rounding dust of batches and TWAMM orders go to the creator. The LP mint and its metadata
+// can't be closed.
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == pool.creator
+            || authority.key() == config.admin @ ErrorCode::Unauthorized,
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// CHECK: Receives the rent, checked against pool.creator
+    #[account(mut, address = pool.creator)]
+    pub creator: UncheckedAccount<'info>,
+
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.mint_lp_bump,
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        close = creator,
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+        ],
+        bump = pool.pool_bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        mut,
+        close = creator,
+        seeds = [b"pool_stats", pool.key().as_ref()],
+        bump = pool_stats.stats_bump,
+    )]
+    pub pool_stats: Box<Account<'info, PoolStats>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_vault_x_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_vault_y_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_mint_lp_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"creator_fee", pool.key().as_ref()],
+        bump = pool.creator_fee_vault_bump,
+    )]
+    pub creator_fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Only exist once the pool has run batches
+    #[account(
+        mut,
+        seeds = [b"batch_vault", pool.key().as_ref(), mint_x.key().as_ref()],
+        bump = pool.batch_vault_x_bump,
+    )]
+    pub batch_vault_x: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"batch_vault", pool.key().as_ref(), mint_y.key().as_ref()],
+        bump = pool.batch_vault_y_bump,
+    )]
+    pub batch_vault_y: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    // Only exist once the pool has had TWAMM orders. A pool created again on the same
+    // pair would otherwise pick up the old TWAMM.
+    #[account(
+        mut,
+        close = creator,
+        seeds = [b"twamm", pool.key().as_ref()],
+        bump = twamm.twamm_bump,
+    )]
+    pub twamm: Option<Box<Account<'info, Twamm>>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_x.key().as_ref()],
+        bump = pool.twamm_vault_x_bump,
+    )]
+    pub twamm_vault_x: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_y.key().as_ref()],
+        bump = pool.twamm_vault_y_bump,
+    )]
+    pub twamm_vault_y: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    // Only exists once LP tokens have been wrapped into a position NFT
+    #[account(
+        mut,
+        seeds = [b"position_vault", pool.key().as_ref()],
+        bump = pool.position_vault_bump,
+    )]
+    pub position_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_x,
+        associated_token::authority = creator,
+        associated_token::token_program = token_program,
+    )]
+    pub creator_mint_x_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_y,
+        associated_token::authority = creator,
+        associated_token::token_program = token_program,
+    )]
+    pub creator_mint_y_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Closed along with the pool when the creator signs
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = creator,
+        associated_token::token_program = token_program,
+    )]
+    pub creator_mint_lp_ata: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClosePool<'info> {
+    pub fn close_pool(&mut self) -> Result<()> {
+        // Wrapped LP tokens count in the supply too
+        require!(self.mint_lp.supply == 0, ErrorCode::PoolNotEmpty);
+        require!(self.pool.open_batches == 0, ErrorCode::BatchesOpen);
+        require!(self.pool.twamm_orders == 0, ErrorCode::TwammOrdersOpen);
+
+        // Without LP tokens nobody has a claim on what is left in the reserves
+        self.close_token_account(&self.pool_vault_x_ata, Some(&self.creator_mint_x_ata))?;
+        self.close_token_account(&self.pool_vault_y_ata, Some(&self.creator_mint_y_ata))?;
+        self.close_token_account(&self.pool_mint_lp_ata, None)?;
+        self.close_token_account(&self.creator_fee_vault, Some(&self.creator_mint_y_ata))?;
+
+        // Rounding dust of past batches
+        if self.pool.batch_id > 0 {
+            let (Some(batch_vault_x), Some(batch_vault_y)) =
+                (&self.batch_vault_x, &self.batch_vault_y)
+            else {
+                return err!(anchor_lang::error::ErrorCode::AccountNotEnoughKeys);
+            };
+            self.close_token_account(batch_vault_x, Some(&self.creator_mint_x_ata))?;
+            self.close_token_account(batch_vault_y, Some(&self.creator_mint_y_ata))?;
+        }
+
+        // Rounding dust of past TWAMM orders, the vault bumps are set with the TWAMM
+        if self.pool.twamm_vault_x_bump != 0 {
+            let (Some(_), Some(twamm_vault_x), Some(twamm_vault_y)) =
+                (&self.twamm, &self.twamm_vault_x, &self.twamm_vault_y)
+            else {
+                return err!(anchor_lang::error::ErrorCode::AccountNotEnoughKeys);
+            };
+            self.close_token_account(twamm_vault_x, Some(&self.creator_mint_x_ata))?;
+            self.close_token_account(twamm_vault_y, Some(&self.creator_mint_y_ata))?;
+        }
+
+        if self.pool.position_vault_bump != 0 {
+            let Some(position_vault) = &self.position_vault else {
+                return err!(anchor_lang::error::ErrorCode::AccountNotEnoughKeys);
+            };
+            self.close_token_account(position_vault, None)?;
+        }
+
+        // The token program only lets the creator close their own account
+        if let Some(creator_mint_lp_ata) = &self.creator_mint_lp_ata {
+            require_keys_eq!(
+                self.authority.key(),
+                self.pool.creator,
+                ErrorCode::Unauthorized
+            );
+            let cpi_accounts = CloseAccount {
+                account: creator_mint_lp_ata.to_account_info(),
+                destination: self.creator.to_account_info(),
+                authority: self.authority.to_account_info(),
+            };
+            let cpi_context = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+            close_account(cpi_context)?;
+        }
+
+        Ok(())
+    }
+
+    // Sweeps what is left to `sweep_to`, then returns the rent to the creator
+    fn close_token_account(
+        &self,
+        account: &InterfaceAccount<'info, TokenAccount>,
+        sweep_to: Option<&InterfaceAccount<'info, TokenAccount>>,
+    ) -> Result<()> {
+        let mint_x_bytes = self.mint_x.key().to_bytes();
+        let mint_y_bytes = self.mint_y.key().to_bytes();
+
+        let seeds = [
+            b"pool",
+            mint_x_bytes.as_ref(),
+            mint_y_bytes.as_ref(),
+            &[self.pool.pool_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        if let Some(to) = sweep_to {
+            if account.amount > 0 {
+                let mint = if account.mint == self.mint_x.key() {
+                    &self.mint_x
+                } else {
+                    &self.mint_y
+                };
+                let cpi_accounts = TransferChecked {
+                    from: account.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: self.pool.to_account_info(),
+                };
+                let cpi_context = CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    cpi_accounts,
+                    signer_seeds,
+                );
+                transfer_checked(cpi_context, account.amount, mint.decimals)?;
+            }
+        }
+
+        let cpi_accounts = CloseAccount {
+            account: account.to_account_info(),
+            destination: self.creator.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        close_account(cpi_context)
+    }
+}
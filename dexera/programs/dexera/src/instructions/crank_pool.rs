@@ -0,0 +1,212 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{transfer_checked, TransferChecked},
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{events::PoolCrankedEvent, Pool, PoolStats, ProtocolConfig, Twamm, VirtualOrders};
+
+// Bringing a pool's time-dependent state up to date without trading, open to anyone.
+// Keepers are paid in the treasury's LP tokens of the pool when the reward is on.
+#[derive(Accounts)]
+pub struct CrankPool<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.mint_lp_bump,
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+        ],
+        bump = pool.pool_bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_vault_x_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_vault_y_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_stats", pool.key().as_ref()],
+        bump = pool_stats.stats_bump,
+    )]
+    pub pool_stats: Box<Account<'info, PoolStats>>,
+
+    // The pool's TWAMM, required while it has orders
+    #[account(
+        mut,
+        seeds = [b"twamm", pool.key().as_ref()],
+        bump = twamm.twamm_bump,
+    )]
+    pub twamm: Option<Box<Account<'info, Twamm>>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_x.key().as_ref()],
+        bump = pool.twamm_vault_x_bump,
+    )]
+    pub twamm_vault_x: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_y.key().as_ref()],
+        bump = pool.twamm_vault_y_bump,
+    )]
+    pub twamm_vault_y: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    // Paid the fees of the TWAMM's fills, required along with it
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config.protocol_fee_account,
+        associated_token::token_program = token_program,
+    )]
+    pub protocol_fee_ata: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"creator_fee", pool.key().as_ref()],
+        bump = pool.creator_fee_vault_bump,
+    )]
+    pub creator_fee_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: Signs for the treasury's LP tokens, only needed to claim the reward
+    #[account(
+        seeds = [b"treasury"],
+        bump = config.treasury_bump,
+    )]
+    pub treasury: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub treasury_lp_ata: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = keeper,
+        associated_token::mint = mint_lp,
+        associated_token::authority = keeper,
+        associated_token::token_program = token_program,
+    )]
+    pub keeper_lp_ata: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl CrankPool<'_> {
+    // Executes the TWAMM orders, decays the accumulated volatility and rolls the daily
+    // stats over, as a trade would. Cranks without the reward accounts are free.
+    pub fn crank_pool(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+
+        let twamm_executed = VirtualOrders {
+            config: &self.config,
+            pool: &self.pool,
+            mint_x: &self.mint_x,
+            mint_y: &self.mint_y,
+            lp_supply: self.mint_lp.supply,
+            pool_vault_x_ata: &mut self.pool_vault_x_ata,
+            pool_vault_y_ata: &mut self.pool_vault_y_ata,
+            twamm: self.twamm.as_deref_mut(),
+            twamm_vault_x: self.twamm_vault_x.as_deref(),
+            twamm_vault_y: self.twamm_vault_y.as_deref(),
+            protocol_fee_ata: self.protocol_fee_ata.as_deref(),
+            creator_fee_vault: self.creator_fee_vault.as_deref(),
+            pool_stats: Some(&mut *self.pool_stats),
+            token_program: &self.token_program,
+        }
+        .execute()?;
+        let volatility_decayed = self.pool.decay_volatility(now);
+        self.pool_stats.roll_day(now);
+
+        // Paying idle cranks would drain the treasury a share at a time
+        let reward = if twamm_executed || volatility_decayed {
+            self.pay_reward(now)?
+        } else {
+            0
+        };
+
+        emit!(PoolCrankedEvent {
+            pool: self.pool.key(),
+            keeper: self.keeper.key(),
+            volatility_accumulator: self.pool.volatility_at(now),
+            reward,
+        });
+
+        Ok(())
+    }
+
+    fn pay_reward(&mut self, now: i64) -> Result<u64> {
+        let (treasury, treasury_lp_ata, keeper_lp_ata) =
+            match (&self.treasury, &self.treasury_lp_ata, &self.keeper_lp_ata) {
+                (Some(treasury), Some(treasury_lp_ata), Some(keeper_lp_ata)) => {
+                    (treasury, treasury_lp_ata, keeper_lp_ata)
+                }
+                _ => return Ok(0),
+            };
+
+        let reward = self
+            .config
+            .crank_reward(&self.pool, now, treasury_lp_ata.amount)?;
+        if reward == 0 {
+            return Ok(0);
+        }
+
+        let seeds = [b"treasury".as_ref(), &[self.config.treasury_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: treasury_lp_ata.to_account_info(),
+            mint: self.mint_lp.to_account_info(),
+            to: keeper_lp_ata.to_account_info(),
+            authority: treasury.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_context, reward, self.mint_lp.decimals)?;
+
+        self.pool.last_crank_reward_at = now;
+
+        Ok(reward)
+    }
+}
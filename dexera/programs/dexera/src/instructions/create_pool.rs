@@ -1,11 +1,31 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
 use anchor_spl::{
     associated_token::AssociatedToken,
+    token_2022::spl_token_2022::{
+        extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+        state::Mint as MintState,
+    },
     token_interface::{transfer_checked, Mint,MintTo, mint_to, TokenAccount, TokenInterface, TransferChecked},
 };
-use constant_product_curve::ConstantProduct;
 
-use crate::{errors::ErrorCode, Pool};
+use crate::{
+    errors::ErrorCode,
+    metadata::{create_lp_metadata, lp_name, mint_symbol, METADATA_PROGRAM_ID},
+    AllowlistEntry, LiquidityQuote, Pool, PoolStats, ProtocolConfig, TokenRegistryEntry,
+    TokenStatus, BASIS_POINTS,
+};
+
+// Extensions that let the issuer move, freeze or skim tokens held by the vaults
+const UNSAFE_MINT_EXTENSIONS: [ExtensionType; 5] = [
+    ExtensionType::PermanentDelegate,
+    ExtensionType::TransferHook,
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::NonTransferable,
+    ExtensionType::DefaultAccountState,
+];
 
 #[derive(Accounts)]
 pub struct CreatePool<'info> {
@@ -15,12 +35,13 @@ pub struct CreatePool<'info> {
     pub mint_x: InterfaceAccount<'info, Mint>,
     pub mint_y: InterfaceAccount<'info, Mint>,
 
+    // Outlives close_pool, a pool created again for the pair takes it over
     #[account(
-        init,
+        init_if_needed,
         payer = creator,
         seeds = [b"lp", pool.key().as_ref()],
         bump, 
-        mint::decimals = 6,
+        mint::decimals = Pool::lp_decimals_for(mint_x.decimals, mint_y.decimals),
         mint::authority = pool,
     )]
     pub mint_lp: InterfaceAccount<'info, Mint>,
@@ -61,7 +82,7 @@ pub struct CreatePool<'info> {
     pub creator_mint_y_ata: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
-        init,
+        init_if_needed,
         payer = creator,
         associated_token::mint = mint_lp,
         associated_token::authority = creator,
@@ -79,16 +100,105 @@ pub struct CreatePool<'info> {
     )]
     pub pool: Account<'info, Pool>,
 
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"creator_fee", pool.key().as_ref()],
+        bump,
+        token::mint = mint_y,
+        token::authority = pool,
+        token::token_program = token_program,
+    )]
+    pub creator_fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"pool_stats", pool.key().as_ref()],
+        space = PoolStats::INIT_SPACE,
+        bump,
+    )]
+    pub pool_stats: Box<Account<'info, PoolStats>>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// CHECK: Receives the pool creation fee, checked against the config
+    #[account(
+        mut,
+        address = config.protocol_fee_account,
+    )]
+    pub protocol_fee_account: UncheckedAccount<'info>,
+
+    // Only needed while pool creation is permissioned
+    #[account(
+        seeds = [b"allowlist", allowlist_entry.key.as_ref()],
+        bump = allowlist_entry.allowlist_bump,
+    )]
+    pub allowlist_entry: Option<Box<Account<'info, AllowlistEntry>>>,
+
+    /// CHECK: Registry entry of mint_x, may not exist so it is read in check_mint
+    #[account(
+        seeds = [b"token", mint_x.key().as_ref()],
+        bump,
+    )]
+    pub token_registry_x: UncheckedAccount<'info>,
+
+    /// CHECK: Registry entry of mint_y, may not exist so it is read in check_mint
+    #[account(
+        seeds = [b"token", mint_y.key().as_ref()],
+        bump,
+    )]
+    pub token_registry_y: UncheckedAccount<'info>,
+
+    // LP metadata is attached when the Metaplex program is passed, it isn't deployed everywhere
+    /// CHECK: Created by the Metaplex program
+    #[account(
+        mut,
+        seeds = [b"metadata", METADATA_PROGRAM_ID.as_ref(), mint_lp.key().as_ref()],
+        bump,
+        seeds::program = METADATA_PROGRAM_ID,
+    )]
+    pub lp_metadata: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Metaplex metadata of mint_x, may not exist so it is read in mint_symbol
+    #[account(
+        seeds = [b"metadata", METADATA_PROGRAM_ID.as_ref(), mint_x.key().as_ref()],
+        bump,
+        seeds::program = METADATA_PROGRAM_ID,
+    )]
+    pub mint_x_metadata: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Metaplex metadata of mint_y, may not exist so it is read in mint_symbol
+    #[account(
+        seeds = [b"metadata", METADATA_PROGRAM_ID.as_ref(), mint_y.key().as_ref()],
+        bump,
+        seeds::program = METADATA_PROGRAM_ID,
+    )]
+    pub mint_y_metadata: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The Metaplex program
+    #[account(address = METADATA_PROGRAM_ID)]
+    pub metadata_program: Option<UncheckedAccount<'info>>,
+
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
-impl CreatePool<'_> {
+impl<'info> CreatePool<'info> {
     pub fn create_pool_state(
         &mut self,
         bumps: CreatePoolBumps,
     ) -> Result<()> {
+        self.check_allowlist()?;
+        let status_x = Self::check_mint(&self.mint_x, &self.token_registry_x)?;
+        let status_y = Self::check_mint(&self.mint_y, &self.token_registry_y)?;
+        self.charge_creation_fee()?;
+
         self.pool.set_inner(Pool {
             creator: self.creator.key(),
             mint_x: self.mint_x.key(),
@@ -96,24 +206,163 @@ impl CreatePool<'_> {
             mint_lp: self.mint_lp.key(),
             pool_bump: bumps.pool,
             mint_lp_bump: bumps.mint_lp,
-            locked: false
+            locked: false,
+            max_price_impact_bps: BASIS_POINTS,
+            verified: status_x == Some(TokenStatus::Verified)
+                && status_y == Some(TokenStatus::Verified),
+            creator_fee: 0,
+            creator_fee_vault_bump: bumps.creator_fee_vault,
+            max_variable_fee: 0,
+            variable_fee_control: 0,
+            volatility_decay_period: 0,
+            volatility_accumulator: 0,
+            last_volatility_update: 0,
+            version: Pool::VERSION,
+            batch_duration: 0,
+            batch_id: 0,
+            batch_end_slot: 0,
+            batch_vault_x_bump: 0,
+            batch_vault_y_bump: 0,
+            open_batches: 0,
+            lp_decimals: self.mint_lp.decimals,
+            twamm_vault_x_bump: 0,
+            twamm_vault_y_bump: 0,
+            twamm_orders: 0,
+            last_crank_reward_at: 0,
+            position_vault_bump: 0,
+            reserved: [0; 22],
         });
 
+        self.pool_stats.set_inner(PoolStats::new(
+            self.pool.key(),
+            bumps.pool_stats,
+            Clock::get()?.unix_timestamp,
+        ));
+
+        // A pool created again on the pair of a closed one takes over its LP mint. The
+        // Metaplex program never closes the mint's metadata, which still fits.
+        if let (Some(lp_metadata), Some(metadata_program)) =
+            (&self.lp_metadata, &self.metadata_program)
+        {
+            if lp_metadata.data_is_empty() {
+                self.create_lp_metadata(lp_metadata, metadata_program)?;
+            }
+        }
+
         // Add initial liquidity
         self.deposit_tokens(1_000_000, 1_000_000, 1_000_000)?;
 
         Ok(())
     }
 
+    // The entry has to approve either the creator or this mint pair's pool
+    fn check_allowlist(&self) -> Result<()> {
+        if !self.config.permissioned_pool_creation {
+            return Ok(());
+        }
+
+        let entry = self
+            .allowlist_entry
+            .as_ref()
+            .ok_or(ErrorCode::PoolCreationNotAllowed)?;
+        require!(
+            entry.key == self.creator.key() || entry.key == self.pool.key(),
+            ErrorCode::PoolCreationNotAllowed
+        );
+
+        Ok(())
+    }
+
+    // Blocked mints never get a pool, unreviewed ones must not be able to freeze or take the vault funds
+    fn check_mint(
+        mint: &InterfaceAccount<Mint>,
+        token_registry_entry: &AccountInfo,
+    ) -> Result<Option<TokenStatus>> {
+        let status = TokenRegistryEntry::status_of(token_registry_entry)?;
+        match status {
+            Some(TokenStatus::Blocked) => return err!(ErrorCode::TokenBlocked),
+            Some(TokenStatus::Verified) => return Ok(status),
+            None => {}
+        }
+
+        require!(
+            mint.freeze_authority.is_none(),
+            ErrorCode::UnsafeMintAuthority
+        );
+
+        let mint_info = mint.to_account_info();
+        let data = mint_info.try_borrow_data()?;
+        let extensions = StateWithExtensions::<MintState>::unpack(&data)?.get_extension_types()?;
+        require!(
+            !extensions
+                .iter()
+                .any(|extension| UNSAFE_MINT_EXTENSIONS.contains(extension)),
+            ErrorCode::UnsafeMintExtension
+        );
+
+        Ok(status)
+    }
+
+    fn create_lp_metadata(
+        &self,
+        lp_metadata: &UncheckedAccount<'info>,
+        metadata_program: &UncheckedAccount<'info>,
+    ) -> Result<()> {
+        let symbol_x = mint_symbol(
+            &self.mint_x.to_account_info(),
+            self.mint_x_metadata.as_ref().map(|metadata| metadata.as_ref()),
+        );
+        let symbol_y = mint_symbol(
+            &self.mint_y.to_account_info(),
+            self.mint_y_metadata.as_ref().map(|metadata| metadata.as_ref()),
+        );
+
+        let mint_x_bytes = self.mint_x.key().to_bytes();
+        let mint_y_bytes = self.mint_y.key().to_bytes();
+
+        let seeds = [
+            b"pool",
+            mint_x_bytes.as_ref(),
+            mint_y_bytes.as_ref(),
+            &[self.pool.pool_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        create_lp_metadata(
+            lp_metadata,
+            &self.mint_lp.to_account_info(),
+            &self.pool.to_account_info(),
+            &self.creator.to_account_info(),
+            &self.system_program.to_account_info(),
+            metadata_program,
+            lp_name(&symbol_x, &symbol_y),
+            signer_seeds,
+        )
+    }
+
+    fn charge_creation_fee(&self) -> Result<()> {
+        if self.config.pool_creation_fee == 0 {
+            return Ok(());
+        }
+
+        let cpi_accounts = Transfer {
+            from: self.creator.to_account_info(),
+            to: self.protocol_fee_account.to_account_info(),
+        };
+        let cpi_context = CpiContext::new(self.system_program.to_account_info(), cpi_accounts);
+
+        transfer(cpi_context, self.config.pool_creation_fee)
+    }
+
     pub fn deposit_tokens(&self, lp_tokens_amount:u64, max_x_tokens:u64, max_y_tokens:u64)->Result<()>{
         require!(lp_tokens_amount > 0, ErrorCode::InvalidAmount);
 
         let (x,y) = match self.pool_vault_x_ata.amount == 0 && self.pool_vault_y_ata.amount ==0 && self.mint_lp.supply == 0{
             true => (max_x_tokens, max_y_tokens),
             false => {
-                let tokens_amount = ConstantProduct::xy_deposit_amounts_from_l(self.pool_vault_x_ata.amount, self.pool_vault_y_ata.amount, self.mint_lp.supply, lp_tokens_amount, 6).unwrap();
+                let quote = LiquidityQuote::deposit(self.pool_vault_x_ata.amount, self.pool_vault_y_ata.amount, self.mint_lp.supply, lp_tokens_amount)?;
 
-                (tokens_amount.x, tokens_amount.y)
+                (quote.x, quote.y)
             },
         };
 
@@ -1,6 +1,6 @@
 use anchor_lang::prelude::*;
 
-use crate::Profile;
+use crate::{Profile, ProfileIdEntry};
 
 #[derive(Accounts)]
 #[instruction(profile_id:u64)]
@@ -17,6 +17,16 @@ pub struct CreateProfile<'info> {
     )]
     pub profile: Account<'info, Profile>,
 
+    // Fails the creation when another profile holds the id
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"profile_id", profile_id.to_le_bytes().as_ref()],
+        bump,
+        space = ProfileIdEntry::INIT_SPACE
+    )]
+    pub profile_id_entry: Account<'info, ProfileIdEntry>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -33,6 +43,54 @@ impl CreateProfile<'_> {
             expiration_timestamp: now + one_month_in_seconds,
             locked: false,
             profile_bump: bumps.profile,
+            version: Profile::VERSION,
+            tier: 0,
+            reserved: [0; 31],
+        });
+
+        self.profile_id_entry.set_inner(ProfileIdEntry {
+            profile_id,
+            profile: self.profile.key(),
+            creator: self.creator.key(),
+            profile_id_bump: bumps.profile_id_entry,
+        });
+
+        Ok(())
+    }
+}
+
+// Reserving the id of a profile created before ids were registered. The first
+// profile to claim an id keeps it.
+#[derive(Accounts)]
+pub struct RegisterProfileId<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        seeds = [b"profile", creator.key().as_ref()],
+        bump = profile.profile_bump,
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"profile_id", profile.profile_id.to_le_bytes().as_ref()],
+        bump,
+        space = ProfileIdEntry::INIT_SPACE
+    )]
+    pub profile_id_entry: Account<'info, ProfileIdEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl RegisterProfileId<'_> {
+    pub fn register_profile_id(&mut self, bumps: RegisterProfileIdBumps) -> Result<()> {
+        self.profile_id_entry.set_inner(ProfileIdEntry {
+            profile_id: self.profile.profile_id,
+            profile: self.profile.key(),
+            creator: self.creator.key(),
+            profile_id_bump: bumps.profile_id_entry,
         });
 
         Ok(())
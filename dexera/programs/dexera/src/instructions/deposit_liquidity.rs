@@ -5,9 +5,11 @@ use anchor_spl::{
         mint_to, transfer_checked, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
     },
 };
-use constant_product_curve::ConstantProduct;
 
-use crate::{errors::ErrorCode, Pool};
+use crate::{
+    errors::ErrorCode, utils::check_deadline, LiquidityQuote, Pool, PoolStats, ProtocolConfig,
+    Twamm, VirtualOrders,
+};
 
 #[derive(Accounts)]
 pub struct DepositLiquidity<'info> {
@@ -18,13 +20,20 @@ pub struct DepositLiquidity<'info> {
     pub mint_y: InterfaceAccount<'info, Mint>,
 
     #[account(
+        mut,
         seeds = [b"lp", pool.key().as_ref()],
         bump = pool.mint_lp_bump,
-        mint::decimals = 6,
+        mint::decimals = pool.lp_decimals,
         mint::authority = pool,
     )]
     pub mint_lp: InterfaceAccount<'info, Mint>,
 
+    #[account(
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
     #[account(
         mut,
         associated_token::mint = mint_x,
@@ -68,6 +77,51 @@ pub struct DepositLiquidity<'info> {
     )]
     pub pool: Account<'info, Pool>,
 
+    // The pool's TWAMM, executed up to now before the deposit while it has orders
+    #[account(
+        mut,
+        seeds = [b"twamm", pool.key().as_ref()],
+        bump = twamm.twamm_bump,
+    )]
+    pub twamm: Option<Box<Account<'info, Twamm>>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_x.key().as_ref()],
+        bump = pool.twamm_vault_x_bump,
+    )]
+    pub twamm_vault_x: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_y.key().as_ref()],
+        bump = pool.twamm_vault_y_bump,
+    )]
+    pub twamm_vault_y: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    // Paid the fees of the TWAMM's fills, required along with it
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config.protocol_fee_account,
+        associated_token::token_program = token_program,
+    )]
+    pub protocol_fee_ata: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"creator_fee", pool.key().as_ref()],
+        bump = pool.creator_fee_vault_bump,
+    )]
+    pub creator_fee_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_stats", pool.key().as_ref()],
+        bump = pool_stats.stats_bump,
+    )]
+    pub pool_stats: Option<Box<Account<'info, PoolStats>>>,
+
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
@@ -75,29 +129,29 @@ pub struct DepositLiquidity<'info> {
 
 impl DepositLiquidity<'_> {
     pub fn deposit_liquidity(
-        &self,
+        &mut self,
         lp_tokens_amount: u64,
         max_x_tokens: u64,
         max_y_tokens: u64,
+        deadline: Option<i64>,
     ) -> Result<()> {
         require!(!self.pool.locked, ErrorCode::AMMLocked);
+        check_deadline(deadline)?;
         require!(lp_tokens_amount > 0, ErrorCode::InvalidAmount);
+        self.execute_twamm()?;
 
-        let (x, y) = if self.pool_vault_x_ata.amount == 0
-            && self.pool_vault_y_ata.amount == 0
-            && self.mint_lp.supply == 0
-        {
+        // Without LP tokens the pool starts afresh, tokens sent to the reserves since the last
+        // withdrawal go to the first depositor
+        let (x, y) = if self.mint_lp.supply == 0 {
             (max_x_tokens, max_y_tokens)
         } else {
-            let tokens_amount = ConstantProduct::xy_deposit_amounts_from_l(
+            let quote = LiquidityQuote::deposit(
                 self.pool_vault_x_ata.amount,
                 self.pool_vault_y_ata.amount,
                 self.mint_lp.supply,
                 lp_tokens_amount,
-                6,
-            )
-            .unwrap();
-            (tokens_amount.x, tokens_amount.y)
+            )?;
+            (quote.x, quote.y)
         };
 
         require!(max_x_tokens >= x, ErrorCode::InsufficientTokenX);
@@ -110,6 +164,28 @@ impl DepositLiquidity<'_> {
         Ok(())
     }
 
+    fn execute_twamm(&mut self) -> Result<()> {
+        VirtualOrders {
+            config: &self.config,
+            pool: &self.pool,
+            mint_x: &self.mint_x,
+            mint_y: &self.mint_y,
+            lp_supply: self.mint_lp.supply,
+            pool_vault_x_ata: &mut self.pool_vault_x_ata,
+            pool_vault_y_ata: &mut self.pool_vault_y_ata,
+            twamm: self.twamm.as_deref_mut(),
+            twamm_vault_x: self.twamm_vault_x.as_deref(),
+            twamm_vault_y: self.twamm_vault_y.as_deref(),
+            protocol_fee_ata: self.protocol_fee_ata.as_deref(),
+            creator_fee_vault: self.creator_fee_vault.as_deref(),
+            pool_stats: self.pool_stats.as_deref_mut(),
+            token_program: &self.token_program,
+        }
+        .execute()?;
+
+        Ok(())
+    }
+
     fn deposit_token(&self, is_x: bool, amount: u64) -> Result<()> {
         let cpi_program = self.token_program.to_account_info();
 
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::{Pool, PoolStats};
+
+// Stats for pools created before they existed, open to anyone. Swaps need them.
+#[derive(Accounts)]
+pub struct InitPoolStats<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"pool_stats", pool.key().as_ref()],
+        space = PoolStats::INIT_SPACE,
+        bump,
+    )]
+    pub pool_stats: Account<'info, PoolStats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl InitPoolStats<'_> {
+    pub fn init_pool_stats(&mut self, bumps: InitPoolStatsBumps) -> Result<()> {
+        self.pool_stats.set_inner(PoolStats::new(
+            self.pool.key(),
+            bumps.pool_stats,
+            Clock::get()?.unix_timestamp,
+        ));
+
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 #![allow(unexpected_cfgs)]
 use anchor_lang::prelude::*;
 
-use crate::ProtocolConfig;
+use crate::{errors::ErrorCode, ProtocolConfig, BASIS_POINTS};
 
 // Initializing DEX
 #[derive(Accounts)]
@@ -26,12 +26,33 @@ pub struct InitializeProtocol<'info> {
 }
 
 impl InitializeProtocol<'_> {
-    pub fn initialize_protocol(&mut self, fee: u16, bumps: InitializeProtocolBumps) -> Result<()> {
+    pub fn initialize_protocol(
+        &mut self,
+        fee: u16,
+        max_integrator_fee: u16,
+        bumps: InitializeProtocolBumps,
+    ) -> Result<()> {
+        require!(fee < BASIS_POINTS, ErrorCode::InvalidFee);
+        require!(max_integrator_fee <= BASIS_POINTS, ErrorCode::InvalidConfig);
+
         self.config.set_inner(ProtocolConfig {
             admin: self.admin.key(),
             protocol_fee_account: self.protocol_fee_account.key(),
             config_bump: bumps.config,
             fee,
+            max_integrator_fee,
+            pool_creation_fee: 0,
+            permissioned_pool_creation: false,
+            max_creator_fee: 0,
+            version: ProtocolConfig::VERSION,
+            compound_protocol_fees: false,
+            treasury_bump: 0,
+            tier_fee_discounts: [0; 3],
+            crank_reward_bps: 0,
+            crank_interval: 0,
+            max_crank_reward: 0,
+            referrer_fee_share: ProtocolConfig::DEFAULT_REFERRER_FEE_SHARE,
+            reserved: [0; 40],
         });
         Ok(())
     }
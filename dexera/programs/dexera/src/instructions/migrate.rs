@@ -0,0 +1,175 @@
+use anchor_lang::{
+    error::ErrorCode as AnchorErrorCode,
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use crate::{errors::ErrorCode, Pool, Profile, ProtocolConfig, BASIS_POINTS};
+
+// Upgrading accounts written by an older program version in place, admin only.
+// The config goes first, the other migrations check the admin against it.
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// CHECK: May still have an old layout, the admin is checked once it is upgraded
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl MigrateConfig<'_> {
+    pub fn migrate_config(&mut self) -> Result<()> {
+        let admin = self.admin.key();
+
+        migrate_account(
+            &self.config,
+            &self.admin,
+            &self.system_program,
+            ProtocolConfig::INIT_SPACE,
+            |config: &mut ProtocolConfig| {
+                require_keys_eq!(config.admin, admin, AnchorErrorCode::ConstraintHasOne);
+                require!(
+                    config.version < ProtocolConfig::VERSION,
+                    ErrorCode::AlreadyMigrated
+                );
+
+                // Configs before version 2 split referred fees two thirds to the referrer
+                if config.version < 2 {
+                    config.referrer_fee_share = ProtocolConfig::DEFAULT_REFERRER_FEE_SHARE;
+                }
+                config.version = ProtocolConfig::VERSION;
+
+                Ok(())
+            },
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin,
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: May still have an old layout, the discriminator is checked once it is upgraded
+    #[account(mut, owner = crate::ID)]
+    pub pool: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl MigratePool<'_> {
+    pub fn migrate_pool(&mut self) -> Result<()> {
+        migrate_account(
+            &self.pool,
+            &self.admin,
+            &self.system_program,
+            Pool::INIT_SPACE,
+            |pool: &mut Pool| {
+                require!(pool.version < Pool::VERSION, ErrorCode::AlreadyMigrated);
+
+                // Pools from before the price impact cap read 0, which would reject any
+                // trade with an impact. create_pool starts them without a cap.
+                if pool.version == 0 && pool.max_price_impact_bps == 0 {
+                    pool.max_price_impact_bps = BASIS_POINTS;
+                }
+                if pool.version < 2 {
+                    pool.lp_decimals = Pool::LEGACY_LP_DECIMALS;
+                }
+                pool.version = Pool::VERSION;
+
+                Ok(())
+            },
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct MigrateProfile<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin,
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: May still have an old layout, the discriminator is checked once it is upgraded
+    #[account(mut, owner = crate::ID)]
+    pub profile: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl MigrateProfile<'_> {
+    pub fn migrate_profile(&mut self) -> Result<()> {
+        migrate_account(
+            &self.profile,
+            &self.admin,
+            &self.system_program,
+            Profile::INIT_SPACE,
+            |profile: &mut Profile| {
+                require!(
+                    profile.version < Profile::VERSION,
+                    ErrorCode::AlreadyMigrated
+                );
+
+                profile.version = Profile::VERSION;
+
+                Ok(())
+            },
+        )
+    }
+}
+
+// Grows the account to `space`, the payer covering the extra rent, then lets `upgrade`
+// bring the fields up to date. Fields are only ever appended or carved out of `reserved`,
+// so an old layout is a prefix of the new one and the zeroed tail reads as defaults.
+// Version 0 is every layout from before the version byte existed.
+fn migrate_account<'info, T: AccountSerialize + AccountDeserialize>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    upgrade: impl FnOnce(&mut T) -> Result<()>,
+) -> Result<()> {
+    if account.data_len() < space {
+        let missing_rent = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(account.lamports());
+        if missing_rent > 0 {
+            let cpi_accounts = Transfer {
+                from: payer.to_account_info(),
+                to: account.clone(),
+            };
+            let cpi_context = CpiContext::new(system_program.to_account_info(), cpi_accounts);
+            transfer(cpi_context, missing_rent)?;
+        }
+
+        // The locked solana release has no resize yet, newer ones deprecate realloc
+        #[allow(deprecated)]
+        account.realloc(space, true)?;
+    }
+
+    let mut state = T::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    upgrade(&mut state)?;
+    state.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+
+    Ok(())
+}
@@ -1,12 +1,27 @@
 pub mod create_profile;
 pub use create_profile::*;
 
+pub mod profile_tier;
+pub use profile_tier::*;
+
 pub mod initialize_protocol;
 pub use initialize_protocol::*;
 
 pub mod update_protocol_config;
 pub use update_protocol_config::*;
 
+pub mod update_pool_config;
+pub use update_pool_config::*;
+
+pub mod pool_creator;
+pub use pool_creator::*;
+
+pub mod allowlist;
+pub use allowlist::*;
+
+pub mod token_registry;
+pub use token_registry::*;
+
 pub mod create_pool;
 pub use create_pool::*;
 
@@ -16,5 +31,35 @@ pub use deposit_liquidity::*;
 pub mod withdraw_liquidity;
 pub use withdraw_liquidity::*;
 
+pub mod close_pool;
+pub use close_pool::*;
+
+pub mod init_pool_stats;
+pub use init_pool_stats::*;
+
+pub mod protocol_liquidity;
+pub use protocol_liquidity::*;
+
 pub mod swap_tokens;
 pub use swap_tokens::*;
+
+pub mod batch_auction;
+pub use batch_auction::*;
+
+pub mod twamm_orders;
+pub use twamm_orders::*;
+
+pub mod token_governance;
+pub use token_governance::*;
+
+pub mod crank_pool;
+pub use crank_pool::*;
+
+pub mod position_nft;
+pub use position_nft::*;
+
+pub mod quote;
+pub use quote::*;
+
+pub mod migrate;
+pub use migrate::*;
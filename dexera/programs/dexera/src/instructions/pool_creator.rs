@@ -0,0 +1,170 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{errors::ErrorCode, Pool, ProtocolConfig};
+
+// Settings the pool creator controls
+#[derive(Accounts)]
+pub struct UpdatePoolCreator<'info> {
+    pub creator: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub mint_x: InterfaceAccount<'info, Mint>,
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        has_one = creator,
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+        ],
+        bump = pool.pool_bump,
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+impl UpdatePoolCreator<'_> {
+    pub fn update_creator_fee(&mut self, creator_fee: u16) -> Result<()> {
+        require!(
+            creator_fee <= self.config.max_creator_fee,
+            ErrorCode::CreatorFeeTooHigh
+        );
+
+        self.pool.creator_fee = creator_fee;
+
+        Ok(())
+    }
+
+    // Unclaimed fees move with the role
+    pub fn transfer_pool_creator(&mut self, new_creator: Pubkey) -> Result<()> {
+        self.pool.creator = new_creator;
+
+        Ok(())
+    }
+}
+
+// Paying the accrued creator fees out to the creator
+#[derive(Accounts)]
+pub struct ClaimCreatorFee<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub mint_x: InterfaceAccount<'info, Mint>,
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        has_one = creator,
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+        ],
+        bump = pool.pool_bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        seeds = [b"creator_fee", pool.key().as_ref()],
+        bump = pool.creator_fee_vault_bump,
+    )]
+    pub creator_fee_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = creator,
+        associated_token::mint = mint_y,
+        associated_token::authority = creator,
+        associated_token::token_program = token_program,
+    )]
+    pub creator_mint_y_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl ClaimCreatorFee<'_> {
+    pub fn claim_creator_fee(&self) -> Result<()> {
+        let amount = self.creator_fee_vault.amount;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let mint_x_bytes = self.mint_x.key().to_bytes();
+        let mint_y_bytes = self.mint_y.key().to_bytes();
+
+        let seeds = [
+            b"pool",
+            mint_x_bytes.as_ref(),
+            mint_y_bytes.as_ref(),
+            &[self.pool.pool_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.creator_fee_vault.to_account_info(),
+            mint: self.mint_y.to_account_info(),
+            to: self.creator_mint_y_ata.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer_checked(cpi_context, amount, self.mint_y.decimals)
+    }
+}
+
+// Creator fee vault for pools created before it existed, open to anyone. Swaps need it.
+#[derive(Accounts)]
+pub struct InitCreatorFeeVault<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub mint_x: InterfaceAccount<'info, Mint>,
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+        ],
+        bump = pool.pool_bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"creator_fee", pool.key().as_ref()],
+        bump,
+        token::mint = mint_y,
+        token::authority = pool,
+        token::token_program = token_program,
+    )]
+    pub creator_fee_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl InitCreatorFeeVault<'_> {
+    pub fn init_creator_fee_vault(&mut self, bumps: InitCreatorFeeVaultBumps) -> Result<()> {
+        self.pool.creator_fee_vault_bump = bumps.creator_fee_vault;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,263 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        burn, mint_to, set_authority, spl_token_2022::instruction::AuthorityType, transfer_checked,
+        Burn, Mint, MintTo, SetAuthority, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{errors::ErrorCode, Pool, Position};
+
+// Locking LP tokens behind a new position NFT, minted by the pool to the owner
+#[derive(Accounts)]
+pub struct WrapPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.mint_lp_bump,
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+        ],
+        bump = pool.pool_bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [b"position_vault", pool.key().as_ref()],
+        bump,
+        token::mint = mint_lp,
+        token::authority = pool,
+        token::token_program = token_program,
+    )]
+    pub position_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_lp_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // A fresh keypair, its mint authority is dropped once the NFT is minted
+    #[account(
+        init,
+        payer = owner,
+        mint::decimals = 0,
+        mint::authority = pool,
+        mint::token_program = token_program,
+    )]
+    pub position_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = position_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_position_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"position", position_mint.key().as_ref()],
+        space = Position::INIT_SPACE,
+        bump,
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl WrapPosition<'_> {
+    pub fn wrap_position(&mut self, lp_amount: u64, bumps: WrapPositionBumps) -> Result<()> {
+        require!(lp_amount > 0, ErrorCode::InvalidAmount);
+
+        let cpi_accounts = TransferChecked {
+            from: self.owner_lp_ata.to_account_info(),
+            mint: self.mint_lp.to_account_info(),
+            to: self.position_vault.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+        let cpi_context = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_context, lp_amount, self.mint_lp.decimals)?;
+
+        self.mint_position_nft()?;
+
+        self.pool.position_vault_bump = bumps.position_vault;
+        self.position.set_inner(Position {
+            pool: self.pool.key(),
+            position_mint: self.position_mint.key(),
+            lp_amount,
+            created_at: Clock::get()?.unix_timestamp,
+            position_bump: bumps.position,
+        });
+
+        Ok(())
+    }
+
+    // Mints the single token, then drops the mint authority so there can't be another
+    fn mint_position_nft(&self) -> Result<()> {
+        let mint_x_bytes = self.mint_x.key().to_bytes();
+        let mint_y_bytes = self.mint_y.key().to_bytes();
+
+        let seeds = [
+            b"pool",
+            mint_x_bytes.as_ref(),
+            mint_y_bytes.as_ref(),
+            &[self.pool.pool_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = MintTo {
+            mint: self.position_mint.to_account_info(),
+            to: self.owner_position_ata.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        mint_to(cpi_context, 1)?;
+
+        let cpi_accounts = SetAuthority {
+            current_authority: self.pool.to_account_info(),
+            account_or_mint: self.position_mint.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        set_authority(cpi_context, AuthorityType::MintTokens, None)
+    }
+}
+
+// Burning a position NFT for its LP tokens, by whoever holds it now
+#[derive(Accounts)]
+pub struct UnwrapPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.mint_lp_bump,
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+        ],
+        bump = pool.pool_bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        mut,
+        seeds = [b"position_vault", pool.key().as_ref()],
+        bump = pool.position_vault_bump,
+    )]
+    pub position_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint_lp,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_lp_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub position_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    // Any of the owner's accounts for the NFT, it may have been moved out of the ATA
+    #[account(
+        mut,
+        token::mint = position_mint,
+        token::authority = owner,
+        token::token_program = token_program,
+    )]
+    pub owner_position_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = pool,
+        has_one = position_mint,
+        seeds = [b"position", position_mint.key().as_ref()],
+        bump = position.position_bump,
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl UnwrapPosition<'_> {
+    pub fn unwrap_position(&mut self) -> Result<()> {
+        require!(
+            self.owner_position_account.amount == 1,
+            ErrorCode::NotPositionHolder
+        );
+
+        let cpi_accounts = Burn {
+            mint: self.position_mint.to_account_info(),
+            from: self.owner_position_account.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+        let cpi_context = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        burn(cpi_context, 1)?;
+
+        let mint_x_bytes = self.mint_x.key().to_bytes();
+        let mint_y_bytes = self.mint_y.key().to_bytes();
+
+        let seeds = [
+            b"pool",
+            mint_x_bytes.as_ref(),
+            mint_y_bytes.as_ref(),
+            &[self.pool.pool_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.position_vault.to_account_info(),
+            mint: self.mint_lp.to_account_info(),
+            to: self.owner_lp_ata.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer_checked(cpi_context, self.position.lp_amount, self.mint_lp.decimals)
+    }
+}
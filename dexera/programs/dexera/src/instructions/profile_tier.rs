@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::ErrorCode, Profile, ProtocolConfig};
+
+// Setting the fee discount tier of a profile, admin only
+#[derive(Accounts)]
+pub struct UpdateProfileTier<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin,
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"profile", profile.creator.as_ref()],
+        bump = profile.profile_bump,
+    )]
+    pub profile: Account<'info, Profile>,
+}
+
+impl UpdateProfileTier<'_> {
+    pub fn update_profile_tier(&mut self, tier: u8) -> Result<()> {
+        require!(tier <= Profile::MAX_TIER, ErrorCode::InvalidProfileTier);
+
+        self.profile.tier = tier;
+
+        Ok(())
+    }
+}
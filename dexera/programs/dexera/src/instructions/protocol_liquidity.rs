@@ -0,0 +1,309 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        burn, transfer_checked, Burn, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    errors::ErrorCode, LiquidityQuote, Pool, PoolStats, ProtocolConfig, Twamm, VirtualOrders,
+};
+
+// Taking the treasury's liquidity out of a pool, admin only. The tokens go to the
+// protocol fee account, like protocol fees that aren't compounded.
+#[derive(Accounts)]
+pub struct WithdrawProtocolLiquidity<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin,
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// CHECK: Signs for the treasury's LP tokens
+    #[account(
+        seeds = [b"treasury"],
+        bump = config.treasury_bump,
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    /// CHECK: Receives the withdrawn tokens, checked against the config
+    #[account(address = config.protocol_fee_account)]
+    pub protocol_fee_account: UncheckedAccount<'info>,
+
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.mint_lp_bump,
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+        ],
+        bump = pool.pool_bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_vault_x_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_vault_y_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub treasury_lp_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        associated_token::mint = mint_x,
+        associated_token::authority = protocol_fee_account,
+        associated_token::token_program = token_program,
+    )]
+    pub protocol_mint_x_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        associated_token::mint = mint_y,
+        associated_token::authority = protocol_fee_account,
+        associated_token::token_program = token_program,
+    )]
+    pub protocol_mint_y_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // The pool's TWAMM, executed up to now before the withdrawal while it has orders
+    #[account(
+        mut,
+        seeds = [b"twamm", pool.key().as_ref()],
+        bump = twamm.twamm_bump,
+    )]
+    pub twamm: Option<Box<Account<'info, Twamm>>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_x.key().as_ref()],
+        bump = pool.twamm_vault_x_bump,
+    )]
+    pub twamm_vault_x: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_y.key().as_ref()],
+        bump = pool.twamm_vault_y_bump,
+    )]
+    pub twamm_vault_y: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    // Paid the fees of the TWAMM's fills along with protocol_mint_y_ata, required with it
+    #[account(
+        mut,
+        seeds = [b"creator_fee", pool.key().as_ref()],
+        bump = pool.creator_fee_vault_bump,
+    )]
+    pub creator_fee_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_stats", pool.key().as_ref()],
+        bump = pool_stats.stats_bump,
+    )]
+    pub pool_stats: Option<Box<Account<'info, PoolStats>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawProtocolLiquidity<'info> {
+    pub fn withdraw_protocol_liquidity(
+        &mut self,
+        lp_tokens_amount: u64,
+        min_x: u64,
+        min_y: u64,
+    ) -> Result<()> {
+        VirtualOrders {
+            config: &self.config,
+            pool: &self.pool,
+            mint_x: &self.mint_x,
+            mint_y: &self.mint_y,
+            lp_supply: self.mint_lp.supply,
+            pool_vault_x_ata: &mut self.pool_vault_x_ata,
+            pool_vault_y_ata: &mut self.pool_vault_y_ata,
+            twamm: self.twamm.as_deref_mut(),
+            twamm_vault_x: self.twamm_vault_x.as_deref(),
+            twamm_vault_y: self.twamm_vault_y.as_deref(),
+            protocol_fee_ata: Some(&*self.protocol_mint_y_ata),
+            creator_fee_vault: self.creator_fee_vault.as_deref(),
+            pool_stats: self.pool_stats.as_deref_mut(),
+            token_program: &self.token_program,
+        }
+        .execute()?;
+
+        let amounts = LiquidityQuote::withdraw(
+            self.pool_vault_x_ata.amount,
+            self.pool_vault_y_ata.amount,
+            self.mint_lp.supply,
+            lp_tokens_amount,
+        )?;
+
+        require!(amounts.x >= min_x, ErrorCode::InsufficientTokenX);
+        require!(amounts.y >= min_y, ErrorCode::InsufficientTokenY);
+
+        self.withdraw_tokens(
+            &self.pool_vault_x_ata,
+            &self.mint_x,
+            &self.protocol_mint_x_ata,
+            amounts.x,
+        )?;
+        self.withdraw_tokens(
+            &self.pool_vault_y_ata,
+            &self.mint_y,
+            &self.protocol_mint_y_ata,
+            amounts.y,
+        )?;
+
+        let seeds = [b"treasury".as_ref(), &[self.config.treasury_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = Burn {
+            mint: self.mint_lp.to_account_info(),
+            from: self.treasury_lp_ata.to_account_info(),
+            authority: self.treasury.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        burn(cpi_context, lp_tokens_amount)
+    }
+
+    fn withdraw_tokens(
+        &self,
+        from: &InterfaceAccount<'info, TokenAccount>,
+        mint: &InterfaceAccount<'info, Mint>,
+        to: &InterfaceAccount<'info, TokenAccount>,
+        amount: u64,
+    ) -> Result<()> {
+        let mint_x_bytes = self.mint_x.key().to_bytes();
+        let mint_y_bytes = self.mint_y.key().to_bytes();
+
+        let seeds = [
+            b"pool",
+            mint_x_bytes.as_ref(),
+            mint_y_bytes.as_ref(),
+            &[self.pool.pool_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: from.to_account_info(),
+            mint: mint.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer_checked(cpi_context, amount, mint.decimals)
+    }
+}
+
+// Handing the treasury's LP tokens to another owner, admin only. The liquidity stays in the pool.
+#[derive(Accounts)]
+pub struct RedirectProtocolLiquidity<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin,
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: Signs for the treasury's LP tokens
+    #[account(
+        seeds = [b"treasury"],
+        bump = config.treasury_bump,
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    /// CHECK: Any wallet can receive the LP tokens
+    pub recipient: UncheckedAccount<'info>,
+
+    pub mint_lp: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub treasury_lp_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        associated_token::mint = mint_lp,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program,
+    )]
+    pub recipient_lp_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl RedirectProtocolLiquidity<'_> {
+    pub fn redirect_protocol_liquidity(&mut self, lp_tokens_amount: u64) -> Result<()> {
+        require!(lp_tokens_amount > 0, ErrorCode::InvalidAmount);
+
+        let seeds = [b"treasury".as_ref(), &[self.config.treasury_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.treasury_lp_ata.to_account_info(),
+            mint: self.mint_lp.to_account_info(),
+            to: self.recipient_lp_ata.to_account_info(),
+            authority: self.treasury.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer_checked(cpi_context, lp_tokens_amount, self.mint_lp.decimals)
+    }
+}
@@ -0,0 +1,513 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use constant_product_curve::{ConstantProduct, CurveError, LiquidityPair};
+
+use crate::{errors::ErrorCode, utils::bps_of, Pool, ProtocolConfig, Twamm, BASIS_POINTS};
+
+// Read-only view of a pool, used to simulate instructions before signing them
+#[derive(Accounts)]
+pub struct Quote<'info> {
+    pub mint_x: InterfaceAccount<'info, Mint>,
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.mint_lp_bump,
+        mint::decimals = pool.lp_decimals,
+        mint::authority = pool,
+    )]
+    pub mint_lp: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        associated_token::mint = mint_x,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_vault_x_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        associated_token::mint = mint_y,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_vault_y_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+        ],
+        bump = pool.pool_bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    // Only needed while the pool has TWAMM orders, quotes see the reserves after they run
+    #[account(
+        seeds = [b"twamm", pool.key().as_ref()],
+        bump = twamm.twamm_bump,
+    )]
+    pub twamm: Option<Box<Account<'info, Twamm>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SwapQuote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    pub referrer_fee: u64,
+    pub protocol_fee: u64,
+    pub integrator_fee: u64,
+    pub creator_fee: u64,
+    // Variable fee of the dynamic fee mode, left in the pool for the LPs
+    pub lp_fee: u64,
+    // How far the execution price is below the spot price, fees excluded
+    pub price_impact_bps: u16,
+    // How far the swap moves the spot price, what the volatility accumulator adds up
+    pub price_move_bps: u32,
+}
+
+// Outcome of a batch at its uniform clearing price. Orders on both sides are matched
+// against each other and only the imbalance trades against the curve.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BatchSettlement {
+    // Direction of the imbalance: X into the pool for Y when set
+    pub is_x: bool,
+    pub pool_amount_in: u64,
+    pub pool_amount_out: u64,
+    // Shared pro rata by the sellers of Y and of X respectively, fees excluded
+    pub x_out: u64,
+    pub y_out: u64,
+    // Swap and creator fees of both sides, in Y
+    pub fee: u64,
+    pub creator_fee: u64,
+    pub price_impact_bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LiquidityQuote {
+    pub lp_tokens_amount: u64,
+    pub x: u64,
+    pub y: u64,
+}
+
+// Everything the pool maths depends on, so quotes can be computed on-chain and off-chain alike
+pub struct PoolSnapshot<'a> {
+    pub config: &'a ProtocolConfig,
+    pub pool: &'a Pool,
+    pub vault_x: u64,
+    pub vault_y: u64,
+    pub lp_supply: u64,
+    // Unix timestamp the quote is for, the accumulated volatility fades with it
+    pub now: i64,
+}
+
+impl PoolSnapshot<'_> {
+    pub fn quote_swap(
+        &self,
+        is_x: bool,
+        amount_in: u64,
+        integrator_fee: u16,
+        referred: bool,
+    ) -> Result<SwapQuote> {
+        require!(!self.pool.locked, ErrorCode::AMMLocked);
+        require!(self.pool.batch_duration == 0, ErrorCode::BatchModeEnabled);
+        require!(amount_in > 0, ErrorCode::InvalidAmount);
+        let variable_fee = self.pool.variable_fee(self.now);
+        require!(
+            self.config.fee
+                + variable_fee
+                + self.config.effective_creator_fee(self.pool.creator_fee)
+                < BASIS_POINTS,
+            ErrorCode::InvalidFee
+        );
+        require!(
+            self.vault_x > 0 && self.vault_y > 0,
+            ErrorCode::ZeroLiquidity
+        );
+
+        let integrator_fee_amount = self
+            .config
+            .integrator_fee_amount(amount_in, integrator_fee)?;
+        let curve_amount_in = amount_in - integrator_fee_amount;
+        // Like the swap fee, the creator and variable fees are in Y: the input pays them when selling Y
+        let (creator_fee_in, lp_fee_in) = if is_x {
+            (0, 0)
+        } else {
+            (
+                self.config
+                    .creator_fee_amount(curve_amount_in, self.pool.creator_fee)?,
+                bps_of(curve_amount_in, variable_fee)?,
+            )
+        };
+
+        let (reserve_in, reserve_out) = if is_x {
+            (self.vault_x, self.vault_y)
+        } else {
+            (self.vault_y, self.vault_x)
+        };
+
+        // Fees are charged in Y: on the way in when selling Y, on the way out when buying it.
+        // Charging an X-denominated fee out of the Y vault would shrink k.
+        let (pair, curve_fee) = if is_x {
+            (LiquidityPair::X, 0)
+        } else {
+            (LiquidityPair::Y, self.config.fee)
+        };
+
+        let mut curve = ConstantProduct::init(
+            self.vault_x,
+            self.vault_y,
+            self.lp_supply,
+            curve_fee,
+            Some(self.pool.lp_decimals),
+        )
+        .map_err(ErrorCode::from)?;
+
+        // The reserves are non-zero, so a zero balance here means nothing would come out
+        let swap_result = curve
+            .swap(pair, curve_amount_in - creator_fee_in - lp_fee_in, 0)
+            .map_err(|error| match error {
+                CurveError::ZeroBalance => ErrorCode::AmountTooSmall,
+                error => ErrorCode::from(error),
+            })?;
+
+        let (fee, creator_fee, lp_fee, amount_out, priced_amount_in) = if is_x {
+            let fee = self.config.swap_fee_amount(swap_result.withdraw)?;
+            let creator_fee = self
+                .config
+                .creator_fee_amount(swap_result.withdraw, self.pool.creator_fee)?;
+            let lp_fee = bps_of(swap_result.withdraw, variable_fee)?;
+            (
+                fee,
+                creator_fee,
+                lp_fee,
+                swap_result.withdraw - fee - creator_fee - lp_fee,
+                curve_amount_in,
+            )
+        } else {
+            (
+                swap_result.fee,
+                creator_fee_in,
+                lp_fee_in,
+                swap_result.withdraw,
+                curve_amount_in - creator_fee_in - lp_fee_in - swap_result.fee,
+            )
+        };
+
+        // Only the LP fee stays behind, everything else leaves the vaults
+        let (vault_x_after, vault_y_after) = if is_x {
+            (
+                self.vault_x + curve_amount_in,
+                self.vault_y - amount_out - fee - creator_fee,
+            )
+        } else {
+            (
+                self.vault_x - amount_out,
+                self.vault_y + curve_amount_in - creator_fee - fee,
+            )
+        };
+        // Pools without the dynamic fee skip the extra maths
+        let price_move_bps = if self.pool.max_variable_fee > 0 {
+            price_move_bps(self.vault_x, self.vault_y, vault_x_after, vault_y_after)?
+        } else {
+            0
+        };
+
+        let (referrer_fee, protocol_fee) = self.config.split_fee(fee, referred);
+
+        Ok(SwapQuote {
+            amount_in,
+            amount_out,
+            fee,
+            referrer_fee,
+            protocol_fee,
+            integrator_fee: integrator_fee_amount,
+            creator_fee,
+            lp_fee,
+            price_impact_bps: price_impact_bps(
+                reserve_in,
+                reserve_out,
+                priced_amount_in,
+                swap_result.withdraw,
+            )?,
+            price_move_bps,
+        })
+    }
+
+    // Batches pay the swap and creator fees in Y like swaps do, but not the variable fee
+    pub fn settle_batch(&self, x_in: u64, y_in: u64) -> Result<BatchSettlement> {
+        require!(!self.pool.locked, ErrorCode::AMMLocked);
+        require!(
+            self.config.fee + self.config.effective_creator_fee(self.pool.creator_fee)
+                < BASIS_POINTS,
+            ErrorCode::InvalidFee
+        );
+        require!(
+            self.vault_x > 0 && self.vault_y > 0,
+            ErrorCode::ZeroLiquidity
+        );
+
+        let fee_in = self.config.swap_fee_amount(y_in)?;
+        let creator_fee_in = self
+            .config
+            .creator_fee_amount(y_in, self.pool.creator_fee)?;
+        let y_net = y_in - fee_in - creator_fee_in;
+
+        // Whichever side is worth more at the spot price trades its excess against the curve
+        let is_x = x_in as u128 * self.vault_y as u128 >= y_net as u128 * self.vault_x as u128;
+        let (pool_amount_in, pool_amount_out, x_out, y_gross, price_impact_bps) = if is_x {
+            let (amount_in, amount_out) = clearing_trade(self.vault_x, self.vault_y, x_in, y_net)?;
+            (
+                amount_in,
+                amount_out,
+                x_in - amount_in,
+                y_net + amount_out,
+                price_impact_bps(self.vault_x, self.vault_y, amount_in, amount_out)?,
+            )
+        } else {
+            let (amount_in, amount_out) = clearing_trade(self.vault_y, self.vault_x, y_net, x_in)?;
+            (
+                amount_in,
+                amount_out,
+                x_in + amount_out,
+                y_net - amount_in,
+                price_impact_bps(self.vault_y, self.vault_x, amount_in, amount_out)?,
+            )
+        };
+
+        let fee_out = self.config.swap_fee_amount(y_gross)?;
+        let creator_fee_out = self
+            .config
+            .creator_fee_amount(y_gross, self.pool.creator_fee)?;
+
+        Ok(BatchSettlement {
+            is_x,
+            pool_amount_in,
+            pool_amount_out,
+            x_out,
+            y_out: y_gross - fee_out - creator_fee_out,
+            fee: fee_in + fee_out,
+            creator_fee: creator_fee_in + creator_fee_out,
+            price_impact_bps,
+        })
+    }
+
+    pub fn quote_deposit(&self, lp_tokens_amount: u64) -> Result<LiquidityQuote> {
+        require!(!self.pool.locked, ErrorCode::AMMLocked);
+
+        LiquidityQuote::deposit(self.vault_x, self.vault_y, self.lp_supply, lp_tokens_amount)
+    }
+
+    pub fn quote_withdraw(&self, lp_tokens_amount: u64) -> Result<LiquidityQuote> {
+        LiquidityQuote::withdraw(self.vault_x, self.vault_y, self.lp_supply, lp_tokens_amount)
+    }
+}
+
+// Liquidity moves pro rata to the LP supply, always rounded in the pool's favour
+// so that deposits and withdrawals never dilute the remaining LPs
+impl LiquidityQuote {
+    pub fn deposit(
+        vault_x: u64,
+        vault_y: u64,
+        lp_supply: u64,
+        lp_tokens_amount: u64,
+    ) -> Result<Self> {
+        require!(lp_tokens_amount > 0, ErrorCode::InvalidAmount);
+
+        Ok(Self {
+            lp_tokens_amount,
+            x: pro_rata(vault_x, lp_tokens_amount, lp_supply, true)?,
+            y: pro_rata(vault_y, lp_tokens_amount, lp_supply, true)?,
+        })
+    }
+
+    pub fn withdraw(
+        vault_x: u64,
+        vault_y: u64,
+        lp_supply: u64,
+        lp_tokens_amount: u64,
+    ) -> Result<Self> {
+        require!(lp_tokens_amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            lp_tokens_amount <= lp_supply,
+            ErrorCode::InsufficientBalance
+        );
+
+        Ok(Self {
+            lp_tokens_amount,
+            x: pro_rata(vault_x, lp_tokens_amount, lp_supply, false)?,
+            y: pro_rata(vault_y, lp_tokens_amount, lp_supply, false)?,
+        })
+    }
+
+    // Y added on its own, by a compounded protocol fee, to a Y reserve that doesn't count it
+    // yet. lp_supply * y / (2 * vault_y + y) never exceeds lp_supply * (sqrt(1 + y / vault_y) - 1),
+    // so sqrt(k) per LP token can only grow. Amounts worth less than one LP token mint nothing.
+    pub fn compound(vault_y: u64, lp_supply: u64, y: u64) -> Result<Self> {
+        require!(vault_y > 0, ErrorCode::ZeroLiquidity);
+
+        let lp_tokens_amount = (lp_supply as u128)
+            .checked_mul(y as u128)
+            .ok_or(ErrorCode::Overflow)?
+            / (2 * vault_y as u128 + y as u128);
+
+        Ok(Self {
+            lp_tokens_amount: u64::try_from(lp_tokens_amount).map_err(|_| ErrorCode::Overflow)?,
+            x: 0,
+            y,
+        })
+    }
+}
+
+// reserve * lp_tokens_amount / lp_supply
+fn pro_rata(reserve: u64, lp_tokens_amount: u64, lp_supply: u64, round_up: bool) -> Result<u64> {
+    require!(lp_supply > 0, ErrorCode::ZeroLiquidity);
+
+    let numerator = (reserve as u128)
+        .checked_mul(lp_tokens_amount as u128)
+        .ok_or(ErrorCode::Overflow)?;
+    let mut amount = numerator / lp_supply as u128;
+    if round_up && numerator % lp_supply as u128 != 0 {
+        amount += 1;
+    }
+
+    u64::try_from(amount).map_err(|_| ErrorCode::Overflow.into())
+}
+
+// 1 - (amount_out / amount_in) / (reserve_out / reserve_in), in basis points
+fn price_impact_bps(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    amount_out: u64,
+) -> Result<u16> {
+    let spot_out = (amount_in as u128)
+        .checked_mul(reserve_out as u128)
+        .ok_or(ErrorCode::Overflow)?;
+    if spot_out == 0 {
+        return Ok(0);
+    }
+
+    let execution_out = (amount_out as u128)
+        .checked_mul(reserve_in as u128)
+        .ok_or(ErrorCode::Overflow)?;
+    let ratio_bps = execution_out
+        .checked_mul(BASIS_POINTS as u128)
+        .ok_or(ErrorCode::Overflow)?
+        / spot_out;
+
+    Ok((BASIS_POINTS as u128).saturating_sub(ratio_bps) as u16)
+}
+
+// reserve_out * amount_in / (reserve_in + amount_in), rounded down
+fn curve_amount_out(reserve_in: u64, reserve_out: u64, amount_in: u64) -> Result<u64> {
+    let amount_out = (reserve_out as u128)
+        .checked_mul(amount_in as u128)
+        .ok_or(ErrorCode::Overflow)?
+        / (reserve_in as u128 + amount_in as u128);
+
+    Ok(amount_out as u64)
+}
+
+// Splits the excess side's `amount` between the other side's `matched` and the curve.
+// Trading `traded` for `received` on the curve clears at received / traded, which leaves
+// (amount - traded) for `matched` on the other side. The price is uniform where both agree:
+// the largest `traded` at which the curve still pays at least the matched price.
+fn clearing_trade(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount: u64,
+    matched: u64,
+) -> Result<(u64, u64)> {
+    let clears = |traded: u64| -> Result<bool> {
+        let received = curve_amount_out(reserve_in, reserve_out, traded)?;
+        Ok((amount - traded) as u128 * received as u128 >= matched as u128 * traded as u128)
+    };
+
+    let (mut low, mut high) = (0, amount);
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if clears(mid)? {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    Ok((low, curve_amount_out(reserve_in, reserve_out, low)?))
+}
+
+// |(y_after / x_after) / (y / x) - 1|, in basis points
+fn price_move_bps(x: u64, y: u64, x_after: u64, y_after: u64) -> Result<u32> {
+    let before = (y as u128)
+        .checked_mul(x_after as u128)
+        .ok_or(ErrorCode::Overflow)?;
+    if before == 0 {
+        return Ok(u32::MAX);
+    }
+
+    let after = (y_after as u128)
+        .checked_mul(x as u128)
+        .ok_or(ErrorCode::Overflow)?;
+    let move_bps = after
+        .abs_diff(before)
+        .checked_mul(BASIS_POINTS as u128)
+        .ok_or(ErrorCode::Overflow)?
+        / before;
+
+    Ok(move_bps.min(u32::MAX as u128) as u32)
+}
+
+impl Quote<'_> {
+    // The pending TWAMM orders are run on a copy, the way the next instruction would run them
+    pub fn snapshot(&self) -> Result<PoolSnapshot<'_>> {
+        let clock = Clock::get()?;
+        let snapshot = |vault_x, vault_y| PoolSnapshot {
+            config: &self.config,
+            pool: &self.pool,
+            vault_x,
+            vault_y,
+            lp_supply: self.mint_lp.supply,
+            now: clock.unix_timestamp,
+        };
+        let (vault_x, vault_y) = (self.pool_vault_x_ata.amount, self.pool_vault_y_ata.amount);
+
+        match &self.twamm {
+            Some(twamm) => {
+                let execution =
+                    Twamm::clone(twamm).execute(snapshot(vault_x, vault_y), clock.slot)?;
+                Ok(snapshot(execution.vault_x, execution.vault_y))
+            }
+            None if self.pool.twamm_orders == 0 => Ok(snapshot(vault_x, vault_y)),
+            None => err!(ErrorCode::MissingTwamm),
+        }
+    }
+
+    pub fn quote_swap(
+        &self,
+        is_x: bool,
+        amount_in: u64,
+        integrator_fee: u16,
+        referred: bool,
+    ) -> Result<SwapQuote> {
+        self.snapshot()?
+            .quote_swap(is_x, amount_in, integrator_fee, referred)
+    }
+
+    pub fn quote_deposit(&self, lp_tokens_amount: u64) -> Result<LiquidityQuote> {
+        self.snapshot()?.quote_deposit(lp_tokens_amount)
+    }
+
+    pub fn quote_withdraw(&self, lp_tokens_amount: u64) -> Result<LiquidityQuote> {
+        self.snapshot()?.quote_withdraw(lp_tokens_amount)
+    }
+}
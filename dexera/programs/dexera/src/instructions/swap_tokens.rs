@@ -1,10 +1,12 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    associated_token::{get_associated_token_address, AssociatedToken}, token::{transfer_checked, TransferChecked}, token_interface::{Mint, TokenAccount, TokenInterface}
+    associated_token::AssociatedToken, token::{transfer_checked, TransferChecked}, token_interface::{mint_to, Mint, MintTo, TokenAccount, TokenInterface}
 };
-use constant_product_curve::{ConstantProduct, LiquidityPair};
 
-use crate::{errors::ErrorCode, Pool, Profile, ProtocolConfig};
+use crate::{
+    errors::ErrorCode, events::SwapEvent, utils::check_deadline, LiquidityQuote, Pool,
+    PoolSnapshot, PoolStats, Profile, ProtocolConfig, SwapQuote, Twamm, VirtualOrders,
+};
 
 #[derive(Accounts)]
 pub struct SwapTokens<'info> {
@@ -15,15 +17,27 @@ pub struct SwapTokens<'info> {
     pub mint_y: InterfaceAccount<'info, Mint>,
 
     #[account(
+        mut,
         seeds = [b"lp", pool.key().as_ref()],
         bump = pool.mint_lp_bump, 
-        mint::decimals = 6,
+        mint::decimals = pool.lp_decimals,
         mint::authority = pool,
     )]
     pub mint_lp: InterfaceAccount<'info, Mint>,
 
+    #[account(
+        seeds = [b"profile", profile.creator.as_ref()],
+        bump = profile.profile_bump,
+    )]
     pub profile: Option<Account<'info, Profile>>,
 
+    // The trader's own profile, for its tier's fee discount
+    #[account(
+        seeds = [b"profile", user.key().as_ref()],
+        bump = user_profile.profile_bump,
+    )]
+    pub user_profile: Option<Box<Account<'info, Profile>>>,
+
     #[account(
         seeds = [b"config"],
         bump,
@@ -61,6 +75,7 @@ pub struct SwapTokens<'info> {
     pub user_mint_y_ata: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
+        mut,
         seeds = [
             b"pool",
             mint_x.key().as_ref(),
@@ -70,9 +85,85 @@ pub struct SwapTokens<'info> {
     )]
     pub pool: Account<'info, Pool>,
 
-    /// CHECK: This is the referrer's ATA that will be created if it doesn't exist
+    /// CHECK: Owns the protocol fee ATA, checked against the config
+    #[account(address = config.protocol_fee_account)]
+    pub protocol_fee_account: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_y,
+        associated_token::authority = protocol_fee_account,
+        associated_token::token_program = token_program,
+    )]
+    pub protocol_fee_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Checked against profile.creator before any referral fee is paid
+    pub referrer: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_y,
+        associated_token::authority = referrer,
+        associated_token::token_program = token_program,
+    )]
+    pub referrer_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
     #[account(mut)]
-    pub referrer_ata: Option<UncheckedAccount<'info>>,
+    pub integrator_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"creator_fee", pool.key().as_ref()],
+        bump = pool.creator_fee_vault_bump,
+    )]
+    pub creator_fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_stats", pool.key().as_ref()],
+        bump = pool_stats.stats_bump,
+    )]
+    pub pool_stats: Box<Account<'info, PoolStats>>,
+
+    /// CHECK: Owns the protocol's LP tokens, only needed while protocol fees are compounded
+    #[account(
+        seeds = [b"treasury"],
+        bump = config.treasury_bump,
+    )]
+    pub treasury: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_lp,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub treasury_lp_ata: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    // The pool's TWAMM, executed up to now before the swap while it has orders
+    #[account(
+        mut,
+        seeds = [b"twamm", pool.key().as_ref()],
+        bump = twamm.twamm_bump,
+    )]
+    pub twamm: Option<Box<Account<'info, Twamm>>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_x.key().as_ref()],
+        bump = pool.twamm_vault_x_bump,
+    )]
+    pub twamm_vault_x: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_y.key().as_ref()],
+        bump = pool.twamm_vault_y_bump,
+    )]
+    pub twamm_vault_y: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -80,46 +171,169 @@ pub struct SwapTokens<'info> {
 }
 
 impl SwapTokens<'_> {
-    pub fn swap_exact_out(&self, max_amount_in:u64, amount_out:u64, program_id: &Pubkey, referrer: Option<Pubkey>) -> Result<()> {
-        require!(!self.pool.locked, ErrorCode::AMMLocked);
+    pub fn swap_exact_out(&mut self, max_amount_in:u64, amount_out:u64, integrator_fee: u16, deadline: Option<i64>) -> Result<()> {
         require!(amount_out > 0, ErrorCode::InvalidAmount);
 
-        let mut curve = ConstantProduct::init(self.pool_vault_x_ata.amount, self.pool_vault_y_ata.amount, self.mint_lp.supply, self.config.fee,None ).unwrap();
+        self.swap(false, max_amount_in, amount_out, integrator_fee, deadline)
+    }
 
-        let swap_result = curve.swap(LiquidityPair::Y, max_amount_in, amount_out).map_err(ErrorCode::from)?;
+    pub fn swap_exact_in(&mut self, amount_in: u64, min_amount_out: u64, integrator_fee: u16, deadline: Option<i64>)-> Result<()> {
+        require!(min_amount_out > 0, ErrorCode::InvalidAmount);
+
+        self.swap(true, amount_in, min_amount_out, integrator_fee, deadline)
+    }
 
-        require!(swap_result.withdraw >= amount_out, ErrorCode::SlippageExceeded);
+    fn swap(
+        &mut self,
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        integrator_fee: u16,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        require!(!self.pool.locked, ErrorCode::AMMLocked);
+        check_deadline(deadline)?;
+        let now = Clock::get()?.unix_timestamp;
+        self.validate_referral()?;
+        self.validate_integrator(is_x, integrator_fee)?;
+        self.execute_twamm()?;
+
+        // The integrator's cut comes off the top, so the curve and the slippage check only see the rest
+        let quote = self.snapshot(now).quote_swap(
+            is_x,
+            amount_in,
+            integrator_fee,
+            self.referrer_ata.is_some(),
+        )?;
+
+        require!(quote.amount_out >= min_amount_out, ErrorCode::SlippageExceeded);
+        require!(
+            quote.price_impact_bps <= self.pool.max_price_impact_bps,
+            ErrorCode::PriceImpactTooHigh
+        );
+
+        self.deposit_from_user_to_pool(is_x, quote.amount_in - quote.integrator_fee)?;
+        self.withdraw_from_pool_to_user(!is_x, quote.amount_out)?;
+
+        if quote.integrator_fee > 0 {
+            self.charge_integrator_fee(is_x, quote.integrator_fee)?;
+        }
 
-        self.deposit_from_user_to_pool(false, swap_result.deposit)?;
-        self.withdraw_from_pool_to_user(true, swap_result.withdraw)?;
+        let fee_discount = self.charge_fee(now, quote.referrer_fee, quote.protocol_fee)?;
+        let protocol_fee = quote.protocol_fee - fee_discount;
 
-        if swap_result.fee > 0 {
-            self.charge_fee(program_id, swap_result.fee, referrer)?;
+        if quote.creator_fee > 0 {
+            self.charge_creator_fee(quote.creator_fee)?;
         }
 
-        Ok(())
-    }
+        if self.config.compound_protocol_fees && protocol_fee > 0 {
+            self.compound_protocol_fee(protocol_fee)?;
+        }
 
-    pub fn swap_exact_in(&self, amount_in: u64, min_amount_out: u64, program_id: &Pubkey, referrer: Option<Pubkey>)-> Result<()> {
-        require!(!self.pool.locked, ErrorCode::AMMLocked);
-        require!(min_amount_out > 0, ErrorCode::InvalidAmount);
+        self.pool.accumulate_volatility(now, quote.price_move_bps);
+        self.pool_stats.record_swap(now, is_x, &SwapQuote { protocol_fee, ..quote.clone() });
+
+        emit!(SwapEvent {
+            pool: self.pool.key(),
+            user: self.user.key(),
+            mint_in: if is_x { self.mint_x.key() } else { self.mint_y.key() },
+            amount_in,
+            amount_out: quote.amount_out,
+            fee: quote.fee,
+            referrer_fee: quote.referrer_fee,
+            protocol_fee,
+            fee_discount,
+            referrer: self.referrer.as_ref().map(|referrer| referrer.key()),
+            integrator_fee: quote.integrator_fee,
+            integrator: self.integrator_ata.as_ref().map(|integrator_ata| integrator_ata.owner),
+            creator_fee: quote.creator_fee,
+            lp_fee: quote.lp_fee,
+        });
 
-        let mut curve = ConstantProduct::init(self.pool_vault_x_ata.amount, self.pool_vault_y_ata.amount, self.mint_lp.supply, self.config.fee,None ).unwrap();
+        Ok(())
+    }
 
-        let swap_result = curve.swap(LiquidityPair::X, amount_in, min_amount_out).map_err(ErrorCode::from)?;
+    fn execute_twamm(&mut self) -> Result<()> {
+        VirtualOrders {
+            config: &self.config,
+            pool: &self.pool,
+            mint_x: &self.mint_x,
+            mint_y: &self.mint_y,
+            lp_supply: self.mint_lp.supply,
+            pool_vault_x_ata: &mut self.pool_vault_x_ata,
+            pool_vault_y_ata: &mut self.pool_vault_y_ata,
+            twamm: self.twamm.as_deref_mut(),
+            twamm_vault_x: self.twamm_vault_x.as_deref(),
+            twamm_vault_y: self.twamm_vault_y.as_deref(),
+            protocol_fee_ata: Some(&*self.protocol_fee_ata),
+            creator_fee_vault: Some(&*self.creator_fee_vault),
+            pool_stats: Some(&mut *self.pool_stats),
+            token_program: &self.token_program,
+        }
+        .execute()?;
 
-        require!(swap_result.withdraw >= min_amount_out, ErrorCode::SlippageExceeded);
+        Ok(())
+    }
 
-        self.deposit_from_user_to_pool(true, swap_result.deposit)?;
-        self.withdraw_from_pool_to_user(false, swap_result.withdraw)?;
+    fn snapshot(&self, now: i64) -> PoolSnapshot<'_> {
+        PoolSnapshot {
+            config: &self.config,
+            pool: &self.pool,
+            vault_x: self.pool_vault_x_ata.amount,
+            vault_y: self.pool_vault_y_ata.amount,
+            lp_supply: self.mint_lp.supply,
+            now,
+        }
+    }
 
-        if swap_result.fee > 0 {
-            self.charge_fee(program_id,swap_result.fee, referrer)?;
+    // The integrator is paid in the token the user sells
+    fn validate_integrator(&self, is_x: bool, integrator_fee: u16) -> Result<()> {
+        if integrator_fee == 0 {
+            return Ok(());
         }
 
+        let mint_in = if is_x { self.mint_x.key() } else { self.mint_y.key() };
+        let integrator_ata = self
+            .integrator_ata
+            .as_ref()
+            .ok_or(ErrorCode::InvalidIntegratorAta)?;
+        require_keys_eq!(integrator_ata.mint, mint_in, ErrorCode::InvalidIntegratorAta);
+
         Ok(())
     }
 
+    fn charge_integrator_fee(&self, is_x: bool, amount: u64) -> Result<()> {
+        let (from, mint, decimals) = if is_x {
+            (
+                self.user_mint_x_ata.to_account_info(),
+                self.mint_x.to_account_info(),
+                self.mint_x.decimals,
+            )
+        } else {
+            (
+                self.user_mint_y_ata.to_account_info(),
+                self.mint_y.to_account_info(),
+                self.mint_y.decimals,
+            )
+        };
+        // validate_integrator only lets a non-zero fee through when the ATA is present
+        let to = self
+            .integrator_ata
+            .as_ref()
+            .ok_or(ErrorCode::InvalidIntegratorAta)?
+            .to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from,
+            mint,
+            to,
+            authority: self.user.to_account_info(),
+        };
+        let cpi_context = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+        transfer_checked(cpi_context, amount, decimals)
+    }
+
     fn deposit_from_user_to_pool(&self, is_token: bool, amount: u64) -> Result<()> {
         let cpi_program = self.token_program.to_account_info();
         let (from, mint, to, authority, decimals) = if is_token {
@@ -183,17 +397,43 @@ impl SwapTokens<'_> {
         transfer_checked(cpi_context, amount, decimals)
     }
 
-    fn charge_fee(
-        &self,
-        program_id: &Pubkey,
-        total_fee: u64,
-        referrer: Option<Pubkey>,
-    ) -> Result<()> {
+    // A referral needs the referrer's profile, the referrer wallet and its ATA for the fee mint
+    fn validate_referral(&self) -> Result<()> {
+        match (&self.profile, &self.referrer, &self.referrer_ata) {
+            (None, None, None) => Ok(()),
+            (None, _, _) => err!(ErrorCode::MissingReferrerProfile),
+            (Some(profile), Some(referrer), Some(_)) => {
+                require_keys_eq!(referrer.key(), profile.creator, ErrorCode::ReferrerMismatch);
+                // The referral share would otherwise go back to the trader
+                require_keys_neq!(profile.creator, self.user.key(), ErrorCode::SelfReferral);
+
+                let now = Clock::get()?.unix_timestamp;
+                require!(profile.is_active(now), ErrorCode::InvalidReferrerProfile);
+
+                Ok(())
+            }
+            (Some(_), _, _) => err!(ErrorCode::InvalidReferrerAta),
+        }
+    }
+
+    // A trader with an active profile gets the discount of its tier back out of the protocol fee
+    fn fee_discount(&self, now: i64, protocol_fee: u64) -> Result<u64> {
+        match &self.user_profile {
+            Some(profile) if profile.is_active(now) => {
+                self.config.tier_fee_discount(profile.tier, protocol_fee)
+            }
+            _ => Ok(0),
+        }
+    }
+
+    // Pays the referrer, the trader's discount and the protocol, returns the discount.
+    // A compounded protocol fee stays in the Y vault.
+    fn charge_fee(&self, now: i64, referrer_fee: u64, protocol_fee: u64) -> Result<u64> {
         let cpi_program = self.token_program.to_account_info();
-    
+
         let mint_x_bytes = self.mint_x.key().to_bytes();
         let mint_y_bytes = self.mint_y.key().to_bytes();
-    
+
         let seeds = [
             b"pool",
             mint_x_bytes.as_ref(),
@@ -201,59 +441,124 @@ impl SwapTokens<'_> {
             &[self.pool.pool_bump],
         ];
         let signer_seeds = &[&seeds[..]];
-    
+
         let decimals = self.mint_y.decimals;
-    
-        let (ref_fee, protocol_fee) = if referrer.is_some() {
-            (total_fee * 2 / 3, total_fee / 3)
-        } else {
-            (0, total_fee)
-        };
-    
+
         let from = self.pool_vault_y_ata.to_account_info();
         let mint = self.mint_y.to_account_info();
-    
-        if let Some(referrer_key) = referrer {
-            // Derive the profile PDA
-            let (profile_pda, _) = Pubkey::find_program_address(
-                &[b"profile", referrer_key.as_ref()],
-                program_id,
-            );
-    
-            // Check if profile exists and is valid
-            if let Some(profile_account) = self.profile.as_ref() {
-                if profile_account.key() == profile_pda {
-                    // Profile exists and is valid, send referral fee
-                    if let Some(referrer_ata) = self.referrer_ata.as_ref() {
-                        let cpi_accounts = TransferChecked {
-                            from: from.clone(),
-                            mint: mint.clone(),
-                            to: referrer_ata.to_account_info(),
-                            authority: self.pool.to_account_info(),
-                        };
-                        let cpi_context =
-                            CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, signer_seeds);
-                        transfer_checked(cpi_context, ref_fee, decimals)?;
-                    }
-                }
+
+        // validate_referral has already checked that the ATA belongs to the profile creator
+        if let Some(referrer_ata) = self.referrer_ata.as_ref() {
+            if referrer_fee > 0 {
+                let cpi_accounts = TransferChecked {
+                    from: from.clone(),
+                    mint: mint.clone(),
+                    to: referrer_ata.to_account_info(),
+                    authority: self.pool.to_account_info(),
+                };
+                let cpi_context =
+                    CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, signer_seeds);
+                transfer_checked(cpi_context, referrer_fee, decimals)?;
             }
-            // If profile doesn't exist or is invalid, the ref_fee will be added to protocol_fee
         }
-    
-        // Always transfer protocol fee (including any ref_fee if profile was invalid)
-        let protocol_fee = if self.profile.is_none() {
-            total_fee // Send full fee to protocol if no profile
-        } else {
-            protocol_fee
-        };
+
+        let fee_discount = self.fee_discount(now, protocol_fee)?;
+        if fee_discount > 0 {
+            let cpi_accounts = TransferChecked {
+                from: from.clone(),
+                mint: mint.clone(),
+                to: self.user_mint_y_ata.to_account_info(),
+                authority: self.pool.to_account_info(),
+            };
+            let cpi_context =
+                CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, signer_seeds);
+            transfer_checked(cpi_context, fee_discount, decimals)?;
+        }
+
+        let protocol_fee = protocol_fee - fee_discount;
+        if protocol_fee == 0 || self.config.compound_protocol_fees {
+            return Ok(fee_discount);
+        }
 
         let cpi_accounts = TransferChecked {
             from,
             mint,
-            to: self.user_mint_y_ata.to_account_info(),
+            to: self.protocol_fee_ata.to_account_info(),
             authority: self.pool.to_account_info(),
         };
         let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-        transfer_checked(cpi_context, protocol_fee, decimals)
-    }    
+        transfer_checked(cpi_context, protocol_fee, decimals)?;
+
+        Ok(fee_discount)
+    }
+
+    // The protocol fee stays in the Y vault and the treasury gets LP tokens for it
+    fn compound_protocol_fee(&mut self, amount: u64) -> Result<()> {
+        let treasury_lp_ata = match (&self.treasury, &self.treasury_lp_ata) {
+            (Some(_), Some(treasury_lp_ata)) => treasury_lp_ata.to_account_info(),
+            _ => return err!(ErrorCode::InvalidTreasury),
+        };
+
+        self.pool_vault_y_ata.reload()?;
+        let quote = LiquidityQuote::compound(
+            self.pool_vault_y_ata.amount - amount,
+            self.mint_lp.supply,
+            amount,
+        )?;
+        if quote.lp_tokens_amount == 0 {
+            return Ok(());
+        }
+
+        let mint_x_bytes = self.mint_x.key().to_bytes();
+        let mint_y_bytes = self.mint_y.key().to_bytes();
+
+        let seeds = [
+            b"pool",
+            mint_x_bytes.as_ref(),
+            mint_y_bytes.as_ref(),
+            &[self.pool.pool_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = MintTo {
+            mint: self.mint_lp.to_account_info(),
+            to: treasury_lp_ata,
+            authority: self.pool.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        mint_to(cpi_context, quote.lp_tokens_amount)
+    }
+
+    // Creator fees stay with the pool until the creator claims them
+    fn charge_creator_fee(&self, amount: u64) -> Result<()> {
+        let mint_x_bytes = self.mint_x.key().to_bytes();
+        let mint_y_bytes = self.mint_y.key().to_bytes();
+
+        let seeds = [
+            b"pool",
+            mint_x_bytes.as_ref(),
+            mint_y_bytes.as_ref(),
+            &[self.pool.pool_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.pool_vault_y_ata.to_account_info(),
+            mint: self.mint_y.to_account_info(),
+            to: self.creator_fee_vault.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer_checked(cpi_context, amount, self.mint_y.decimals)
+    }
 }
@@ -0,0 +1,360 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::{transfer_checked, TransferChecked},
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::ErrorCode, Governance, Pool, Proposal, ProposalAction, ProtocolConfig, VoteRecord,
+    Voter,
+};
+
+// Setting up token voting over the protocol config, admin only
+#[derive(Accounts)]
+pub struct InitGovernance<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin,
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"governance"],
+        space = Governance::INIT_SPACE,
+        bump,
+    )]
+    pub governance: Account<'info, Governance>,
+
+    pub governance_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"governance_vault"],
+        bump,
+        token::mint = governance_mint,
+        token::authority = governance,
+        token::token_program = token_program,
+    )]
+    pub governance_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl InitGovernance<'_> {
+    pub fn init_governance(
+        &mut self,
+        voting_period: i64,
+        execution_delay: i64,
+        quorum: u64,
+        proposal_threshold: u64,
+        bumps: InitGovernanceBumps,
+    ) -> Result<()> {
+        require!(voting_period > 0, ErrorCode::InvalidConfig);
+        require!(execution_delay >= 0, ErrorCode::InvalidConfig);
+        require!(quorum > 0, ErrorCode::InvalidConfig);
+
+        self.governance.set_inner(Governance {
+            governance_mint: self.governance_mint.key(),
+            voting_period,
+            execution_delay,
+            quorum,
+            proposal_threshold,
+            proposal_count: 0,
+            governance_bump: bumps.governance,
+            vault_bump: bumps.governance_vault,
+        });
+
+        Ok(())
+    }
+}
+
+// Locking governance tokens to vote with, or unlocking them once the voter's votes ended
+#[derive(Accounts)]
+pub struct LockGovernanceTokens<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        has_one = governance_mint,
+        seeds = [b"governance"],
+        bump = governance.governance_bump,
+    )]
+    pub governance: Account<'info, Governance>,
+
+    pub governance_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"governance_vault"],
+        bump = governance.vault_bump,
+    )]
+    pub governance_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [b"voter", owner.key().as_ref()],
+        space = Voter::INIT_SPACE,
+        bump,
+    )]
+    pub voter: Account<'info, Voter>,
+
+    // Checked by the transfer
+    #[account(mut)]
+    pub owner_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl LockGovernanceTokens<'_> {
+    pub fn lock_governance_tokens(
+        &mut self,
+        amount: u64,
+        bumps: LockGovernanceTokensBumps,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        self.voter.owner = self.owner.key();
+        self.voter.voter_bump = bumps.voter;
+        self.voter.locked_amount = self
+            .voter
+            .locked_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.owner_ata.to_account_info(),
+            mint: self.governance_mint.to_account_info(),
+            to: self.governance_vault.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+        let cpi_context = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+        transfer_checked(cpi_context, amount, self.governance_mint.decimals)
+    }
+
+    pub fn unlock_governance_tokens(&mut self, amount: u64) -> Result<()> {
+        require!(
+            amount > 0 && amount <= self.voter.locked_amount,
+            ErrorCode::InvalidAmount
+        );
+        require!(
+            Clock::get()?.unix_timestamp >= self.voter.active_until,
+            ErrorCode::VotesActive
+        );
+
+        self.voter.locked_amount -= amount;
+
+        let seeds = [b"governance".as_ref(), &[self.governance.governance_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.governance_vault.to_account_info(),
+            mint: self.governance_mint.to_account_info(),
+            to: self.owner_ata.to_account_info(),
+            authority: self.governance.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer_checked(cpi_context, amount, self.governance_mint.decimals)
+    }
+}
+
+// Proposing a config change, for voters with enough locked tokens
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"governance"],
+        bump = governance.governance_bump,
+    )]
+    pub governance: Account<'info, Governance>,
+
+    #[account(
+        seeds = [b"voter", proposer.key().as_ref()],
+        bump = voter.voter_bump,
+    )]
+    pub voter: Account<'info, Voter>,
+
+    #[account(
+        init,
+        payer = proposer,
+        seeds = [b"proposal", governance.proposal_count.to_le_bytes().as_ref()],
+        space = Proposal::INIT_SPACE,
+        bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl CreateProposal<'_> {
+    pub fn create_proposal(
+        &mut self,
+        action: ProposalAction,
+        bumps: CreateProposalBumps,
+    ) -> Result<()> {
+        require!(
+            self.voter.locked_amount >= self.governance.proposal_threshold,
+            ErrorCode::ProposalThresholdNotMet
+        );
+        action.validate()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let voting_ends_at = now + self.governance.voting_period;
+        self.proposal.set_inner(Proposal {
+            proposal_id: self.governance.proposal_count,
+            proposer: self.proposer.key(),
+            action,
+            voting_ends_at,
+            executable_at: voting_ends_at + self.governance.execution_delay,
+            votes_for: 0,
+            votes_against: 0,
+            executed: false,
+            proposal_bump: bumps.proposal,
+        });
+        self.governance.proposal_count += 1;
+
+        Ok(())
+    }
+}
+
+// Voting on a proposal with all the voter's locked tokens, once
+#[derive(Accounts)]
+pub struct CastVote<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.proposal_bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"voter", owner.key().as_ref()],
+        bump = voter.voter_bump,
+    )]
+    pub voter: Account<'info, Voter>,
+
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"vote", proposal.key().as_ref(), owner.key().as_ref()],
+        space = VoteRecord::INIT_SPACE,
+        bump,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl CastVote<'_> {
+    pub fn cast_vote(&mut self, support: bool, bumps: CastVoteBumps) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp < self.proposal.voting_ends_at,
+            ErrorCode::VotingClosed
+        );
+        let weight = self.voter.locked_amount;
+        require!(weight > 0, ErrorCode::InvalidAmount);
+
+        let proposal = &mut self.proposal;
+        if support {
+            proposal.votes_for = proposal
+                .votes_for
+                .checked_add(weight)
+                .ok_or(ErrorCode::Overflow)?;
+        } else {
+            proposal.votes_against = proposal
+                .votes_against
+                .checked_add(weight)
+                .ok_or(ErrorCode::Overflow)?;
+        }
+        self.voter.active_until = self.voter.active_until.max(proposal.voting_ends_at);
+
+        self.vote_record.set_inner(VoteRecord {
+            proposal: proposal.key(),
+            owner: self.owner.key(),
+            support,
+            weight,
+            vote_bump: bumps.vote_record,
+        });
+
+        Ok(())
+    }
+}
+
+// Applying a passed proposal once its delay is over, open to anyone
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(
+        seeds = [b"governance"],
+        bump = governance.governance_bump,
+    )]
+    pub governance: Account<'info, Governance>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.proposal_bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    // Only for PoolLocked proposals, checked against the proposal
+    #[account(mut)]
+    pub pool: Option<Account<'info, Pool>>,
+}
+
+impl ExecuteProposal<'_> {
+    pub fn execute_proposal(&mut self) -> Result<()> {
+        let proposal = &self.proposal;
+        require!(!proposal.executed, ErrorCode::ProposalAlreadyExecuted);
+        require!(
+            Clock::get()?.unix_timestamp >= proposal.executable_at,
+            ErrorCode::ProposalTimelocked
+        );
+        require!(
+            proposal.passed(self.governance.quorum),
+            ErrorCode::ProposalNotPassed
+        );
+
+        let pool = match (proposal.action.pool(), self.pool.as_mut()) {
+            (Some(expected), Some(pool)) => {
+                require_keys_eq!(pool.key(), expected, ErrorCode::InvalidProposalPool);
+                Some(&mut **pool)
+            }
+            _ => None,
+        };
+        proposal.action.apply(&mut self.config, pool)?;
+
+        self.proposal.executed = true;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{ProtocolConfig, TokenRegistryEntry, TokenStatus};
+
+// Verifying or blocking a mint, also changes the status of a registered one
+#[derive(Accounts)]
+pub struct RegisterToken<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin,
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        seeds = [b"token", mint.key().as_ref()],
+        bump,
+        space = TokenRegistryEntry::INIT_SPACE
+    )]
+    pub token_registry_entry: Account<'info, TokenRegistryEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl RegisterToken<'_> {
+    pub fn register_token(&mut self, status: TokenStatus, bumps: RegisterTokenBumps) -> Result<()> {
+        self.token_registry_entry.set_inner(TokenRegistryEntry {
+            mint: self.mint.key(),
+            status,
+            token_registry_bump: bumps.token_registry_entry,
+        });
+
+        Ok(())
+    }
+}
+
+// Dropping a mint from the registry, the rent goes back to the admin
+#[derive(Accounts)]
+pub struct UnregisterToken<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin,
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"token", token_registry_entry.mint.as_ref()],
+        bump = token_registry_entry.token_registry_bump,
+    )]
+    pub token_registry_entry: Account<'info, TokenRegistryEntry>,
+}
+
+impl UnregisterToken<'_> {
+    pub fn unregister_token(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
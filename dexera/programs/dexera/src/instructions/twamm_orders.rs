@@ -0,0 +1,697 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::{transfer_checked, TransferChecked},
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::ErrorCode, events::TwammExecutedEvent, Pool, PoolSnapshot, PoolStats, ProtocolConfig,
+    Twamm, TwammOrder, TWAMM_INTERVAL,
+};
+
+// The pool's TWAMM, for the instructions that have to execute its orders before touching
+// the reserves. The TWAMM accounts, and the fee accounts its fills pay into, are only
+// required while the pool has orders.
+pub struct VirtualOrders<'a, 'info> {
+    pub config: &'a ProtocolConfig,
+    pub pool: &'a Account<'info, Pool>,
+    pub mint_x: &'a InterfaceAccount<'info, Mint>,
+    pub mint_y: &'a InterfaceAccount<'info, Mint>,
+    pub lp_supply: u64,
+    pub pool_vault_x_ata: &'a mut InterfaceAccount<'info, TokenAccount>,
+    pub pool_vault_y_ata: &'a mut InterfaceAccount<'info, TokenAccount>,
+    pub twamm: Option<&'a mut Account<'info, Twamm>>,
+    pub twamm_vault_x: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    pub twamm_vault_y: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    pub protocol_fee_ata: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    pub creator_fee_vault: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    pub pool_stats: Option<&'a mut Account<'info, PoolStats>>,
+    pub token_program: &'a Interface<'info, TokenInterface>,
+}
+
+impl<'info> VirtualOrders<'_, 'info> {
+    // Runs the orders up to the current slot and tells whether they sold anything. The pool
+    // vaults only see the net of what the orders traded with each other and with the curve,
+    // and are reloaded after.
+    pub fn execute(self) -> Result<bool> {
+        let (
+            Some(twamm),
+            Some(twamm_vault_x),
+            Some(twamm_vault_y),
+            Some(protocol_fee_ata),
+            Some(creator_fee_vault),
+            Some(pool_stats),
+        ) = (
+            self.twamm,
+            self.twamm_vault_x,
+            self.twamm_vault_y,
+            self.protocol_fee_ata,
+            self.creator_fee_vault,
+            self.pool_stats,
+        )
+        else {
+            if self.pool.twamm_orders == 0 {
+                return Ok(false);
+            }
+            return err!(ErrorCode::MissingTwamm);
+        };
+
+        let clock = Clock::get()?;
+        let execution = twamm.execute(
+            PoolSnapshot {
+                config: self.config,
+                pool: self.pool,
+                vault_x: self.pool_vault_x_ata.amount,
+                vault_y: self.pool_vault_y_ata.amount,
+                lp_supply: self.lp_supply,
+                now: clock.unix_timestamp,
+            },
+            clock.slot,
+        )?;
+        if execution.x_in == 0 && execution.y_in == 0 {
+            return Ok(false);
+        }
+
+        let transfer = |from: &InterfaceAccount<'info, TokenAccount>,
+                        to: &InterfaceAccount<'info, TokenAccount>,
+                        mint: &InterfaceAccount<'info, Mint>,
+                        amount: u64|
+         -> Result<()> {
+            if amount == 0 {
+                return Ok(());
+            }
+
+            let mint_x_bytes = self.mint_x.key().to_bytes();
+            let mint_y_bytes = self.mint_y.key().to_bytes();
+
+            let seeds = [
+                b"pool",
+                mint_x_bytes.as_ref(),
+                mint_y_bytes.as_ref(),
+                &[self.pool.pool_bump],
+            ];
+            let signer_seeds = &[&seeds[..]];
+
+            let cpi_accounts = TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: self.pool.to_account_info(),
+            };
+            let cpi_context = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            );
+
+            transfer_checked(cpi_context, amount, mint.decimals)
+        };
+
+        let (vault_x, vault_y) = (self.pool_vault_x_ata.amount, self.pool_vault_y_ata.amount);
+        if execution.vault_x > vault_x {
+            transfer(
+                twamm_vault_x,
+                self.pool_vault_x_ata,
+                self.mint_x,
+                execution.vault_x - vault_x,
+            )?;
+        } else if execution.vault_x < vault_x {
+            transfer(
+                self.pool_vault_x_ata,
+                twamm_vault_x,
+                self.mint_x,
+                vault_x - execution.vault_x,
+            )?;
+        }
+        if execution.vault_y > vault_y {
+            transfer(
+                twamm_vault_y,
+                self.pool_vault_y_ata,
+                self.mint_y,
+                execution.vault_y - vault_y,
+            )?;
+        } else if execution.vault_y < vault_y {
+            transfer(
+                self.pool_vault_y_ata,
+                twamm_vault_y,
+                self.mint_y,
+                vault_y - execution.vault_y,
+            )?;
+        }
+
+        self.pool_vault_x_ata.reload()?;
+        self.pool_vault_y_ata.reload()?;
+
+        // Fees go where a batch's do, except that the protocol fee is never compounded:
+        // minting the treasury's LP tokens would need the treasury accounts and a mutable
+        // LP mint on every instruction that executes the orders
+        transfer(twamm_vault_y, protocol_fee_ata, self.mint_y, execution.fee)?;
+        transfer(
+            twamm_vault_y,
+            creator_fee_vault,
+            self.mint_y,
+            execution.creator_fee,
+        )?;
+        pool_stats.record_twamm(clock.unix_timestamp, &execution);
+
+        emit!(TwammExecutedEvent {
+            pool: self.pool.key(),
+            slot: twamm.last_executed_slot,
+            x_in: execution.x_in,
+            y_in: execution.y_in,
+            x_out: execution.x_out,
+            y_out: execution.y_out,
+            fee: execution.fee,
+            creator_fee: execution.creator_fee,
+        });
+
+        Ok(true)
+    }
+}
+
+// Executing a pool's TWAMM orders up to now, open to anyone
+#[derive(Accounts)]
+pub struct ExecuteTwamm<'info> {
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.mint_lp_bump,
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+        ],
+        bump = pool.pool_bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm", pool.key().as_ref()],
+        bump = twamm.twamm_bump,
+    )]
+    pub twamm: Box<Account<'info, Twamm>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_x.key().as_ref()],
+        bump = pool.twamm_vault_x_bump,
+    )]
+    pub twamm_vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_y.key().as_ref()],
+        bump = pool.twamm_vault_y_bump,
+    )]
+    pub twamm_vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Paid the fees of the TWAMM's fills
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config.protocol_fee_account,
+        associated_token::token_program = token_program,
+    )]
+    pub protocol_fee_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"creator_fee", pool.key().as_ref()],
+        bump = pool.creator_fee_vault_bump,
+    )]
+    pub creator_fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_stats", pool.key().as_ref()],
+        bump = pool_stats.stats_bump,
+    )]
+    pub pool_stats: Box<Account<'info, PoolStats>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_vault_x_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_vault_y_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl ExecuteTwamm<'_> {
+    pub fn execute_twamm(&mut self) -> Result<()> {
+        VirtualOrders {
+            config: &self.config,
+            pool: &self.pool,
+            mint_x: &self.mint_x,
+            mint_y: &self.mint_y,
+            lp_supply: self.mint_lp.supply,
+            pool_vault_x_ata: &mut self.pool_vault_x_ata,
+            pool_vault_y_ata: &mut self.pool_vault_y_ata,
+            twamm: Some(&mut *self.twamm),
+            twamm_vault_x: Some(&*self.twamm_vault_x),
+            twamm_vault_y: Some(&*self.twamm_vault_y),
+            protocol_fee_ata: Some(&*self.protocol_fee_ata),
+            creator_fee_vault: Some(&*self.creator_fee_vault),
+            pool_stats: Some(&mut *self.pool_stats),
+            token_program: &self.token_program,
+        }
+        .execute()?;
+
+        Ok(())
+    }
+}
+
+// Depositing a long-term order that sells at a constant rate until the end of an interval,
+// setting up the pool's TWAMM with the first one
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct PlaceTwammOrder<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.mint_lp_bump,
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+        ],
+        bump = pool.pool_bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"twamm", pool.key().as_ref()],
+        space = Twamm::INIT_SPACE,
+        bump,
+    )]
+    pub twamm: Box<Account<'info, Twamm>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_x.key().as_ref()],
+        bump,
+        token::mint = mint_x,
+        token::authority = pool,
+        token::token_program = token_program,
+    )]
+    pub twamm_vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_y.key().as_ref()],
+        bump,
+        token::mint = mint_y,
+        token::authority = pool,
+        token::token_program = token_program,
+    )]
+    pub twamm_vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Paid the fees of the TWAMM's fills
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config.protocol_fee_account,
+        associated_token::token_program = token_program,
+    )]
+    pub protocol_fee_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"creator_fee", pool.key().as_ref()],
+        bump = pool.creator_fee_vault_bump,
+    )]
+    pub creator_fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_stats", pool.key().as_ref()],
+        bump = pool_stats.stats_bump,
+    )]
+    pub pool_stats: Box<Account<'info, PoolStats>>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [
+            b"twamm_order",
+            pool.key().as_ref(),
+            user.key().as_ref(),
+            order_id.to_le_bytes().as_ref(),
+        ],
+        space = TwammOrder::INIT_SPACE,
+        bump,
+    )]
+    pub twamm_order: Box<Account<'info, TwammOrder>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_vault_x_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_vault_y_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // In the mint sold, checked by the transfer
+    #[account(mut)]
+    pub user_ata_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl PlaceTwammOrder<'_> {
+    pub fn place_twamm_order(
+        &mut self,
+        order_id: u64,
+        is_x: bool,
+        sell_rate: u64,
+        intervals: u64,
+        bumps: PlaceTwammOrderBumps,
+    ) -> Result<()> {
+        require!(!self.pool.locked, ErrorCode::AMMLocked);
+        require!(sell_rate > 0, ErrorCode::InvalidAmount);
+        require!(
+            self.pool_vault_x_ata.amount > 0 && self.pool_vault_y_ata.amount > 0,
+            ErrorCode::ZeroLiquidity
+        );
+
+        let slot = Clock::get()?.slot;
+        if self.twamm.pool == Pubkey::default() {
+            self.twamm
+                .set_inner(Twamm::new(self.pool.key(), bumps.twamm, slot));
+            self.pool.twamm_vault_x_bump = bumps.twamm_vault_x;
+            self.pool.twamm_vault_y_bump = bumps.twamm_vault_y;
+        }
+
+        // Orders already running sell at the old rates up to now
+        VirtualOrders {
+            config: &self.config,
+            pool: &self.pool,
+            mint_x: &self.mint_x,
+            mint_y: &self.mint_y,
+            lp_supply: self.mint_lp.supply,
+            pool_vault_x_ata: &mut self.pool_vault_x_ata,
+            pool_vault_y_ata: &mut self.pool_vault_y_ata,
+            twamm: Some(&mut *self.twamm),
+            twamm_vault_x: Some(&*self.twamm_vault_x),
+            twamm_vault_y: Some(&*self.twamm_vault_y),
+            protocol_fee_ata: Some(&*self.protocol_fee_ata),
+            creator_fee_vault: Some(&*self.creator_fee_vault),
+            pool_stats: Some(&mut *self.pool_stats),
+            token_program: &self.token_program,
+        }
+        .execute()?;
+
+        let end_interval = (slot / TWAMM_INTERVAL)
+            .checked_add(intervals)
+            .ok_or(ErrorCode::InvalidTwammDuration)?;
+        let amount = self.twamm.add_order(is_x, sell_rate, end_interval)?;
+        self.pool.twamm_orders += 1;
+
+        self.twamm_order.set_inner(TwammOrder {
+            pool: self.pool.key(),
+            owner: self.user.key(),
+            order_id,
+            is_x,
+            sell_rate,
+            start_slot: self.twamm.last_executed_slot,
+            end_slot: end_interval * TWAMM_INTERVAL,
+            earnings_per_rate: if is_x {
+                self.twamm.earnings_per_rate_x
+            } else {
+                self.twamm.earnings_per_rate_y
+            },
+            twamm_order_bump: bumps.twamm_order,
+        });
+
+        let (mint, to) = if is_x {
+            (&self.mint_x, &self.twamm_vault_x)
+        } else {
+            (&self.mint_y, &self.twamm_vault_y)
+        };
+        let cpi_accounts = TransferChecked {
+            from: self.user_ata_in.to_account_info(),
+            mint: mint.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+        let cpi_context = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+        transfer_checked(cpi_context, amount, mint.decimals)
+    }
+}
+
+// Paying out an order's proceeds so far, and its unsold tokens when cancelled.
+// The order is closed once cancelled or ended.
+#[derive(Accounts)]
+pub struct ClaimTwammOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"lp", pool.key().as_ref()],
+        bump = pool.mint_lp_bump,
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+        ],
+        bump = pool.pool_bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm", pool.key().as_ref()],
+        bump = twamm.twamm_bump,
+    )]
+    pub twamm: Box<Account<'info, Twamm>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_x.key().as_ref()],
+        bump = pool.twamm_vault_x_bump,
+    )]
+    pub twamm_vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_y.key().as_ref()],
+        bump = pool.twamm_vault_y_bump,
+    )]
+    pub twamm_vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Paid the fees of the TWAMM's fills
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config.protocol_fee_account,
+        associated_token::token_program = token_program,
+    )]
+    pub protocol_fee_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"creator_fee", pool.key().as_ref()],
+        bump = pool.creator_fee_vault_bump,
+    )]
+    pub creator_fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_stats", pool.key().as_ref()],
+        bump = pool_stats.stats_bump,
+    )]
+    pub pool_stats: Box<Account<'info, PoolStats>>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [
+            b"twamm_order",
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            twamm_order.order_id.to_le_bytes().as_ref(),
+        ],
+        bump = twamm_order.twamm_order_bump,
+    )]
+    pub twamm_order: Box<Account<'info, TwammOrder>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_vault_x_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_vault_y_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Checked by the transfers
+    #[account(mut)]
+    pub owner_ata_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub owner_ata_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ClaimTwammOrder<'info> {
+    pub fn withdraw_twamm_proceeds(&mut self) -> Result<()> {
+        self.claim(false)
+    }
+
+    pub fn cancel_twamm_order(&mut self) -> Result<()> {
+        self.claim(true)
+    }
+
+    fn claim(&mut self, cancel: bool) -> Result<()> {
+        VirtualOrders {
+            config: &self.config,
+            pool: &self.pool,
+            mint_x: &self.mint_x,
+            mint_y: &self.mint_y,
+            lp_supply: self.mint_lp.supply,
+            pool_vault_x_ata: &mut self.pool_vault_x_ata,
+            pool_vault_y_ata: &mut self.pool_vault_y_ata,
+            twamm: Some(&mut *self.twamm),
+            twamm_vault_x: Some(&*self.twamm_vault_x),
+            twamm_vault_y: Some(&*self.twamm_vault_y),
+            protocol_fee_ata: Some(&*self.protocol_fee_ata),
+            creator_fee_vault: Some(&*self.creator_fee_vault),
+            pool_stats: Some(&mut *self.pool_stats),
+            token_program: &self.token_program,
+        }
+        .execute()?;
+
+        let is_x = self.twamm_order.is_x;
+        let proceeds = self.twamm.proceeds(&self.twamm_order)?;
+        let unsold = if cancel {
+            self.twamm.unsold(&self.twamm_order)
+        } else {
+            0
+        };
+        let close = cancel || self.twamm.is_expired(&self.twamm_order);
+        self.twamm_order.earnings_per_rate = self.twamm.earnings_per_rate(&self.twamm_order);
+
+        // Rounding dust stays in the TWAMM vaults
+        self.pay(!is_x, proceeds)?;
+        self.pay(is_x, unsold)?;
+
+        if close {
+            self.twamm.remove_order(&self.twamm_order);
+            self.pool.twamm_orders -= 1;
+            self.twamm_order.close(self.owner.to_account_info())?;
+        }
+
+        Ok(())
+    }
+
+    fn pay(&self, is_x: bool, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let (from, mint, to) = if is_x {
+            (&self.twamm_vault_x, &self.mint_x, &self.owner_ata_x)
+        } else {
+            (&self.twamm_vault_y, &self.mint_y, &self.owner_ata_y)
+        };
+
+        let mint_x_bytes = self.mint_x.key().to_bytes();
+        let mint_y_bytes = self.mint_y.key().to_bytes();
+
+        let seeds = [
+            b"pool",
+            mint_x_bytes.as_ref(),
+            mint_y_bytes.as_ref(),
+            &[self.pool.pool_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: from.to_account_info(),
+            mint: mint.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer_checked(cpi_context, amount, mint.decimals)
+    }
+}
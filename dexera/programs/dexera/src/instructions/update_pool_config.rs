@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{errors::ErrorCode, Pool, ProtocolConfig, BASIS_POINTS};
+
+// Updating per-pool settings, admin only
+#[derive(Accounts)]
+pub struct UpdatePoolConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin,
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub mint_x: InterfaceAccount<'info, Mint>,
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+        ],
+        bump = pool.pool_bump,
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+impl UpdatePoolConfig<'_> {
+    pub fn update_pool_max_price_impact(&mut self, max_price_impact_bps: u16) -> Result<()> {
+        require!(
+            max_price_impact_bps <= BASIS_POINTS,
+            ErrorCode::InvalidConfig
+        );
+
+        self.pool.max_price_impact_bps = max_price_impact_bps;
+
+        Ok(())
+    }
+
+    // max_variable_fee 0 turns the dynamic fee off
+    pub fn update_pool_dynamic_fee(
+        &mut self,
+        max_variable_fee: u16,
+        variable_fee_control: u16,
+        volatility_decay_period: u32,
+    ) -> Result<()> {
+        require!(max_variable_fee < BASIS_POINTS, ErrorCode::InvalidConfig);
+        require!(
+            max_variable_fee == 0 || volatility_decay_period > 0,
+            ErrorCode::InvalidConfig
+        );
+
+        let pool = &mut self.pool;
+        pool.max_variable_fee = max_variable_fee;
+        pool.variable_fee_control = variable_fee_control;
+        pool.volatility_decay_period = volatility_decay_period;
+        // Start from a calm market under the new settings
+        pool.volatility_accumulator = 0;
+        pool.last_volatility_update = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    // batch_duration 0 turns batch auction mode off, open batches can still be settled
+    pub fn update_pool_batch_mode(&mut self, batch_duration: u32) -> Result<()> {
+        self.pool.batch_duration = batch_duration;
+
+        Ok(())
+    }
+
+    pub fn update_pool_verified(&mut self, verified: bool) -> Result<()> {
+        self.pool.verified = verified;
+
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use anchor_lang::prelude::*;
 
-use crate::ProtocolConfig;
+use crate::{errors::ErrorCode, ProtocolConfig, BASIS_POINTS};
 
 // Updating DEX config
 #[derive(Accounts)]
@@ -9,6 +9,7 @@ pub struct UpdateProtocolConfig<'info> {
     pub admin: Signer<'info>,
 
     #[account(
+        mut,
         has_one = admin,
         seeds = [b"config"],
         bump = config.config_bump,
@@ -28,4 +29,71 @@ impl UpdateProtocolConfig<'_> {
 
         Ok(())
     }
+
+    pub fn update_max_integrator_fee(&mut self, max_integrator_fee: u16) -> Result<()> {
+        require!(max_integrator_fee <= BASIS_POINTS, ErrorCode::InvalidConfig);
+
+        self.config.max_integrator_fee = max_integrator_fee;
+
+        Ok(())
+    }
+
+    pub fn update_max_creator_fee(&mut self, max_creator_fee: u16) -> Result<()> {
+        require!(max_creator_fee <= BASIS_POINTS, ErrorCode::InvalidConfig);
+
+        self.config.max_creator_fee = max_creator_fee;
+
+        Ok(())
+    }
+
+    pub fn update_pool_creation_fee(&mut self, pool_creation_fee: u64) -> Result<()> {
+        self.config.pool_creation_fee = pool_creation_fee;
+
+        Ok(())
+    }
+
+    pub fn update_permissioned_pool_creation(&mut self, enabled: bool) -> Result<()> {
+        self.config.permissioned_pool_creation = enabled;
+
+        Ok(())
+    }
+
+    pub fn update_compound_protocol_fees(&mut self, enabled: bool) -> Result<()> {
+        self.config.compound_protocol_fees = enabled;
+        self.config.treasury_bump = Pubkey::find_program_address(&[b"treasury"], &crate::ID).1;
+
+        Ok(())
+    }
+
+    pub fn update_tier_fee_discounts(&mut self, tier_fee_discounts: [u16; 3]) -> Result<()> {
+        require!(
+            tier_fee_discounts
+                .iter()
+                .all(|discount| *discount <= BASIS_POINTS),
+            ErrorCode::InvalidConfig
+        );
+
+        self.config.tier_fee_discounts = tier_fee_discounts;
+
+        Ok(())
+    }
+
+    pub fn update_crank_reward(
+        &mut self,
+        crank_reward_bps: u16,
+        crank_interval: u32,
+        max_crank_reward: u64,
+    ) -> Result<()> {
+        require!(crank_reward_bps <= BASIS_POINTS, ErrorCode::InvalidConfig);
+        require!(
+            crank_reward_bps == 0 || (crank_interval > 0 && max_crank_reward > 0),
+            ErrorCode::InvalidConfig
+        );
+
+        self.config.crank_reward_bps = crank_reward_bps;
+        self.config.crank_interval = crank_interval;
+        self.config.max_crank_reward = max_crank_reward;
+
+        Ok(())
+    }
 }
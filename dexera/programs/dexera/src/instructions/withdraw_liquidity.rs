@@ -4,9 +4,11 @@ use anchor_spl::{
     token::{transfer_checked, TransferChecked},
     token_interface::{burn, Burn, Mint, TokenAccount, TokenInterface},
 };
-use constant_product_curve::ConstantProduct;
 
-use crate::{errors::ErrorCode, Pool};
+use crate::{
+    errors::ErrorCode, utils::check_deadline, LiquidityQuote, Pool, PoolStats, ProtocolConfig,
+    Twamm, VirtualOrders,
+};
 
 #[derive(Accounts)]
 pub struct WithdrawLiquidity<'info> {
@@ -23,11 +25,17 @@ pub struct WithdrawLiquidity<'info> {
             pool.key().as_ref()
         ],
         bump = pool.mint_lp_bump,
-        mint::decimals = 6,
+        mint::decimals = pool.lp_decimals,
         mint::authority = pool,
     )]
     pub mint_lp: InterfaceAccount<'info, Mint>,
 
+    #[account(
+        seeds = [b"config"],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
     #[account(
         mut,
         associated_token::mint = mint_x,
@@ -95,23 +103,73 @@ pub struct WithdrawLiquidity<'info> {
     )]
     pub pool: Account<'info, Pool>,
 
+    // The pool's TWAMM, executed up to now before the withdrawal while it has orders
+    #[account(
+        mut,
+        seeds = [b"twamm", pool.key().as_ref()],
+        bump = twamm.twamm_bump,
+    )]
+    pub twamm: Option<Box<Account<'info, Twamm>>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_x.key().as_ref()],
+        bump = pool.twamm_vault_x_bump,
+    )]
+    pub twamm_vault_x: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"twamm_vault", pool.key().as_ref(), mint_y.key().as_ref()],
+        bump = pool.twamm_vault_y_bump,
+    )]
+    pub twamm_vault_y: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    // Paid the fees of the TWAMM's fills, required along with it
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config.protocol_fee_account,
+        associated_token::token_program = token_program,
+    )]
+    pub protocol_fee_ata: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"creator_fee", pool.key().as_ref()],
+        bump = pool.creator_fee_vault_bump,
+    )]
+    pub creator_fee_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_stats", pool.key().as_ref()],
+        bump = pool_stats.stats_bump,
+    )]
+    pub pool_stats: Option<Box<Account<'info, PoolStats>>>,
+
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 impl WithdrawLiquidity<'_> {
-    pub fn withdraw_liquidity(&self, amount: u64, min_x: u64, min_y: u64) -> Result<()> {
-        require!(amount > 0, ErrorCode::InvalidAmount);
+    pub fn withdraw_liquidity(
+        &mut self,
+        amount: u64,
+        min_x: u64,
+        min_y: u64,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        check_deadline(deadline)?;
+        self.execute_twamm()?;
 
-        let amounts = ConstantProduct::xy_withdraw_amounts_from_l(
+        let amounts = LiquidityQuote::withdraw(
             self.pool_vault_x_ata.amount,
             self.pool_vault_y_ata.amount,
             self.mint_lp.supply,
             amount,
-            6,
-        )
-        .unwrap();
+        )?;
 
         require!(amounts.x >= min_x, ErrorCode::InsufficientTokenX);
         require!(amounts.y >= min_y, ErrorCode::InsufficientTokenY);
@@ -124,6 +182,28 @@ impl WithdrawLiquidity<'_> {
         Ok(())
     }
 
+    fn execute_twamm(&mut self) -> Result<()> {
+        VirtualOrders {
+            config: &self.config,
+            pool: &self.pool,
+            mint_x: &self.mint_x,
+            mint_y: &self.mint_y,
+            lp_supply: self.mint_lp.supply,
+            pool_vault_x_ata: &mut self.pool_vault_x_ata,
+            pool_vault_y_ata: &mut self.pool_vault_y_ata,
+            twamm: self.twamm.as_deref_mut(),
+            twamm_vault_x: self.twamm_vault_x.as_deref(),
+            twamm_vault_y: self.twamm_vault_y.as_deref(),
+            protocol_fee_ata: self.protocol_fee_ata.as_deref(),
+            creator_fee_vault: self.creator_fee_vault.as_deref(),
+            pool_stats: self.pool_stats.as_deref_mut(),
+            token_program: &self.token_program,
+        }
+        .execute()?;
+
+        Ok(())
+    }
+
     fn withdraw_tokens(&self, is_x: bool, amount: u64) -> Result<()> {
         let cpi_program = self.token_program.to_account_info();
         let (from, mint, to, authority, decimals) = match is_x {
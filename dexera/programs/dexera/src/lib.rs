@@ -1,12 +1,16 @@
 #![allow(unexpected_cfgs)]
 pub mod constants;
 pub mod errors;
+pub mod events;
 pub mod instructions;
+pub mod metadata;
 pub mod state;
+pub mod utils;
 
 use anchor_lang::prelude::*;
 
 pub use constants::*;
+pub use events::*;
 pub use instructions::*;
 pub use state::*;
 
@@ -16,8 +20,13 @@ declare_id!("GSfSHoQyvYNvifuotN1qLjin1rggxDgFcGSYW37X4a8A");
 pub mod dexera {
     use super::*;
 
-    pub fn initialize_protocol(ctx: Context<InitializeProtocol>, fee: u16) -> Result<()> {
-        ctx.accounts.initialize_protocol(fee, ctx.bumps)
+    pub fn initialize_protocol(
+        ctx: Context<InitializeProtocol>,
+        fee: u16,
+        max_integrator_fee: u16,
+    ) -> Result<()> {
+        ctx.accounts
+            .initialize_protocol(fee, max_integrator_fee, ctx.bumps)
     }
 
     pub fn update_protocol_fee_account(
@@ -28,42 +37,261 @@ pub mod dexera {
             .update_protocol_fee_account(new_protocol_fee_account)
     }
 
+    pub fn update_max_integrator_fee(
+        ctx: Context<UpdateProtocolConfig>,
+        max_integrator_fee: u16,
+    ) -> Result<()> {
+        ctx.accounts.update_max_integrator_fee(max_integrator_fee)
+    }
+
+    pub fn update_max_creator_fee(
+        ctx: Context<UpdateProtocolConfig>,
+        max_creator_fee: u16,
+    ) -> Result<()> {
+        ctx.accounts.update_max_creator_fee(max_creator_fee)
+    }
+
+    pub fn update_pool_creation_fee(
+        ctx: Context<UpdateProtocolConfig>,
+        pool_creation_fee: u64,
+    ) -> Result<()> {
+        ctx.accounts.update_pool_creation_fee(pool_creation_fee)
+    }
+
+    pub fn update_permissioned_pool_creation(
+        ctx: Context<UpdateProtocolConfig>,
+        enabled: bool,
+    ) -> Result<()> {
+        ctx.accounts.update_permissioned_pool_creation(enabled)
+    }
+
+    pub fn update_compound_protocol_fees(
+        ctx: Context<UpdateProtocolConfig>,
+        enabled: bool,
+    ) -> Result<()> {
+        ctx.accounts.update_compound_protocol_fees(enabled)
+    }
+
+    pub fn update_tier_fee_discounts(
+        ctx: Context<UpdateProtocolConfig>,
+        tier_fee_discounts: [u16; 3],
+    ) -> Result<()> {
+        ctx.accounts.update_tier_fee_discounts(tier_fee_discounts)
+    }
+
+    pub fn update_crank_reward(
+        ctx: Context<UpdateProtocolConfig>,
+        crank_reward_bps: u16,
+        crank_interval: u32,
+        max_crank_reward: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .update_crank_reward(crank_reward_bps, crank_interval, max_crank_reward)
+    }
+
+    pub fn add_to_allowlist(ctx: Context<AddToAllowlist>, key: Pubkey) -> Result<()> {
+        ctx.accounts.add_to_allowlist(key, ctx.bumps)
+    }
+
+    pub fn remove_from_allowlist(ctx: Context<RemoveFromAllowlist>) -> Result<()> {
+        ctx.accounts.remove_from_allowlist()
+    }
+
+    pub fn register_token(ctx: Context<RegisterToken>, status: TokenStatus) -> Result<()> {
+        ctx.accounts.register_token(status, ctx.bumps)
+    }
+
+    pub fn unregister_token(ctx: Context<UnregisterToken>) -> Result<()> {
+        ctx.accounts.unregister_token()
+    }
+
     pub fn create_profile(ctx: Context<CreateProfile>, profile_id: u64) -> Result<()> {
         ctx.accounts.create_profile(profile_id, ctx.bumps)
     }
 
+    pub fn register_profile_id(ctx: Context<RegisterProfileId>) -> Result<()> {
+        ctx.accounts.register_profile_id(ctx.bumps)
+    }
+
+    pub fn update_profile_tier(ctx: Context<UpdateProfileTier>, tier: u8) -> Result<()> {
+        ctx.accounts.update_profile_tier(tier)
+    }
+
     pub fn create_pool(ctx: Context<CreatePool>) -> Result<()> {
         ctx.accounts.create_pool_state(ctx.bumps)
     }
 
+    pub fn update_pool_max_price_impact(
+        ctx: Context<UpdatePoolConfig>,
+        max_price_impact_bps: u16,
+    ) -> Result<()> {
+        ctx.accounts
+            .update_pool_max_price_impact(max_price_impact_bps)
+    }
+
+    pub fn update_pool_dynamic_fee(
+        ctx: Context<UpdatePoolConfig>,
+        max_variable_fee: u16,
+        variable_fee_control: u16,
+        volatility_decay_period: u32,
+    ) -> Result<()> {
+        ctx.accounts.update_pool_dynamic_fee(
+            max_variable_fee,
+            variable_fee_control,
+            volatility_decay_period,
+        )
+    }
+
+    pub fn update_pool_batch_mode(
+        ctx: Context<UpdatePoolConfig>,
+        batch_duration: u32,
+    ) -> Result<()> {
+        ctx.accounts.update_pool_batch_mode(batch_duration)
+    }
+
+    pub fn update_pool_verified(ctx: Context<UpdatePoolConfig>, verified: bool) -> Result<()> {
+        ctx.accounts.update_pool_verified(verified)
+    }
+
+    pub fn update_creator_fee(ctx: Context<UpdatePoolCreator>, creator_fee: u16) -> Result<()> {
+        ctx.accounts.update_creator_fee(creator_fee)
+    }
+
+    pub fn transfer_pool_creator(
+        ctx: Context<UpdatePoolCreator>,
+        new_creator: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.transfer_pool_creator(new_creator)
+    }
+
+    pub fn claim_creator_fee(ctx: Context<ClaimCreatorFee>) -> Result<()> {
+        ctx.accounts.claim_creator_fee()
+    }
+
+    pub fn init_creator_fee_vault(ctx: Context<InitCreatorFeeVault>) -> Result<()> {
+        ctx.accounts.init_creator_fee_vault(ctx.bumps)
+    }
+
     pub fn deposit_liquidity(
         ctx: Context<DepositLiquidity>,
         lp_tokens_amount: u64,
         max_x_tokens: u64,
         max_y_tokens: u64,
+        deadline: Option<i64>,
     ) -> Result<()> {
         ctx.accounts
-            .deposit_liquidity(lp_tokens_amount, max_x_tokens, max_y_tokens)
+            .deposit_liquidity(lp_tokens_amount, max_x_tokens, max_y_tokens, deadline)
     }
 
     pub fn swap_exact_in(
         ctx: Context<SwapTokens>,
         max_amount_in: u64,
         amount_out: u64,
-        referrer: Option<Pubkey>,
+        integrator_fee: u16,
+        deadline: Option<i64>,
     ) -> Result<()> {
         ctx.accounts
-            .swap_exact_in(max_amount_in, amount_out, ctx.program_id, referrer)
+            .swap_exact_in(max_amount_in, amount_out, integrator_fee, deadline)
     }
 
     pub fn swap_exact_out(
         ctx: Context<SwapTokens>,
         amount_in: u64,
         min_amount_out: u64,
-        referrer: Option<Pubkey>,
+        integrator_fee: u16,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        ctx.accounts
+            .swap_exact_out(amount_in, min_amount_out, integrator_fee, deadline)
+    }
+
+    pub fn submit_batch_order(
+        ctx: Context<SubmitBatchOrder>,
+        batch_id: u64,
+        is_x: bool,
+        amount_in: u64,
     ) -> Result<()> {
         ctx.accounts
-            .swap_exact_out(amount_in, min_amount_out, ctx.program_id, referrer)
+            .submit_batch_order(batch_id, is_x, amount_in, ctx.bumps)
+    }
+
+    pub fn settle_batch(ctx: Context<SettleBatch>) -> Result<()> {
+        ctx.accounts.settle_batch()
+    }
+
+    pub fn claim_batch_order(ctx: Context<ClaimBatchOrder>) -> Result<()> {
+        ctx.accounts.claim_batch_order()
+    }
+
+    pub fn place_twamm_order(
+        ctx: Context<PlaceTwammOrder>,
+        order_id: u64,
+        is_x: bool,
+        sell_rate: u64,
+        intervals: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .place_twamm_order(order_id, is_x, sell_rate, intervals, ctx.bumps)
+    }
+
+    pub fn execute_twamm(ctx: Context<ExecuteTwamm>) -> Result<()> {
+        ctx.accounts.execute_twamm()
+    }
+
+    pub fn withdraw_twamm_proceeds(ctx: Context<ClaimTwammOrder>) -> Result<()> {
+        ctx.accounts.withdraw_twamm_proceeds()
+    }
+
+    pub fn cancel_twamm_order(ctx: Context<ClaimTwammOrder>) -> Result<()> {
+        ctx.accounts.cancel_twamm_order()
+    }
+
+    pub fn crank_pool(ctx: Context<CrankPool>) -> Result<()> {
+        ctx.accounts.crank_pool()
+    }
+
+    pub fn wrap_position(ctx: Context<WrapPosition>, lp_amount: u64) -> Result<()> {
+        ctx.accounts.wrap_position(lp_amount, ctx.bumps)
+    }
+
+    pub fn unwrap_position(ctx: Context<UnwrapPosition>) -> Result<()> {
+        ctx.accounts.unwrap_position()
+    }
+
+    pub fn init_governance(
+        ctx: Context<InitGovernance>,
+        voting_period: i64,
+        execution_delay: i64,
+        quorum: u64,
+        proposal_threshold: u64,
+    ) -> Result<()> {
+        ctx.accounts.init_governance(
+            voting_period,
+            execution_delay,
+            quorum,
+            proposal_threshold,
+            ctx.bumps,
+        )
+    }
+
+    pub fn lock_governance_tokens(ctx: Context<LockGovernanceTokens>, amount: u64) -> Result<()> {
+        ctx.accounts.lock_governance_tokens(amount, ctx.bumps)
+    }
+
+    pub fn unlock_governance_tokens(ctx: Context<LockGovernanceTokens>, amount: u64) -> Result<()> {
+        ctx.accounts.unlock_governance_tokens(amount)
+    }
+
+    pub fn create_proposal(ctx: Context<CreateProposal>, action: ProposalAction) -> Result<()> {
+        ctx.accounts.create_proposal(action, ctx.bumps)
+    }
+
+    pub fn cast_vote(ctx: Context<CastVote>, support: bool) -> Result<()> {
+        ctx.accounts.cast_vote(support, ctx.bumps)
+    }
+
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        ctx.accounts.execute_proposal()
     }
 
     pub fn withdraw_liquidity(
@@ -71,7 +299,65 @@ pub mod dexera {
         amount: u64,
         min_x: u64,
         min_y: u64,
+        deadline: Option<i64>,
     ) -> Result<()> {
-        ctx.accounts.withdraw_liquidity(amount, min_x, min_y)
+        ctx.accounts
+            .withdraw_liquidity(amount, min_x, min_y, deadline)
+    }
+
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        ctx.accounts.close_pool()
+    }
+
+    pub fn init_pool_stats(ctx: Context<InitPoolStats>) -> Result<()> {
+        ctx.accounts.init_pool_stats(ctx.bumps)
+    }
+
+    pub fn withdraw_protocol_liquidity(
+        ctx: Context<WithdrawProtocolLiquidity>,
+        lp_tokens_amount: u64,
+        min_x: u64,
+        min_y: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .withdraw_protocol_liquidity(lp_tokens_amount, min_x, min_y)
+    }
+
+    pub fn redirect_protocol_liquidity(
+        ctx: Context<RedirectProtocolLiquidity>,
+        lp_tokens_amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.redirect_protocol_liquidity(lp_tokens_amount)
+    }
+
+    pub fn quote_swap(
+        ctx: Context<Quote>,
+        is_x: bool,
+        amount_in: u64,
+        integrator_fee: u16,
+        referred: bool,
+    ) -> Result<SwapQuote> {
+        ctx.accounts
+            .quote_swap(is_x, amount_in, integrator_fee, referred)
+    }
+
+    pub fn quote_deposit(ctx: Context<Quote>, lp_tokens_amount: u64) -> Result<LiquidityQuote> {
+        ctx.accounts.quote_deposit(lp_tokens_amount)
+    }
+
+    pub fn quote_withdraw(ctx: Context<Quote>, lp_tokens_amount: u64) -> Result<LiquidityQuote> {
+        ctx.accounts.quote_withdraw(lp_tokens_amount)
+    }
+
+    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+        ctx.accounts.migrate_config()
+    }
+
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        ctx.accounts.migrate_pool()
+    }
+
+    pub fn migrate_profile(ctx: Context<MigrateProfile>) -> Result<()> {
+        ctx.accounts.migrate_profile()
     }
 }
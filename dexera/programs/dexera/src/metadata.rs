@@ -0,0 +1,129 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program::invoke_signed,
+    },
+};
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        extension::{BaseStateWithExtensions, StateWithExtensions},
+        state::Mint as MintState,
+    },
+    token_2022_extensions::spl_token_metadata_interface::state::TokenMetadata,
+};
+
+// LP token metadata lives in the Metaplex program. The two layouts used here are built by
+// hand rather than pulling in mpl-token-metadata for them.
+pub const METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+pub const LP_SYMBOL: &str = "DEXERA-LP";
+
+// Metaplex limits, in bytes
+const MAX_NAME_LENGTH: usize = 32;
+const MAX_SYMBOL_LENGTH: usize = 10;
+
+const METADATA_V1_KEY: u8 = 4;
+const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+
+// "DEXERA X-Y LP"
+pub fn lp_name(symbol_x: &str, symbol_y: &str) -> String {
+    let name = format!("DEXERA {}-{} LP", symbol_x, symbol_y);
+    truncate(&name, MAX_NAME_LENGTH).to_string()
+}
+
+// From the Token-2022 metadata extension, then the Metaplex metadata account, falling back
+// to the start of the mint address
+pub fn mint_symbol(mint: &AccountInfo, metaplex_metadata: Option<&AccountInfo>) -> String {
+    let symbol = mint
+        .try_borrow_data()
+        .ok()
+        .and_then(|data| token_2022_symbol(&data))
+        .or_else(|| {
+            metaplex_metadata
+                .filter(|metadata| metadata.owner == &METADATA_PROGRAM_ID)
+                .and_then(|metadata| metaplex_symbol(&metadata.try_borrow_data().ok()?))
+        });
+
+    match symbol {
+        Some(symbol) if !symbol.is_empty() => truncate(&symbol, MAX_SYMBOL_LENGTH).to_string(),
+        _ => mint.key().to_string()[..4].to_string(),
+    }
+}
+
+pub fn token_2022_symbol(mint_data: &[u8]) -> Option<String> {
+    let mint = StateWithExtensions::<MintState>::unpack(mint_data).ok()?;
+    let metadata = mint.get_variable_len_extension::<TokenMetadata>().ok()?;
+
+    Some(metadata.symbol.trim().to_string())
+}
+
+// Metadata accounts start with key, update authority, mint, then name and symbol as
+// borsh strings padded with zeros
+pub fn metaplex_symbol(data: &[u8]) -> Option<String> {
+    if *data.first()? != METADATA_V1_KEY {
+        return None;
+    }
+
+    let mut rest = data.get(1 + 32 + 32..)?;
+    let _name = String::deserialize(&mut rest).ok()?;
+    let symbol = String::deserialize(&mut rest).ok()?;
+
+    Some(symbol.trim_end_matches('\0').trim().to_string())
+}
+
+// Cuts at a char boundary
+fn truncate(value: &str, max_length: usize) -> &str {
+    let mut end = value.len().min(max_length);
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    &value[..end]
+}
+
+// Mutable metadata, with the pool as mint and update authority
+#[allow(clippy::too_many_arguments)]
+pub fn create_lp_metadata<'info>(
+    metadata: &AccountInfo<'info>,
+    mint_lp: &AccountInfo<'info>,
+    pool: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    metadata_program: &AccountInfo<'info>,
+    name: String,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut data = vec![CREATE_METADATA_ACCOUNT_V3];
+    (name, LP_SYMBOL.to_string(), String::new(), 0u16).serialize(&mut data)?;
+    // No creators, collection or uses, mutable, no collection details
+    data.extend_from_slice(&[0, 0, 0, 1, 0]);
+
+    let instruction = Instruction {
+        program_id: METADATA_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(metadata.key(), false),
+            AccountMeta::new_readonly(mint_lp.key(), false),
+            AccountMeta::new_readonly(pool.key(), true),
+            AccountMeta::new(payer.key(), true),
+            AccountMeta::new_readonly(pool.key(), true),
+            AccountMeta::new_readonly(system_program.key(), false),
+            // Optional rent sysvar, left out
+            AccountMeta::new_readonly(METADATA_PROGRAM_ID, false),
+        ],
+        data,
+    };
+
+    invoke_signed(
+        &instruction,
+        &[
+            metadata.clone(),
+            mint_lp.clone(),
+            pool.clone(),
+            payer.clone(),
+            system_program.clone(),
+            metadata_program.clone(),
+        ],
+        signer_seeds,
+    )
+    .map_err(Into::into)
+}
@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+// Grants pool creation while creation is permissioned. The key is either a
+// creator wallet or, for an approved mint pair, the pool address the pair derives to.
+#[account]
+pub struct AllowlistEntry {
+    pub key: Pubkey,
+    pub allowlist_bump: u8,
+}
+
+impl AllowlistEntry {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // key
+        1; // allowlist_bump
+}
@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchStatus {
+    Open,
+    Settled,
+    // The clearing price was past the pool's price impact cap, orders get their tokens back
+    Cancelled,
+}
+
+#[account]
+pub struct Batch {
+    pub pool: Pubkey,
+    pub batch_id: u64,
+    // Paid the rent, refunded once the last order is claimed
+    pub payer: Pubkey,
+    // Orders are taken until this slot, the batch can be settled from it on
+    pub end_slot: u64,
+    pub status: BatchStatus,
+    // Orders not claimed yet
+    pub orders: u32,
+    // Sold by the orders of each side
+    pub x_in: u64,
+    pub y_in: u64,
+    // Shared pro rata by the sellers of the other side once settled, fees excluded
+    pub x_out: u64,
+    pub y_out: u64,
+    pub batch_bump: u8,
+}
+
+impl Batch {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // pool
+        8 + // batch_id
+        32 + // payer
+        8 + // end_slot
+        1 + // status
+        4 + // orders
+        8 + // x_in
+        8 + // y_in
+        8 + // x_out
+        8 + // y_out
+        1; // batch_bump
+}
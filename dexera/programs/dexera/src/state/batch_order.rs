@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+#[account]
+pub struct BatchOrder {
+    pub batch: Pubkey,
+    pub owner: Pubkey,
+    // Sells X for Y when set
+    pub is_x: bool,
+    pub amount_in: u64,
+    pub batch_order_bump: u8,
+}
+
+impl BatchOrder {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // batch
+        32 + // owner
+        1 + // is_x
+        8 + // amount_in
+        1; // batch_order_bump
+}
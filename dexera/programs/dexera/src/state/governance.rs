@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+// Token voting over the protocol config. Holders of governance_mint lock tokens to vote,
+// each locked token is one vote. Seeded by [b"governance"].
+#[account]
+pub struct Governance {
+    pub governance_mint: Pubkey,
+    // Seconds a proposal takes votes for
+    pub voting_period: i64,
+    // Seconds between the end of the vote and when a passed proposal can be executed
+    pub execution_delay: i64,
+    // Votes needed, for and against together, for a proposal to pass
+    pub quorum: u64,
+    // Locked tokens needed to create a proposal
+    pub proposal_threshold: u64,
+    // Proposals created so far, the next one gets this id
+    pub proposal_count: u64,
+    pub governance_bump: u8,
+    // Token account holding the locked tokens
+    pub vault_bump: u8,
+}
+
+impl Governance {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // governance_mint
+        8 + // voting_period
+        8 + // execution_delay
+        8 + // quorum
+        8 + // proposal_threshold
+        8 + // proposal_count
+        1 + // governance_bump
+        1; // vault_bump
+}
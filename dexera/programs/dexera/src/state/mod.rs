@@ -1,8 +1,44 @@
 pub mod profile;
 pub use profile::*;
 
+pub mod profile_id_entry;
+pub use profile_id_entry::*;
+
 pub mod protocol_config;
 pub use protocol_config::*;
 
 pub mod pool;
 pub use pool::*;
+
+pub mod allowlist_entry;
+pub use allowlist_entry::*;
+
+pub mod token_registry_entry;
+pub use token_registry_entry::*;
+
+pub mod batch;
+pub use batch::*;
+
+pub mod batch_order;
+pub use batch_order::*;
+
+pub mod pool_stats;
+pub use pool_stats::*;
+
+pub mod twamm;
+pub use twamm::*;
+
+pub mod governance;
+pub use governance::*;
+
+pub mod voter;
+pub use voter::*;
+
+pub mod proposal;
+pub use proposal::*;
+
+pub mod vote_record;
+pub use vote_record::*;
+
+pub mod position;
+pub use position::*;
@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 
+use crate::BASIS_POINTS;
+
 #[account]
 pub struct Pool {
     pub creator: Pubkey,
@@ -9,6 +11,50 @@ pub struct Pool {
     pub pool_bump: u8,
     pub mint_lp_bump: u8,
     pub locked: bool,
+    // Swaps moving the price further than this in one go are rejected, in basis points
+    pub max_price_impact_bps: u16,
+    // Curated by the admin, set at creation when both mints are verified
+    pub verified: bool,
+    // Set by the creator, capped by ProtocolConfig.max_creator_fee, in basis points
+    pub creator_fee: u16,
+    // Token account in Y where creator fees accrue until claimed
+    pub creator_fee_vault_bump: u8,
+    // Dynamic fee mode, off while max_variable_fee is 0. On top of the base fee, swaps pay
+    // variable_fee_control bps per 10_000 bps of accumulated volatility, up to max_variable_fee
+    pub max_variable_fee: u16,
+    pub variable_fee_control: u16,
+    // Seconds for accumulated volatility to fade out completely
+    pub volatility_decay_period: u32,
+    // Sum of recent spot price moves in basis points, as of last_volatility_update
+    pub volatility_accumulator: u32,
+    pub last_volatility_update: i64,
+    // Layout version, see migrate_pool
+    pub version: u8,
+    // Batch auction mode, off while batch_duration is 0. Swaps are then queued as orders
+    // for batch_duration slots and settled together at one price by settle_batch
+    pub batch_duration: u32,
+    // Latest batch and the slot it stops taking orders at
+    pub batch_id: u64,
+    pub batch_end_slot: u64,
+    // Token accounts holding the orders' tokens until their batch is claimed
+    pub batch_vault_x_bump: u8,
+    pub batch_vault_y_bump: u8,
+    // Batches with orders left to claim, the pool can't be closed before they are
+    pub open_batches: u32,
+    // Decimals of mint_lp, also the precision of the curve maths
+    pub lp_decimals: u8,
+    // Token accounts holding what TWAMM orders haven't sold yet and their proceeds
+    pub twamm_vault_x_bump: u8,
+    pub twamm_vault_y_bump: u8,
+    // TWAMM orders not closed yet. While there are any, whatever trades against or prices
+    // the pool executes them first, and the pool can't be closed
+    pub twamm_orders: u32,
+    // Last time crank_pool paid a keeper reward for the pool
+    pub last_crank_reward_at: i64,
+    // LP token account holding the LP tokens wrapped into position NFTs
+    pub position_vault_bump: u8,
+    // Room for new fields without a realloc
+    pub reserved: [u8; 22],
 }
 
 impl Pool {
@@ -19,5 +65,100 @@ impl Pool {
         32 + // mint_lp
         1 + // pool_bump
         1 + // mint_lp_bump
-        1; // locked
+        1 + // locked
+        2 + // max_price_impact_bps
+        1 + // verified
+        2 + // creator_fee
+        1 + // creator_fee_vault_bump
+        2 + // max_variable_fee
+        2 + // variable_fee_control
+        4 + // volatility_decay_period
+        4 + // volatility_accumulator
+        8 + // last_volatility_update
+        1 + // version
+        4 + // batch_duration
+        8 + // batch_id
+        8 + // batch_end_slot
+        1 + // batch_vault_x_bump
+        1 + // batch_vault_y_bump
+        4 + // open_batches
+        1 + // lp_decimals
+        1 + // twamm_vault_x_bump
+        1 + // twamm_vault_y_bump
+        4 + // twamm_orders
+        8 + // last_crank_reward_at
+        1 + // position_vault_bump
+        22; // reserved
+
+    pub const VERSION: u8 = 2;
+
+    // LP mints were created with 6 decimals before lp_decimals was stored
+    pub const LEGACY_LP_DECIMALS: u8 = 6;
+
+    // The curve keeps its precision as 10^decimals in a u32
+    pub const MAX_LP_DECIMALS: u8 = 9;
+}
+
+impl Pool {
+    // As precise as the more precise mint of the pair, within what the curve supports
+    pub fn lp_decimals_for(decimals_x: u8, decimals_y: u8) -> u8 {
+        decimals_x.max(decimals_y).min(Self::MAX_LP_DECIMALS)
+    }
+
+    // Batch orders placed at `slot` go to, the next one once the latest stopped taking orders
+    pub fn open_batch_id(&self, slot: u64) -> u64 {
+        if slot < self.batch_end_slot {
+            self.batch_id
+        } else {
+            self.batch_id + 1
+        }
+    }
+
+    // Accumulated volatility at `now`, fading out linearly over the decay period
+    pub fn volatility_at(&self, now: i64) -> u32 {
+        let elapsed = now.saturating_sub(self.last_volatility_update).max(0) as u64;
+        let period = self.volatility_decay_period as u64;
+        if elapsed >= period {
+            return 0;
+        }
+
+        (self.volatility_accumulator as u64 * (period - elapsed) / period) as u32
+    }
+
+    // Variable part of the swap fee at `now`, in basis points
+    pub fn variable_fee(&self, now: i64) -> u16 {
+        let fee =
+            self.volatility_at(now) as u64 * self.variable_fee_control as u64 / BASIS_POINTS as u64;
+
+        fee.min(self.max_variable_fee as u64) as u16
+    }
+
+    // Clears the accumulated volatility once it has faded out, without moving
+    // last_volatility_update. Re-anchoring the decay at every call would slow it down.
+    pub fn decay_volatility(&mut self, now: i64) -> bool {
+        if self.volatility_accumulator == 0 || self.volatility_at(now) > 0 {
+            return false;
+        }
+        self.volatility_accumulator = 0;
+
+        true
+    }
+
+    pub fn accumulate_volatility(&mut self, now: i64, price_move_bps: u32) {
+        self.volatility_accumulator = self
+            .volatility_at(now)
+            .saturating_add(price_move_bps)
+            .min(self.max_volatility());
+        self.last_volatility_update = now;
+    }
+
+    // Volatility past the point where the variable fee is maxed out would only slow the decay
+    fn max_volatility(&self) -> u32 {
+        if self.variable_fee_control == 0 {
+            return 0;
+        }
+
+        (self.max_variable_fee as u64 * BASIS_POINTS as u64 / self.variable_fee_control as u64)
+            as u32
+    }
 }
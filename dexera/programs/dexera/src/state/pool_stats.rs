@@ -0,0 +1,199 @@
+use anchor_lang::prelude::*;
+
+use crate::{Batch, BatchSettlement, SwapQuote, TwammExecution, BASIS_POINTS};
+
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+// Trading counters of a pool, updated by swaps, batch settlements and TWAMM executions
+#[account]
+pub struct PoolStats {
+    pub pool: Pubkey,
+    pub stats_bump: u8,
+    // Layout version
+    pub version: u8,
+    // Sold by traders, in the token they sold, integrator fees included
+    pub volume_x: u128,
+    pub volume_y: u128,
+    // Fees by recipient, in Y except the integrator fees which are paid in the token sold
+    pub lp_fees: u128,
+    pub protocol_fees: u128,
+    pub referrer_fees: u128,
+    pub creator_fees: u128,
+    pub integrator_fees_x: u128,
+    pub integrator_fees_y: u128,
+    // Swaps plus orders of settled batches
+    pub swap_count: u64,
+    pub last_trade_timestamp: i64,
+    // Rolling daily bucket: the day of the last trade (unix_timestamp / SECONDS_PER_DAY),
+    // its totals so far and the totals of the day before
+    pub day: i64,
+    pub day_volume_x: u64,
+    pub day_volume_y: u64,
+    pub day_lp_fees: u64,
+    pub previous_day_volume_x: u64,
+    pub previous_day_volume_y: u64,
+    pub previous_day_lp_fees: u64,
+    // Room for new fields without a realloc
+    pub reserved: [u8; 64],
+}
+
+impl PoolStats {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // pool
+        1 + // stats_bump
+        1 + // version
+        16 + // volume_x
+        16 + // volume_y
+        16 + // lp_fees
+        16 + // protocol_fees
+        16 + // referrer_fees
+        16 + // creator_fees
+        16 + // integrator_fees_x
+        16 + // integrator_fees_y
+        8 + // swap_count
+        8 + // last_trade_timestamp
+        8 + // day
+        8 + // day_volume_x
+        8 + // day_volume_y
+        8 + // day_lp_fees
+        8 + // previous_day_volume_x
+        8 + // previous_day_volume_y
+        8 + // previous_day_lp_fees
+        64; // reserved
+
+    pub const VERSION: u8 = 1;
+
+    pub fn new(pool: Pubkey, stats_bump: u8, now: i64) -> Self {
+        Self {
+            pool,
+            stats_bump,
+            version: Self::VERSION,
+            volume_x: 0,
+            volume_y: 0,
+            lp_fees: 0,
+            protocol_fees: 0,
+            referrer_fees: 0,
+            creator_fees: 0,
+            integrator_fees_x: 0,
+            integrator_fees_y: 0,
+            swap_count: 0,
+            last_trade_timestamp: 0,
+            day: now.div_euclid(SECONDS_PER_DAY),
+            day_volume_x: 0,
+            day_volume_y: 0,
+            day_lp_fees: 0,
+            previous_day_volume_x: 0,
+            previous_day_volume_y: 0,
+            previous_day_lp_fees: 0,
+            reserved: [0; 64],
+        }
+    }
+}
+
+// Counters saturate rather than fail a trade
+impl PoolStats {
+    pub fn record_swap(&mut self, now: i64, is_x: bool, quote: &SwapQuote) {
+        let (volume_x, volume_y) = if is_x {
+            (quote.amount_in, 0)
+        } else {
+            (0, quote.amount_in)
+        };
+        self.record_trade(now, 1, volume_x, volume_y, quote.lp_fee);
+
+        self.protocol_fees = self
+            .protocol_fees
+            .saturating_add(quote.protocol_fee as u128);
+        self.referrer_fees = self
+            .referrer_fees
+            .saturating_add(quote.referrer_fee as u128);
+        self.creator_fees = self.creator_fees.saturating_add(quote.creator_fee as u128);
+        if is_x {
+            self.integrator_fees_x = self
+                .integrator_fees_x
+                .saturating_add(quote.integrator_fee as u128);
+        } else {
+            self.integrator_fees_y = self
+                .integrator_fees_y
+                .saturating_add(quote.integrator_fee as u128);
+        }
+    }
+
+    // Batches pay no variable fee and no referral, the whole swap fee goes to the protocol
+    pub fn record_batch(&mut self, now: i64, batch: &Batch, settlement: &BatchSettlement) {
+        self.record_trade(now, batch.orders as u64, batch.x_in, batch.y_in, 0);
+
+        self.protocol_fees = self.protocol_fees.saturating_add(settlement.fee as u128);
+        self.creator_fees = self
+            .creator_fees
+            .saturating_add(settlement.creator_fee as u128);
+    }
+
+    // Like a batch's, except that each execution only fills part of long-running orders
+    // and doesn't add to swap_count
+    pub fn record_twamm(&mut self, now: i64, execution: &TwammExecution) {
+        self.record_trade(now, 0, execution.x_in, execution.y_in, 0);
+
+        self.protocol_fees = self.protocol_fees.saturating_add(execution.fee as u128);
+        self.creator_fees = self
+            .creator_fees
+            .saturating_add(execution.creator_fee as u128);
+    }
+
+    // LP fees of the last complete day as of `now`
+    pub fn last_day_lp_fees(&self, now: i64) -> u64 {
+        match now.div_euclid(SECONDS_PER_DAY) - self.day {
+            0 => self.previous_day_lp_fees,
+            1 => self.day_lp_fees,
+            _ => 0,
+        }
+    }
+
+    // Yearly return of the last complete day's LP fees, in basis points. At the spot
+    // price the pool holds as much value in X as in Y, so it is worth twice vault_y.
+    pub fn lp_fee_apr_bps(&self, now: i64, vault_y: u64) -> u64 {
+        if vault_y == 0 {
+            return 0;
+        }
+
+        let apr =
+            self.last_day_lp_fees(now) as u128 * 365 * BASIS_POINTS as u128 / (2 * vault_y as u128);
+
+        apr.min(u64::MAX as u128) as u64
+    }
+
+    fn record_trade(&mut self, now: i64, trades: u64, volume_x: u64, volume_y: u64, lp_fee: u64) {
+        self.roll_day(now);
+
+        self.volume_x = self.volume_x.saturating_add(volume_x as u128);
+        self.volume_y = self.volume_y.saturating_add(volume_y as u128);
+        self.lp_fees = self.lp_fees.saturating_add(lp_fee as u128);
+        self.swap_count = self.swap_count.saturating_add(trades);
+        self.last_trade_timestamp = now;
+
+        self.day_volume_x = self.day_volume_x.saturating_add(volume_x);
+        self.day_volume_y = self.day_volume_y.saturating_add(volume_y);
+        self.day_lp_fees = self.day_lp_fees.saturating_add(lp_fee);
+    }
+
+    // The current day's totals become the previous day's, or zero after a day without trades
+    pub fn roll_day(&mut self, now: i64) {
+        let day = now.div_euclid(SECONDS_PER_DAY);
+        if day == self.day {
+            return;
+        }
+
+        (
+            self.previous_day_volume_x,
+            self.previous_day_volume_y,
+            self.previous_day_lp_fees,
+        ) = if day == self.day + 1 {
+            (self.day_volume_x, self.day_volume_y, self.day_lp_fees)
+        } else {
+            (0, 0, 0)
+        };
+        self.day = day;
+        self.day_volume_x = 0;
+        self.day_volume_y = 0;
+        self.day_lp_fees = 0;
+    }
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+// LP tokens locked behind a position NFT, returned to whoever holds the NFT.
+// Seeded by [b"position", position_mint].
+#[account]
+pub struct Position {
+    pub pool: Pubkey,
+    // Mint of the NFT, supply 1 and no mint authority left
+    pub position_mint: Pubkey,
+    pub lp_amount: u64,
+    // Unix timestamp the LP tokens were wrapped at
+    pub created_at: i64,
+    pub position_bump: u8,
+}
+
+impl Position {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // pool
+        32 + // position_mint
+        8 + // lp_amount
+        8 + // created_at
+        1; // position_bump
+}
@@ -8,6 +8,12 @@ pub struct Profile {
     pub expiration_timestamp: i64,
     pub locked: bool,
     pub profile_bump: u8,
+    // Layout version, see migrate_profile
+    pub version: u8,
+    // Fee discount tier set by the admin, 0 for none, see ProtocolConfig.tier_fee_discounts
+    pub tier: u8,
+    // Room for new fields without a realloc
+    pub reserved: [u8; 31],
 }
 
 impl Profile {
@@ -17,5 +23,15 @@ impl Profile {
         8 + // created_timestamp
         8 + // expiration_timestamp
         1 + // locked
-        1; // profile_bump
+        1 + // profile_bump
+        1 + // version
+        1 + // tier
+        31; // reserved
+
+    pub const VERSION: u8 = 1;
+    pub const MAX_TIER: u8 = 3;
+
+    pub fn is_active(&self, now: i64) -> bool {
+        !self.locked && self.expiration_timestamp > now
+    }
 }
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+// Reserves a profile_id for one profile, so a referral link can carry the id alone
+// and resolve it to the referrer. Seeded by [b"profile_id", profile_id as little endian].
+#[account]
+pub struct ProfileIdEntry {
+    pub profile_id: u64,
+    pub profile: Pubkey,
+    // The referrer wallet, same as profile.creator
+    pub creator: Pubkey,
+    pub profile_id_bump: u8,
+}
+
+impl ProfileIdEntry {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        8 + // profile_id
+        32 + // profile
+        32 + // creator
+        1; // profile_id_bump
+}
@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::ErrorCode, Pool, ProtocolConfig, BASIS_POINTS};
+
+// Change a proposal makes once executed, checked like the admin's updates
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProposalAction {
+    Fee(u16),
+    MaxIntegratorFee(u16),
+    MaxCreatorFee(u16),
+    PoolCreationFee(u64),
+    PermissionedPoolCreation(bool),
+    CompoundProtocolFees(bool),
+    TierFeeDiscounts([u16; 3]),
+    // Referrer's share of referred swap fees, in basis points
+    ReferrerFeeShare(u16),
+    // Pauses or resumes trading and deposits on one pool
+    PoolLocked { pool: Pubkey, locked: bool },
+}
+
+impl ProposalAction {
+    // Largest variant, PoolLocked
+    pub const INIT_SPACE: usize = 1 + // variant
+        32 + // pool
+        1; // locked
+
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Self::Fee(fee) => require!(*fee < BASIS_POINTS, ErrorCode::InvalidFee),
+            Self::MaxIntegratorFee(fee)
+            | Self::MaxCreatorFee(fee)
+            | Self::ReferrerFeeShare(fee) => {
+                require!(*fee <= BASIS_POINTS, ErrorCode::InvalidConfig)
+            }
+            Self::TierFeeDiscounts(discounts) => require!(
+                discounts.iter().all(|discount| *discount <= BASIS_POINTS),
+                ErrorCode::InvalidConfig
+            ),
+            Self::PoolCreationFee(_)
+            | Self::PermissionedPoolCreation(_)
+            | Self::CompoundProtocolFees(_)
+            | Self::PoolLocked { .. } => {}
+        }
+
+        Ok(())
+    }
+
+    // The pool a PoolLocked proposal needs at execution
+    pub fn pool(&self) -> Option<Pubkey> {
+        match self {
+            Self::PoolLocked { pool, .. } => Some(*pool),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&self, config: &mut ProtocolConfig, pool: Option<&mut Pool>) -> Result<()> {
+        self.validate()?;
+
+        match *self {
+            Self::Fee(fee) => config.fee = fee,
+            Self::MaxIntegratorFee(fee) => config.max_integrator_fee = fee,
+            Self::MaxCreatorFee(fee) => config.max_creator_fee = fee,
+            Self::PoolCreationFee(fee) => config.pool_creation_fee = fee,
+            Self::PermissionedPoolCreation(enabled) => config.permissioned_pool_creation = enabled,
+            Self::CompoundProtocolFees(enabled) => {
+                config.compound_protocol_fees = enabled;
+                config.treasury_bump = Pubkey::find_program_address(&[b"treasury"], &crate::ID).1;
+            }
+            Self::TierFeeDiscounts(discounts) => config.tier_fee_discounts = discounts,
+            Self::ReferrerFeeShare(share) => config.referrer_fee_share = share,
+            Self::PoolLocked { locked, .. } => {
+                pool.ok_or(ErrorCode::InvalidProposalPool)?.locked = locked;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Seeded by [b"proposal", proposal_id as little endian]
+#[account]
+pub struct Proposal {
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub action: ProposalAction,
+    // Votes are taken until this timestamp
+    pub voting_ends_at: i64,
+    // A passed proposal can be executed from this timestamp on
+    pub executable_at: i64,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub executed: bool,
+    pub proposal_bump: u8,
+}
+
+impl Proposal {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        8 + // proposal_id
+        32 + // proposer
+        ProposalAction::INIT_SPACE + // action
+        8 + // voting_ends_at
+        8 + // executable_at
+        8 + // votes_for
+        8 + // votes_against
+        1 + // executed
+        1; // proposal_bump
+
+    // More votes for than against, with enough votes cast
+    pub fn passed(&self, quorum: u64) -> bool {
+        self.votes_for > self.votes_against
+            && self.votes_for as u128 + self.votes_against as u128 >= quorum as u128
+    }
+}
@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 
+use crate::{errors::ErrorCode, utils::bps_of, Pool, BASIS_POINTS};
+
 // Config state for whole DEX
 #[account]
 pub struct ProtocolConfig {
@@ -7,6 +9,32 @@ pub struct ProtocolConfig {
     pub protocol_fee_account: Pubkey,
     pub config_bump: u8,
     pub fee: u16,
+    // Upper bound for the fee frontends may add on top of a swap, in basis points
+    pub max_integrator_fee: u16,
+    // Lamports paid to the protocol fee account by whoever creates a pool
+    pub pool_creation_fee: u64,
+    // When set, only allowlisted creators or mint pairs can create pools
+    pub permissioned_pool_creation: bool,
+    // Upper bound for the fee pool creators may charge on swaps, in basis points
+    pub max_creator_fee: u16,
+    // Layout version, see migrate_config
+    pub version: u8,
+    // When set, the protocol's share of swap fees stays in the pools as liquidity owned
+    // by the treasury PDA instead of being paid out in Y
+    pub compound_protocol_fees: bool,
+    pub treasury_bump: u8,
+    // Share of the protocol fee refunded to traders with a profile of tier 1 to 3, in basis points
+    pub tier_fee_discounts: [u16; 3],
+    // Share of the treasury's LP tokens in a pool paid to whoever cranks it, in basis points,
+    // at most once every crank_interval seconds per pool
+    pub crank_reward_bps: u16,
+    pub crank_interval: u32,
+    // Cap on a single crank reward, in LP tokens
+    pub max_crank_reward: u64,
+    // Share of a referred swap's fee paid to the referrer, in basis points
+    pub referrer_fee_share: u16,
+    // Room for new fields without a realloc
+    pub reserved: [u8; 40],
 }
 
 impl ProtocolConfig {
@@ -14,5 +42,84 @@ impl ProtocolConfig {
         32 + // admin
         32 + // protocol_fee_account
         1 + // config_bump
-        2; // fee
+        2 + // fee
+        2 + // max_integrator_fee
+        8 + // pool_creation_fee
+        1 + // permissioned_pool_creation
+        2 + // max_creator_fee
+        1 + // version
+        1 + // compound_protocol_fees
+        1 + // treasury_bump
+        2 * 3 + // tier_fee_discounts
+        2 + // crank_reward_bps
+        4 + // crank_interval
+        8 + // max_crank_reward
+        2 + // referrer_fee_share
+        40; // reserved
+
+    pub const VERSION: u8 = 2;
+
+    // Two thirds, what referrers got before the split was configurable
+    pub const DEFAULT_REFERRER_FEE_SHARE: u16 = 6_666;
+}
+
+impl ProtocolConfig {
+    // Referrers get referrer_fee_share of the swap fee, the protocol keeps the rest.
+    // The protocol share is the remainder so the two always add up to the total.
+    pub fn split_fee(&self, total_fee: u64, referred: bool) -> (u64, u64) {
+        if referred {
+            let referrer_fee =
+                (total_fee as u128 * self.referrer_fee_share as u128 / BASIS_POINTS as u128) as u64;
+            (referrer_fee, total_fee - referrer_fee)
+        } else {
+            (0, total_fee)
+        }
+    }
+
+    pub fn swap_fee_amount(&self, amount: u64) -> Result<u64> {
+        bps_of(amount, self.fee)
+    }
+
+    pub fn integrator_fee_amount(&self, amount_in: u64, integrator_fee: u16) -> Result<u64> {
+        require!(
+            integrator_fee <= self.max_integrator_fee,
+            ErrorCode::IntegratorFeeTooHigh
+        );
+
+        bps_of(amount_in, integrator_fee)
+    }
+
+    // A pool's creator fee is held to the current cap, in case the admin lowered it since
+    pub fn effective_creator_fee(&self, creator_fee: u16) -> u16 {
+        creator_fee.min(self.max_creator_fee)
+    }
+
+    pub fn creator_fee_amount(&self, amount: u64, creator_fee: u16) -> Result<u64> {
+        bps_of(amount, self.effective_creator_fee(creator_fee))
+    }
+
+    // Part of the protocol fee a trader with a profile of this tier gets back, none for tier 0
+    pub fn tier_fee_discount(&self, tier: u8, protocol_fee: u64) -> Result<u64> {
+        let discount = tier
+            .checked_sub(1)
+            .and_then(|index| self.tier_fee_discounts.get(index as usize))
+            .copied()
+            .unwrap_or(0);
+
+        bps_of(protocol_fee, discount)
+    }
+
+    // LP tokens a crank of the pool at `now` earns out of the treasury's, up to
+    // max_crank_reward and none before crank_interval has passed since the last reward
+    pub fn crank_reward(&self, pool: &Pool, now: i64, treasury_lp_tokens: u64) -> Result<u64> {
+        if now
+            < pool
+                .last_crank_reward_at
+                .saturating_add(self.crank_interval as i64)
+        {
+            return Ok(0);
+        }
+
+        Ok(bps_of(treasury_lp_tokens, self.crank_reward_bps)?.min(self.max_crank_reward))
+    }
 }
@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TokenStatus {
+    // Reviewed by the admin, pools for it skip the mint safety checks
+    Verified,
+    // Pools for it cannot be created
+    Blocked,
+}
+
+// Admin curated status of a mint, one entry per mint
+#[account]
+pub struct TokenRegistryEntry {
+    pub mint: Pubkey,
+    pub status: TokenStatus,
+    pub token_registry_bump: u8,
+}
+
+impl TokenRegistryEntry {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // mint
+        1 + // status
+        1; // token_registry_bump
+
+    // Status of the mint behind a registry address, None while it has no entry
+    pub fn status_of(info: &AccountInfo) -> Result<Option<TokenStatus>> {
+        if info.owner != &crate::ID || info.data_is_empty() {
+            return Ok(None);
+        }
+
+        let data = info.try_borrow_data()?;
+        let entry = TokenRegistryEntry::try_deserialize(&mut &data[..])?;
+
+        Ok(Some(entry.status))
+    }
+}
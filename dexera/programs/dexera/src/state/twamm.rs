@@ -0,0 +1,323 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::ErrorCode, PoolSnapshot};
+
+// TWAMM orders end on multiples of this many slots, about 24 minutes
+pub const TWAMM_INTERVAL: u64 = 3_600;
+// Orders run for at most TWAMM_INTERVALS - 1 intervals, which keeps every expiry in the ring
+pub const TWAMM_INTERVALS: usize = 64;
+
+// Sell rates of the orders ending at one interval, and the earnings per rate when they ended
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TwammExpiry {
+    // The interval these orders end at, slot / TWAMM_INTERVAL
+    pub interval: u64,
+    pub sell_rate_x: u64,
+    pub sell_rate_y: u64,
+    pub earnings_per_rate_x: u128,
+    pub earnings_per_rate_y: u128,
+    // Orders ending here not closed yet, the entry can't be reused before they are
+    pub orders: u32,
+}
+
+impl TwammExpiry {
+    pub const INIT_SPACE: usize = 8 + // interval
+        8 + // sell_rate_x
+        8 + // sell_rate_y
+        16 + // earnings_per_rate_x
+        16 + // earnings_per_rate_y
+        4; // orders
+}
+
+// Long-running orders of a pool, selling at constant rates against the curve. They run
+// lazily: whatever touches the pool first executes them up to the current slot.
+#[account]
+pub struct Twamm {
+    pub pool: Pubkey,
+    pub twamm_bump: u8,
+    // Tokens sold per slot by all running orders
+    pub sell_rate_x: u64,
+    pub sell_rate_y: u64,
+    pub last_executed_slot: u64,
+    // Proceeds of one token per slot of sell rate since the TWAMM started, as Q64.64.
+    // They wrap around: only differences count.
+    pub earnings_per_rate_x: u128,
+    pub earnings_per_rate_y: u128,
+    // Ring indexed by interval % TWAMM_INTERVALS
+    pub expiries: Vec<TwammExpiry>,
+}
+
+impl Twamm {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // pool
+        1 + // twamm_bump
+        8 + // sell_rate_x
+        8 + // sell_rate_y
+        8 + // last_executed_slot
+        16 + // earnings_per_rate_x
+        16 + // earnings_per_rate_y
+        4 + TwammExpiry::INIT_SPACE * TWAMM_INTERVALS; // expiries
+
+    pub fn new(pool: Pubkey, twamm_bump: u8, slot: u64) -> Self {
+        Self {
+            pool,
+            twamm_bump,
+            sell_rate_x: 0,
+            sell_rate_y: 0,
+            last_executed_slot: slot,
+            earnings_per_rate_x: 0,
+            earnings_per_rate_y: 0,
+            expiries: vec![TwammExpiry::default(); TWAMM_INTERVALS],
+        }
+    }
+}
+
+// Result of executing the virtual orders: the pool reserves after them, what they traded
+// and the fees they owe, in Y
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TwammExecution {
+    pub vault_x: u64,
+    pub vault_y: u64,
+    pub x_in: u64,
+    pub y_in: u64,
+    pub x_out: u64,
+    pub y_out: u64,
+    pub fee: u64,
+    pub creator_fee: u64,
+}
+
+impl Twamm {
+    // Runs the orders up to `slot`, settling each stretch of constant sell rates like a batch.
+    // Nothing runs while the pool is locked or empty, the orders keep their tokens until it
+    // can trade again. The swap and creator fees stay in the TWAMM's Y vault, to be paid
+    // out like a batch's.
+    pub fn execute(&mut self, mut pool: PoolSnapshot, slot: u64) -> Result<TwammExecution> {
+        let mut execution = TwammExecution::default();
+
+        while self.last_executed_slot < slot {
+            if self.sell_rate_x == 0 && self.sell_rate_y == 0 {
+                self.last_executed_slot = slot;
+                break;
+            }
+            if pool.pool.locked || pool.vault_x == 0 || pool.vault_y == 0 {
+                break;
+            }
+
+            let (end_slot, expiry) = self.next_expiry(slot);
+            self.trade(
+                &mut pool,
+                end_slot - self.last_executed_slot,
+                &mut execution,
+            )?;
+            self.last_executed_slot = end_slot;
+
+            if let Some(interval) = expiry {
+                let expiry = &mut self.expiries[interval as usize % TWAMM_INTERVALS];
+                self.sell_rate_x -= expiry.sell_rate_x;
+                self.sell_rate_y -= expiry.sell_rate_y;
+                expiry.earnings_per_rate_x = self.earnings_per_rate_x;
+                expiry.earnings_per_rate_y = self.earnings_per_rate_y;
+            }
+        }
+
+        execution.vault_x = pool.vault_x;
+        execution.vault_y = pool.vault_y;
+
+        Ok(execution)
+    }
+
+    // The first interval after the last execution where orders end, or `slot` if none does before
+    fn next_expiry(&self, slot: u64) -> (u64, Option<u64>) {
+        let mut interval = self.last_executed_slot / TWAMM_INTERVAL + 1;
+
+        while interval * TWAMM_INTERVAL <= slot {
+            let expiry = &self.expiries[interval as usize % TWAMM_INTERVALS];
+            if expiry.interval == interval && (expiry.sell_rate_x > 0 || expiry.sell_rate_y > 0) {
+                return (interval * TWAMM_INTERVAL, Some(interval));
+            }
+            interval += 1;
+        }
+
+        (slot, None)
+    }
+
+    fn trade(
+        &mut self,
+        pool: &mut PoolSnapshot,
+        slots: u64,
+        execution: &mut TwammExecution,
+    ) -> Result<()> {
+        let x_in = self
+            .sell_rate_x
+            .checked_mul(slots)
+            .ok_or(ErrorCode::Overflow)?;
+        let y_in = self
+            .sell_rate_y
+            .checked_mul(slots)
+            .ok_or(ErrorCode::Overflow)?;
+        let settlement = pool.settle_batch(x_in, y_in)?;
+
+        if settlement.is_x {
+            pool.vault_x += settlement.pool_amount_in;
+            pool.vault_y -= settlement.pool_amount_out;
+        } else {
+            pool.vault_y += settlement.pool_amount_in;
+            pool.vault_x -= settlement.pool_amount_out;
+        }
+
+        if self.sell_rate_x > 0 {
+            self.earnings_per_rate_x = self
+                .earnings_per_rate_x
+                .wrapping_add(((settlement.y_out as u128) << 64) / self.sell_rate_x as u128);
+        }
+        if self.sell_rate_y > 0 {
+            self.earnings_per_rate_y = self
+                .earnings_per_rate_y
+                .wrapping_add(((settlement.x_out as u128) << 64) / self.sell_rate_y as u128);
+        }
+
+        execution.x_in += x_in;
+        execution.y_in += y_in;
+        execution.x_out += settlement.x_out;
+        execution.y_out += settlement.y_out;
+        execution.fee += settlement.fee;
+        execution.creator_fee += settlement.creator_fee;
+
+        Ok(())
+    }
+}
+
+// Order bookkeeping, always after executing up to the current slot
+impl Twamm {
+    // Starts selling sell_rate per slot from the last execution until end_interval.
+    // Returns the amount the order sells in total.
+    pub fn add_order(&mut self, is_x: bool, sell_rate: u64, end_interval: u64) -> Result<u64> {
+        let current_interval = self.last_executed_slot / TWAMM_INTERVAL;
+        require!(
+            end_interval > current_interval
+                && end_interval - current_interval < TWAMM_INTERVALS as u64,
+            ErrorCode::InvalidTwammDuration
+        );
+
+        let expiry = &mut self.expiries[end_interval as usize % TWAMM_INTERVALS];
+        if expiry.interval != end_interval {
+            require!(expiry.orders == 0, ErrorCode::TwammExpiryBusy);
+            *expiry = TwammExpiry {
+                interval: end_interval,
+                ..TwammExpiry::default()
+            };
+        }
+
+        expiry.orders += 1;
+        if is_x {
+            expiry.sell_rate_x = expiry
+                .sell_rate_x
+                .checked_add(sell_rate)
+                .ok_or(ErrorCode::Overflow)?;
+            self.sell_rate_x = self
+                .sell_rate_x
+                .checked_add(sell_rate)
+                .ok_or(ErrorCode::Overflow)?;
+        } else {
+            expiry.sell_rate_y = expiry
+                .sell_rate_y
+                .checked_add(sell_rate)
+                .ok_or(ErrorCode::Overflow)?;
+            self.sell_rate_y = self
+                .sell_rate_y
+                .checked_add(sell_rate)
+                .ok_or(ErrorCode::Overflow)?;
+        }
+
+        Ok(sell_rate
+            .checked_mul(end_interval * TWAMM_INTERVAL - self.last_executed_slot)
+            .ok_or(ErrorCode::Overflow)?)
+    }
+
+    pub fn is_expired(&self, order: &TwammOrder) -> bool {
+        self.last_executed_slot >= order.end_slot
+    }
+
+    // Earnings per rate the order's proceeds are owed up to, frozen once it ended
+    pub fn earnings_per_rate(&self, order: &TwammOrder) -> u128 {
+        let (earnings_per_rate_x, earnings_per_rate_y) = if self.is_expired(order) {
+            let expiry = &self.expiries[order.end_interval() as usize % TWAMM_INTERVALS];
+            (expiry.earnings_per_rate_x, expiry.earnings_per_rate_y)
+        } else {
+            (self.earnings_per_rate_x, self.earnings_per_rate_y)
+        };
+
+        if order.is_x {
+            earnings_per_rate_x
+        } else {
+            earnings_per_rate_y
+        }
+    }
+
+    // Proceeds in the other token not withdrawn yet, rounded down
+    pub fn proceeds(&self, order: &TwammOrder) -> Result<u64> {
+        let earnings = self
+            .earnings_per_rate(order)
+            .wrapping_sub(order.earnings_per_rate);
+        let high = (earnings >> 64) * order.sell_rate as u128;
+        let low = (earnings as u64 as u128 * order.sell_rate as u128) >> 64;
+
+        Ok(u64::try_from(high + low).map_err(|_| ErrorCode::Overflow)?)
+    }
+
+    // What the order hasn't sold yet
+    pub fn unsold(&self, order: &TwammOrder) -> u64 {
+        order.sell_rate * order.end_slot.saturating_sub(self.last_executed_slot)
+    }
+
+    // Takes a running order's rate out, or releases the expiry of an ended one
+    pub fn remove_order(&mut self, order: &TwammOrder) {
+        let expired = self.is_expired(order);
+        let expiry = &mut self.expiries[order.end_interval() as usize % TWAMM_INTERVALS];
+        expiry.orders -= 1;
+
+        if expired {
+            return;
+        }
+        if order.is_x {
+            expiry.sell_rate_x -= order.sell_rate;
+            self.sell_rate_x -= order.sell_rate;
+        } else {
+            expiry.sell_rate_y -= order.sell_rate;
+            self.sell_rate_y -= order.sell_rate;
+        }
+    }
+}
+
+#[account]
+pub struct TwammOrder {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    // Sells X for Y when set
+    pub is_x: bool,
+    // Tokens sold per slot from start_slot to end_slot
+    pub sell_rate: u64,
+    pub start_slot: u64,
+    pub end_slot: u64,
+    // Twamm earnings per rate when the proceeds were last withdrawn
+    pub earnings_per_rate: u128,
+    pub twamm_order_bump: u8,
+}
+
+impl TwammOrder {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // pool
+        32 + // owner
+        8 + // order_id
+        1 + // is_x
+        8 + // sell_rate
+        8 + // start_slot
+        8 + // end_slot
+        16 + // earnings_per_rate
+        1; // twamm_order_bump
+
+    pub fn end_interval(&self) -> u64 {
+        self.end_slot / TWAMM_INTERVAL
+    }
+}
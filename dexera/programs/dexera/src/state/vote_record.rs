@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+// One voter's vote on one proposal, its existence keeps the voter from voting twice.
+// Seeded by [b"vote", proposal, voter owner].
+#[account]
+pub struct VoteRecord {
+    pub proposal: Pubkey,
+    pub owner: Pubkey,
+    pub support: bool,
+    pub weight: u64,
+    pub vote_bump: u8,
+}
+
+impl VoteRecord {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // proposal
+        32 + // owner
+        1 + // support
+        8 + // weight
+        1; // vote_bump
+}
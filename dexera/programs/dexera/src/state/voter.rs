@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+// Governance tokens a wallet locked to vote with. Seeded by [b"voter", owner].
+#[account]
+pub struct Voter {
+    pub owner: Pubkey,
+    pub locked_amount: u64,
+    // End of the latest vote this voter took part in, tokens can't be unlocked before.
+    // Otherwise they could vote again from another wallet.
+    pub active_until: i64,
+    pub voter_bump: u8,
+}
+
+impl Voter {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // owner
+        8 + // locked_amount
+        8 + // active_until
+        1; // voter_bump
+}
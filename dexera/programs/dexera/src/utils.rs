@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::ErrorCode, BASIS_POINTS};
+
+// Rejects transactions that land after the unix timestamp the user signed for
+pub fn check_deadline(deadline: Option<i64>) -> Result<()> {
+    if let Some(deadline) = deadline {
+        require!(
+            Clock::get()?.unix_timestamp <= deadline,
+            ErrorCode::DeadlineExceeded
+        );
+    }
+
+    Ok(())
+}
+
+// amount * bps / 10_000, rounded down
+pub fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+    let amount = (amount as u128)
+        .checked_mul(bps as u128)
+        .ok_or(ErrorCode::Overflow)?
+        / BASIS_POINTS as u128;
+
+    Ok(amount as u64)
+}
@@ -90,6 +90,16 @@ describe("dexera", () => {
     programId
   );
 
+  const [creatorFeeVault] = PublicKey.findProgramAddressSync(
+    [Buffer.from("creator_fee"), pool.toBuffer()],
+    programId
+  );
+
+  const [poolStats] = PublicKey.findProgramAddressSync(
+    [Buffer.from("pool_stats"), pool.toBuffer()],
+    programId
+  );
+
   const poolAtaLp = getAssociatedTokenAddressSync(
     mintLp,
     pool,
@@ -109,11 +119,24 @@ describe("dexera", () => {
     program.programId
   );
 
+  const [tokenRegistryX, tokenRegistryY] = [mintX, mintY].map(
+    (m) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("token"), m.publicKey.toBuffer()],
+        program.programId
+      )[0]
+  );
+
   const [profile] = PublicKey.findProgramAddressSync(
     [Buffer.from("profile"), profileCreator.publicKey.toBuffer()],
     program.programId
   );
 
+  const [profileIdEntry] = PublicKey.findProgramAddressSync(
+    [Buffer.from("profile_id"), profileId.toArrayLike(Buffer, "le", 8)],
+    program.programId
+  );
+
   const [poolAtaX, poolAtaY] = [mintX, mintY].map((m) =>
     getAssociatedTokenAddressSync(m.publicKey, pool, true, tokenProgram)
   );
@@ -131,6 +154,8 @@ describe("dexera", () => {
     protocolFeeAccount: protocolFeeAccount.publicKey,
     poolAtaLp,
     poolCreatorLpAta,
+    creatorFeeVault,
+    poolStats,
     mintLp,
     poolCreatorAtaY,
     traderAtaY,
@@ -273,7 +298,7 @@ describe("dexera", () => {
   it("Initialize protocol", async () => {
     // Initialize protocol
     await program.methods
-      .initializeProtocol(100)
+      .initializeProtocol(100, 50)
       .accountsStrict({
         admin: dexAdmin.publicKey,
         config,
@@ -311,6 +336,7 @@ describe("dexera", () => {
       .accountsStrict({
         creator: profileCreator.publicKey,
         profile,
+        profileIdEntry,
         systemProgram: SystemProgram.programId,
       })
       .signers([profileCreator])
@@ -334,6 +360,17 @@ describe("dexera", () => {
         creatorMintYAta: accounts.poolCreatorAtaY,
         creatorMintLpAta: accounts.poolCreatorLpAta,
         pool: accounts.pool,
+        creatorFeeVault: accounts.creatorFeeVault,
+        poolStats: accounts.poolStats,
+        config,
+        protocolFeeAccount: protocolFeeAccount.publicKey,
+        allowlistEntry: null,
+        tokenRegistryX,
+        tokenRegistryY,
+        lpMetadata: null,
+        mintXMetadata: null,
+        mintYMetadata: null,
+        metadataProgram: null,
         tokenProgram,
         associatedTokenProgram: associatedTokenProgram,
         systemProgram: SystemProgram.programId,
@@ -389,6 +426,30 @@ describe("dexera", () => {
     return { amountIn, amountInWithSlippage, amountOut };
   });
 
+  it("Quote swap", async () => {
+    const quote = await program.methods
+      .quoteSwap(false, new BN(10000), 0, false)
+      .accountsPartial({
+        mintX: accounts.mintX,
+        mintY: accounts.mintY,
+        mintLp: accounts.mintLp,
+        config: accounts.config,
+        poolVaultXAta: accounts.poolAtaX,
+        poolVaultYAta: accounts.poolAtaY,
+        pool,
+        tokenProgram,
+      })
+      .view();
+
+    console.log("\nQuote for 10000 Y tokens in:");
+    console.log("X tokens out:", quote.amountOut.toString());
+    console.log("Fee:", quote.fee.toString());
+    console.log("Price impact (bps):", quote.priceImpactBps);
+
+    expect(quote.amountOut.toNumber()).greaterThan(0);
+    expect(quote.protocolFee.toNumber()).to.equal(quote.fee.toNumber());
+  });
+
   it("Swap tokens", async () => {
     // Get pool state before swap
     const beforeX = await connection.getTokenAccountBalance(accounts.poolAtaX);
@@ -423,20 +484,37 @@ describe("dexera", () => {
     );
 
     await program.methods
-      .swapExactOut(new BN(maxAmountIn), new BN(amountOut), null)
+      .swapExactOut(new BN(maxAmountIn), new BN(amountOut), 0, null)
       .accountsPartial({
         user: trader.publicKey,
         mintX: accounts.mintX,
         mintY: accounts.mintY,
         mintLp: accounts.mintLp,
-        profile: accounts.profile,
+        profile: null,
+        userProfile: null,
         config: accounts.config,
         poolVaultXAta: accounts.poolAtaX,
         poolVaultYAta: accounts.poolAtaY,
         userMintXAta: traderAtaX,
         userMintYAta: traderAtaY,
         pool,
+        protocolFeeAccount: protocolFeeAccount.publicKey,
+        protocolFeeAta: getAssociatedTokenAddressSync(
+          mintY.publicKey,
+          protocolFeeAccount.publicKey,
+          false,
+          tokenProgram
+        ),
+        referrer: null,
         referrerAta: null,
+        integratorAta: null,
+        creatorFeeVault: accounts.creatorFeeVault,
+        poolStats: accounts.poolStats,
+        treasury: null,
+        treasuryLpAta: null,
+        twamm: null,
+        twammVaultX: null,
+        twammVaultY: null,
         tokenProgram,
         associatedTokenProgram: associatedTokenProgram,
         systemProgram: SystemProgram.programId,
@@ -544,24 +622,31 @@ describe("dexera", () => {
     );
 
     await program.methods
-      .swapExactOut(
-        new BN(maxAmountIn),
-        new BN(amountOut),
-        profileCreator.publicKey
-      ) // Use profileCreator as referrer
+      .swapExactOut(new BN(maxAmountIn), new BN(amountOut), 0, null)
       .accountsPartial({
         user: trader.publicKey,
         mintX: accounts.mintX,
         mintY: accounts.mintY,
         mintLp: accounts.mintLp,
         profile: accounts.profile, // Use existing profile
+        userProfile: null,
         config: accounts.config,
         poolVaultXAta: accounts.poolAtaX,
         poolVaultYAta: accounts.poolAtaY,
         userMintXAta: traderAtaX,
         userMintYAta: traderAtaY,
         pool,
+        protocolFeeAta: protocolFeeAtaY,
+        referrer: profileCreator.publicKey, // Use profileCreator as referrer
         referrerAta: referrerAtaY,
+        integratorAta: null,
+        creatorFeeVault: accounts.creatorFeeVault,
+        poolStats: accounts.poolStats,
+        treasury: null,
+        treasuryLpAta: null,
+        twamm: null,
+        twammVaultX: null,
+        twammVaultY: null,
         tokenProgram,
         associatedTokenProgram: associatedTokenProgram,
         systemProgram: SystemProgram.programId,
@@ -660,7 +745,8 @@ describe("dexera", () => {
       .withdrawLiquidity(
         new BN(withdrawAmount),
         new BN(Math.floor(expectedX * 0.99)), // 1% slippage tolerance
-        new BN(Math.floor(expectedY * 0.99)) // 1% slippage tolerance
+        new BN(Math.floor(expectedY * 0.99)), // 1% slippage tolerance
+        null
       )
       .accountsPartial({
         withdrawer: poolCreator.publicKey,
@@ -674,6 +760,12 @@ describe("dexera", () => {
         withdrawerMintYAta: accounts.poolCreatorAtaY,
         withdrawerMintLpAta: accounts.poolCreatorLpAta,
         pool: accounts.pool,
+        twamm: null,
+        twammVaultX: null,
+        twammVaultY: null,
+        protocolFeeAta: null,
+        creatorFeeVault: null,
+        poolStats: null,
         tokenProgram,
         associatedTokenProgram: associatedTokenProgram,
         systemProgram: SystemProgram.programId,